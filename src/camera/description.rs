@@ -0,0 +1,116 @@
+use std::f32::consts::FRAC_PI_2;
+
+use crate::geometry::Vec3f;
+
+use super::{
+	Camera, EquirectangularCamera, Exposure, FisheyeCamera, Fov, OrthographicCamera,
+	PerspectiveCamera, Projection, Shutter, StereoLayout, StereoRig, ThinLens,
+};
+
+/// Camera settings as a scene describes them, before they are turned into
+/// one camera per view.
+#[derive(Debug, Clone)]
+pub struct CameraDescription {
+	pub projection: Projection,
+	pub position: Vec3f,
+	pub target: Vec3f,
+	pub up: Vec3f,
+	/// Used by the perspective and fisheye projections. Orthographic cameras
+	/// cover the width a perspective one would at the target.
+	pub fov: Fov,
+	/// Only perspective cameras have a lens, pinhole when `None`.
+	pub lens: Option<ThinLens>,
+	pub shutter: Shutter,
+	pub exposure: Exposure,
+	pub stereo: Option<(StereoRig, StereoLayout)>,
+}
+
+impl CameraDescription {
+	pub fn new() -> Self {
+		Self {
+			projection: Projection::Perspective,
+			position: Vec3f::new(0., 0., 0.),
+			target: Vec3f::new(0., 0., 1.),
+			up: Vec3f::new(0., 1., 0.),
+			fov: Fov::Horizontal(FRAC_PI_2),
+			lens: None,
+			shutter: Shutter {
+				open: 0.,
+				close: 0.,
+			},
+			exposure: Exposure::new(),
+			stereo: None,
+		}
+	}
+
+	/// Size of the frame holding every view of `width` by `height` pixels.
+	pub fn frame_size(&self, width: u32, height: u32) -> (u32, u32) {
+		match self.stereo {
+			Some((_, layout)) => layout.frame_size(width, height),
+			None => (width, height),
+		}
+	}
+
+	/// One camera per view, each rendering a `width` by `height` tile of the
+	/// frame at its offset.
+	pub fn views(&self, width: u32, height: u32) -> Vec<(Box<dyn Camera>, (u32, u32))> {
+		let aspect_ratio = width as f32 / height as f32;
+
+		match self.stereo {
+			Some((rig, layout)) => rig
+				.eyes(self.position, self.target, self.up)
+				.iter()
+				.enumerate()
+				.map(|(i, eye)| {
+					(
+						self.camera(eye.position, eye.target, eye.shift, aspect_ratio),
+						layout.offset(i as u32, width, height),
+					)
+				})
+				.collect(),
+			None => vec![(
+				self.camera(self.position, self.target, 0., aspect_ratio),
+				(0, 0),
+			)],
+		}
+	}
+
+	fn camera(
+		&self,
+		position: Vec3f,
+		target: Vec3f,
+		shift: f32,
+		aspect_ratio: f32,
+	) -> Box<dyn Camera> {
+		let up = self.up;
+
+		match self.projection {
+			Projection::Perspective => {
+				let mut camera =
+					PerspectiveCamera::new(position, target, up, self.fov, aspect_ratio);
+				camera.lens = self.lens;
+				camera.shift = shift;
+				Box::new(camera)
+			}
+			Projection::Orthographic => {
+				let fov = self.fov.horizontal(aspect_ratio);
+				let width = 2. * (target - position).len() * (fov / 2.).tan();
+				Box::new(OrthographicCamera::new(
+					position,
+					target,
+					up,
+					width,
+					aspect_ratio,
+				))
+			}
+			Projection::Equirectangular => {
+				Box::new(EquirectangularCamera::new(position, target, up))
+			}
+			Projection::Fisheye => {
+				// The image circle is round, so either axis gives its angle.
+				let (Fov::Horizontal(fov) | Fov::Vertical(fov)) = self.fov;
+				Box::new(FisheyeCamera::new(position, target, up, fov, aspect_ratio))
+			}
+		}
+	}
+}
@@ -0,0 +1,90 @@
+use crate::image::Colour;
+
+/// Multiplier bringing scene radiance into the range tone mapping expects.
+#[derive(Debug, Clone, Copy)]
+pub struct Exposure {
+	scale: f32,
+}
+
+impl Exposure {
+	/// Leaves radiance unchanged.
+	pub fn new() -> Self {
+		Self { scale: 1. }
+	}
+
+	/// Exposure value at ISO 100, for radiance in cd/m². The brightest value
+	/// that does not clip is the saturation-based sensor limit.
+	pub fn from_ev100(ev100: f32) -> Self {
+		Self {
+			scale: 1. / (1.2 * 2f32.powf(ev100)),
+		}
+	}
+
+	/// Exposure for bright sunlight by the sunny 16 rule, which suits the
+	/// physical sky.
+	pub fn daylight() -> Self {
+		Self::from_ev100(15.)
+	}
+
+	/// Exposure of a camera with sensitivity `iso`, the shutter open for
+	/// `shutter` seconds and an aperture of f/`f_number`.
+	pub fn from_camera(iso: f32, shutter: f32, f_number: f32) -> Self {
+		Self::from_ev100((f_number * f_number / shutter * 100. / iso).log2())
+	}
+
+	/// Brightens by `stops` photographic stops, darkens when negative.
+	pub fn compensate(self, stops: f32) -> Self {
+		Self {
+			scale: self.scale * 2f32.powf(stops),
+		}
+	}
+
+	pub fn apply(&self, colour: Colour) -> Colour {
+		colour * self.scale
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::f32::consts::FRAC_PI_2;
+
+	use super::*;
+	use crate::testing::assert_close;
+	use crate::{
+		geometry::Vec3f,
+		light::{Environment, Sky},
+	};
+
+	fn scale(exposure: Exposure) -> f32 {
+		exposure.apply(Colour::from_rgb(1., 1., 1.)).r
+	}
+
+	#[test]
+	fn follows_exposure_values() {
+		assert_eq!(scale(Exposure::new()), 1.);
+		assert_close(scale(Exposure::from_ev100(0.)), 1. / 1.2);
+		assert_close(scale(Exposure::from_ev100(3.)), 1. / 9.6);
+		assert_close(scale(Exposure::from_ev100(3.).compensate(2.)), 4. / 9.6);
+	}
+
+	#[test]
+	fn camera_settings_give_their_exposure_value() {
+		// f/1 for a second at ISO 100 is EV 0, f/4 for 1/8 s at ISO 400
+		// is EV 5.
+		assert_close(
+			scale(Exposure::from_camera(100., 1., 1.)),
+			scale(Exposure::from_ev100(0.)),
+		);
+		assert_close(
+			scale(Exposure::from_camera(400., 0.125, 4.)),
+			scale(Exposure::from_ev100(5.)),
+		);
+	}
+
+	#[test]
+	fn daylight_keeps_the_sky_in_range() {
+		let zenith = Sky::new(FRAC_PI_2, 0., 2.).radiance(Vec3f::new(0., 1., 0.));
+		let exposed = Exposure::daylight().apply(zenith).luminance();
+		assert!(exposed > 0.2 && exposed < 1., "{}", exposed);
+	}
+}
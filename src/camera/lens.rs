@@ -0,0 +1,121 @@
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+/// Shape of the lens opening, which is also the shape of out of focus
+/// highlights.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aperture {
+	Disc,
+	/// Regular polygon with the given number of blades.
+	Polygon(u32),
+}
+
+impl Aperture {
+	/// Maps two uniform numbers to a uniformly distributed point on the unit
+	/// sized aperture.
+	pub fn sample(&self, (u0, u1): (f32, f32)) -> (f32, f32) {
+		match *self {
+			Aperture::Polygon(blades) if blades >= 3 => {
+				let n = blades as f32;
+				let blade = ((u0 * n) as u32).min(blades - 1);
+				let u0 = u0 * n - blade as f32;
+
+				let a0 = 2. * PI * blade as f32 / n + FRAC_PI_2;
+				let a1 = 2. * PI * (blade + 1) as f32 / n + FRAC_PI_2;
+
+				let su = u0.sqrt();
+				let (b1, b2) = (su * (1. - u1), su * u1);
+				(b1 * a0.cos() + b2 * a1.cos(), b1 * a0.sin() + b2 * a1.sin())
+			}
+			_ => {
+				let (x, y) = (2. * u0 - 1., 2. * u1 - 1.);
+				if x == 0. && y == 0. {
+					return (0., 0.);
+				}
+
+				let (r, theta) = if x.abs() > y.abs() {
+					(x, FRAC_PI_4 * (y / x))
+				} else {
+					(y, FRAC_PI_2 - FRAC_PI_4 * (x / y))
+				};
+				(r * theta.cos(), r * theta.sin())
+			}
+		}
+	}
+}
+
+/// Thin lens focusing at `focus_distance` along the view direction.
+#[derive(Debug, Clone, Copy)]
+pub struct ThinLens {
+	pub radius: f32,
+	pub focus_distance: f32,
+	pub aperture: Aperture,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		camera::{Camera, Fov, PerspectiveCamera},
+		geometry::Vec3f,
+	};
+
+	fn grid() -> impl Iterator<Item = (f32, f32)> {
+		(0..400).map(|k| ((k % 20) as f32 / 19., (k / 20) as f32 / 19.))
+	}
+
+	#[test]
+	fn disc_samples_cover_the_unit_disc() {
+		assert_eq!(Aperture::Disc.sample((0.5, 0.5)), (0., 0.));
+		let (x, y) = Aperture::Disc.sample((1., 0.5));
+		assert!((x - 1.).abs() < 1e-6 && y.abs() < 1e-6);
+
+		let (mut sum_x, mut sum_y) = (0., 0.);
+		for u in grid() {
+			let (x, y) = Aperture::Disc.sample(u);
+			assert!(x * x + y * y <= 1. + 1e-5);
+			sum_x += x;
+			sum_y += y;
+		}
+		assert!(sum_x.abs() < 1e-3 && sum_y.abs() < 1e-3);
+	}
+
+	#[test]
+	fn polygon_samples_stay_inside_the_blades() {
+		let blades = 6;
+		let apothem = (PI / blades as f32).cos();
+		for u in grid() {
+			let (x, y) = Aperture::Polygon(blades).sample(u);
+			// Distance to every edge, whose normals sit between the corners.
+			for i in 0..blades {
+				let angle = 2. * PI * (i as f32 + 0.5) / blades as f32 + FRAC_PI_2;
+				assert!(x * angle.cos() + y * angle.sin() <= apothem + 1e-5);
+			}
+		}
+	}
+
+	#[test]
+	fn lens_rays_meet_on_the_focus_plane() {
+		let mut camera = PerspectiveCamera::new(
+			Vec3f::new(0., 0., 0.),
+			Vec3f::new(0., 0., 1.),
+			Vec3f::new(0., 1., 0.),
+			Fov::Horizontal(1.),
+			1.,
+		);
+		camera.lens = Some(ThinLens {
+			radius: 0.5,
+			focus_distance: 4.,
+			aperture: Aperture::Disc,
+		});
+
+		let at_focus = |lens| {
+			let ray = camera.ray((0.3, 0.8), lens).unwrap();
+			ray.origin + ray.direction * ((4. - ray.origin.z) / ray.direction.z)
+		};
+		let centre = at_focus((0.5, 0.5));
+		for lens in [(0., 0.), (1., 0.2), (0.7, 1.)] {
+			assert!((at_focus(lens) - centre).len() < 1e-4);
+			assert!(camera.ray((0.3, 0.8), lens).unwrap().origin != Vec3f::new(0., 0., 0.));
+		}
+	}
+}
@@ -0,0 +1,98 @@
+use std::str::FromStr;
+
+use crate::geometry::{Ray, Vec3f};
+
+mod description;
+pub use description::*;
+
+mod exposure;
+pub use exposure::*;
+
+mod lens;
+pub use lens::*;
+
+mod perspective;
+pub use perspective::*;
+
+mod projections;
+pub use projections::*;
+
+mod stereo;
+pub use stereo::*;
+
+pub trait Camera: Sync + Send {
+	/// Primary ray through film coordinates in `[0, 1]²`, `(0, 0)` being the
+	/// top left corner of the image. `lens` picks the point on the aperture.
+	/// `None` for film points the projection does not cover.
+	fn ray(&self, film: (f32, f32), lens: (f32, f32)) -> Option<Ray>;
+}
+
+/// Interval the shutter stays open for, in scene time. Objects move over
+/// time zero to one.
+#[derive(Debug, Clone, Copy)]
+pub struct Shutter {
+	pub open: f32,
+	pub close: f32,
+}
+
+impl Shutter {
+	pub fn sample(&self, u: f32) -> f32 {
+		self.open + (self.close - self.open) * u
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Projection {
+	Perspective,
+	Orthographic,
+	Equirectangular,
+	Fisheye,
+}
+
+impl FromStr for Projection {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"perspective" => Ok(Projection::Perspective),
+			"orthographic" => Ok(Projection::Orthographic),
+			"equirectangular" => Ok(Projection::Equirectangular),
+			"fisheye" => Ok(Projection::Fisheye),
+			_ => Err(format!("Unknown projection '{}'", s)),
+		}
+	}
+}
+
+/// Right-pointing and down-pointing film axes for a camera looking along
+/// `forward`. Falls back to another up vector when `up` is parallel to
+/// `forward`.
+pub fn film_axes(forward: Vec3f, up: Vec3f) -> (Vec3f, Vec3f) {
+	let mut right = up.cross(forward);
+	if right.len_sq() < 1e-12 {
+		let fallback = if forward.y.abs() < 0.9 {
+			Vec3f::new(0., 1., 0.)
+		} else {
+			Vec3f::new(0., 0., 1.)
+		};
+		right = fallback.cross(forward);
+	}
+	let right = right.unit();
+
+	(right, right.cross(forward))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn shutter_times_span_the_interval() {
+		let shutter = Shutter {
+			open: 0.25,
+			close: 0.75,
+		};
+		assert_eq!(shutter.sample(0.), 0.25);
+		assert_eq!(shutter.sample(0.5), 0.5);
+		assert_eq!(shutter.sample(1.), 0.75);
+	}
+}
@@ -0,0 +1,161 @@
+use crate::geometry::{Ray, Vec3f};
+
+use super::{film_axes, Camera, ThinLens};
+
+/// Field of view across the whole film, in radians.
+#[derive(Debug, Clone, Copy)]
+pub enum Fov {
+	Horizontal(f32),
+	Vertical(f32),
+}
+
+impl Fov {
+	/// Horizontal field of view of a film with `aspect_ratio` width over
+	/// height.
+	pub fn horizontal(self, aspect_ratio: f32) -> f32 {
+		match self {
+			Fov::Horizontal(fov) => fov,
+			Fov::Vertical(fov) => 2. * ((fov / 2.).tan() * aspect_ratio).atan(),
+		}
+	}
+}
+
+pub struct PerspectiveCamera {
+	position: Vec3f,
+	forward: Vec3f,
+	right: Vec3f,
+	down: Vec3f,
+	half_width: f32,
+	half_height: f32,
+	/// Pinhole when `None`.
+	pub lens: Option<ThinLens>,
+	/// Horizontal offset of the film, in view plane units at distance one,
+	/// for off-axis stereo.
+	pub shift: f32,
+}
+
+impl PerspectiveCamera {
+	/// `aspect_ratio` is film width over height.
+	pub fn new(position: Vec3f, target: Vec3f, up: Vec3f, fov: Fov, aspect_ratio: f32) -> Self {
+		let forward = (target - position).unit();
+		let (right, down) = film_axes(forward, up);
+
+		let (half_width, half_height) = match fov {
+			Fov::Horizontal(fov) => {
+				let w = (fov / 2.).tan();
+				(w, w / aspect_ratio)
+			}
+			Fov::Vertical(fov) => {
+				let h = (fov / 2.).tan();
+				(h * aspect_ratio, h)
+			}
+		};
+
+		Self {
+			position,
+			forward,
+			right,
+			down,
+			half_width,
+			half_height,
+			lens: None,
+			shift: 0.,
+		}
+	}
+}
+
+impl Camera for PerspectiveCamera {
+	fn ray(&self, (u, v): (f32, f32), lens: (f32, f32)) -> Option<Ray> {
+		let direction = self.forward
+			+ self.right * ((2. * u - 1.) * self.half_width + self.shift)
+			+ self.down * ((2. * v - 1.) * self.half_height);
+
+		match self.lens {
+			Some(l) if l.radius > 0. => {
+				let focus = self.position + direction * l.focus_distance;
+				let (lx, ly) = l.aperture.sample(lens);
+				let origin =
+					self.position + self.right * (lx * l.radius) + self.down * (ly * l.radius);
+
+				Some(Ray::new(origin, (focus - origin).unit()))
+			}
+			_ => Some(Ray::new(self.position, direction.unit())),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::f32::consts::FRAC_PI_2;
+
+	use super::*;
+
+	fn assert_direction(ray: Ray, expected: Vec3f) {
+		assert!(
+			(ray.direction - expected.unit()).len() < 1e-5,
+			"{:?}",
+			ray.direction
+		);
+	}
+
+	#[test]
+	fn looks_at_the_target() {
+		let position = Vec3f::new(1., 2., 3.);
+		let target = Vec3f::new(4., 2., 7.);
+		let camera = PerspectiveCamera::new(
+			position,
+			target,
+			Vec3f::new(0., 1., 0.),
+			Fov::Vertical(1.),
+			1.5,
+		);
+
+		let ray = camera.ray((0.5, 0.5), (0.5, 0.5)).unwrap();
+		assert_eq!(ray.origin, position);
+		assert_direction(ray, target - position);
+	}
+
+	#[test]
+	fn film_spans_the_field_of_view() {
+		// Looking down `+z` with a 90 degree horizontal field of view.
+		let camera = PerspectiveCamera::new(
+			Vec3f::new(0., 0., 0.),
+			Vec3f::new(0., 0., 1.),
+			Vec3f::new(0., 1., 0.),
+			Fov::Horizontal(FRAC_PI_2),
+			2.,
+		);
+
+		let ray = |u, v| camera.ray((u, v), (0.5, 0.5)).unwrap();
+		assert_direction(ray(0., 0.5), Vec3f::new(-1., 0., 1.));
+		assert_direction(ray(1., 0.5), Vec3f::new(1., 0., 1.));
+		assert_direction(ray(0.5, 0.), Vec3f::new(0., 0.5, 1.));
+		assert_direction(ray(1., 1.), Vec3f::new(1., -0.5, 1.));
+	}
+
+	#[test]
+	fn converts_vertical_fields_of_view() {
+		let horizontal = Fov::Vertical(FRAC_PI_2).horizontal(2.);
+		assert!((horizontal - 2. * 2f32.atan()).abs() < 1e-6);
+		assert_eq!(Fov::Horizontal(1.).horizontal(2.), 1.);
+	}
+
+	#[test]
+	fn looks_straight_down() {
+		let camera = PerspectiveCamera::new(
+			Vec3f::new(0., 5., 0.),
+			Vec3f::new(0., 0., 0.),
+			Vec3f::new(0., 1., 0.),
+			Fov::Horizontal(1.),
+			1.,
+		);
+		let ray = camera.ray((0.5, 0.5), (0.5, 0.5)).unwrap();
+		assert_direction(ray, Vec3f::new(0., -1., 0.));
+		assert!(camera
+			.ray((0., 0.), (0.5, 0.5))
+			.unwrap()
+			.direction
+			.x
+			.is_finite());
+	}
+}
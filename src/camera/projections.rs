@@ -0,0 +1,188 @@
+use std::f32::consts::PI;
+
+use crate::geometry::{Ray, Vec3f};
+
+use super::{film_axes, Camera};
+
+/// Parallel projection, for technical drawings.
+pub struct OrthographicCamera {
+	position: Vec3f,
+	forward: Vec3f,
+	right: Vec3f,
+	down: Vec3f,
+	half_width: f32,
+	half_height: f32,
+}
+
+impl OrthographicCamera {
+	/// `width` is the extent of the film in world units.
+	pub fn new(position: Vec3f, target: Vec3f, up: Vec3f, width: f32, aspect_ratio: f32) -> Self {
+		let forward = (target - position).unit();
+		let (right, down) = film_axes(forward, up);
+
+		Self {
+			position,
+			forward,
+			right,
+			down,
+			half_width: width / 2.,
+			half_height: width / 2. / aspect_ratio,
+		}
+	}
+}
+
+impl Camera for OrthographicCamera {
+	fn ray(&self, (u, v): (f32, f32), _lens: (f32, f32)) -> Option<Ray> {
+		let origin = self.position
+			+ self.right * ((2. * u - 1.) * self.half_width)
+			+ self.down * ((2. * v - 1.) * self.half_height);
+
+		Some(Ray::new(origin, self.forward))
+	}
+}
+
+/// Full 360° by 180° latitude-longitude panorama centred on the view
+/// direction.
+pub struct EquirectangularCamera {
+	position: Vec3f,
+	forward: Vec3f,
+	right: Vec3f,
+	up: Vec3f,
+}
+
+impl EquirectangularCamera {
+	pub fn new(position: Vec3f, target: Vec3f, up: Vec3f) -> Self {
+		let forward = (target - position).unit();
+		let (right, down) = film_axes(forward, up);
+
+		Self {
+			position,
+			forward,
+			right,
+			up: down * -1.,
+		}
+	}
+}
+
+impl Camera for EquirectangularCamera {
+	fn ray(&self, (u, v): (f32, f32), _lens: (f32, f32)) -> Option<Ray> {
+		let phi = (2. * u - 1.) * PI;
+		let theta = (0.5 - v) * PI;
+
+		let direction = (self.forward * phi.cos() + self.right * phi.sin()) * theta.cos()
+			+ self.up * theta.sin();
+
+		Some(Ray::new(self.position, direction))
+	}
+}
+
+/// Equidistant fisheye whose image circle fills the shorter side of the
+/// film, as used for dome projection.
+pub struct FisheyeCamera {
+	position: Vec3f,
+	forward: Vec3f,
+	right: Vec3f,
+	down: Vec3f,
+	/// Angle from the view direction at the edge of the image circle.
+	half_fov: f32,
+	aspect_ratio: f32,
+}
+
+impl FisheyeCamera {
+	pub fn new(position: Vec3f, target: Vec3f, up: Vec3f, fov: f32, aspect_ratio: f32) -> Self {
+		let forward = (target - position).unit();
+		let (right, down) = film_axes(forward, up);
+
+		Self {
+			position,
+			forward,
+			right,
+			down,
+			half_fov: fov / 2.,
+			aspect_ratio,
+		}
+	}
+}
+
+impl Camera for FisheyeCamera {
+	fn ray(&self, (u, v): (f32, f32), _lens: (f32, f32)) -> Option<Ray> {
+		let (mut x, mut y) = (2. * u - 1., 2. * v - 1.);
+		if self.aspect_ratio > 1. {
+			x *= self.aspect_ratio;
+		} else {
+			y /= self.aspect_ratio;
+		}
+
+		let r = (x * x + y * y).sqrt();
+		if r > 1. {
+			return None;
+		}
+
+		let theta = r * self.half_fov;
+		let phi = y.atan2(x);
+		let direction = self.forward * theta.cos()
+			+ (self.right * phi.cos() + self.down * phi.sin()) * theta.sin();
+
+		Some(Ray::new(self.position, direction))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::f32::consts::FRAC_PI_2;
+
+	use super::*;
+	use crate::testing::assert_close;
+
+	fn forward() -> (Vec3f, Vec3f, Vec3f) {
+		(
+			Vec3f::new(0., 0., 0.),
+			Vec3f::new(0., 0., 1.),
+			Vec3f::new(0., 1., 0.),
+		)
+	}
+
+	#[test]
+	fn orthographic_rays_are_parallel() {
+		let (position, target, up) = forward();
+		let camera = OrthographicCamera::new(position, target, up, 4., 2.);
+
+		let top_left = camera.ray((0., 0.), (0.5, 0.5)).unwrap();
+		assert_close(top_left.origin, Vec3f::new(-2., 1., 0.));
+		assert_close(top_left.direction, target);
+
+		let bottom_right = camera.ray((1., 1.), (0.5, 0.5)).unwrap();
+		assert_close(bottom_right.origin, Vec3f::new(2., -1., 0.));
+		assert_close(bottom_right.direction, target);
+	}
+
+	#[test]
+	fn equirectangular_covers_the_sphere() {
+		let (position, target, up) = forward();
+		let camera = EquirectangularCamera::new(position, target, up);
+		let direction = |u, v| camera.ray((u, v), (0.5, 0.5)).unwrap().direction;
+
+		assert_close(direction(0.5, 0.5), Vec3f::new(0., 0., 1.));
+		assert_close(direction(0.75, 0.5), Vec3f::new(1., 0., 0.));
+		assert_close(direction(0., 0.5), Vec3f::new(0., 0., -1.));
+		assert_close(direction(0.5, 0.), Vec3f::new(0., 1., 0.));
+		assert_close(direction(0.3, 1.), Vec3f::new(0., -1., 0.));
+	}
+
+	#[test]
+	fn fisheye_is_equidistant() {
+		let (position, target, up) = forward();
+		let camera = FisheyeCamera::new(position, target, up, PI, 2.);
+		let direction = |u, v| camera.ray((u, v), (0.5, 0.5)).map(|ray| ray.direction);
+
+		assert_close(direction(0.5, 0.5).unwrap(), Vec3f::new(0., 0., 1.));
+		// The circle fills the height, reaching 90 degrees at its edge.
+		assert_close(direction(0.5, 0.).unwrap(), Vec3f::new(0., 1., 0.));
+		assert_close(direction(0.75, 0.5).unwrap(), Vec3f::new(1., 0., 0.));
+		let halfway = direction(0.5, 0.25).unwrap();
+		assert!((halfway.dot(target) - (FRAC_PI_2 / 2.).cos()).abs() < 1e-5);
+
+		assert!(direction(0., 0.5).is_none());
+		assert!(direction(0.9, 0.1).is_none());
+	}
+}
@@ -0,0 +1,126 @@
+use std::str::FromStr;
+
+use crate::geometry::Vec3f;
+
+use super::film_axes;
+
+/// How the two eyes of a stereo render are written out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StereoLayout {
+	/// One image per eye.
+	Separate,
+	/// Left eye on the left half of a double width image.
+	SideBySide,
+	/// Left eye on the top half of a double height image.
+	TopBottom,
+}
+
+impl FromStr for StereoLayout {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"separate" => Ok(StereoLayout::Separate),
+			"side-by-side" => Ok(StereoLayout::SideBySide),
+			"top-bottom" => Ok(StereoLayout::TopBottom),
+			_ => Err(format!("Unknown stereo layout '{}'", s)),
+		}
+	}
+}
+
+impl StereoLayout {
+	/// Size of the frame holding both eyes of `width` by `height` pixels.
+	/// Separate eyes are rendered side by side and split when written.
+	pub fn frame_size(self, width: u32, height: u32) -> (u32, u32) {
+		match self {
+			StereoLayout::Separate | StereoLayout::SideBySide => (width * 2, height),
+			StereoLayout::TopBottom => (width, height * 2),
+		}
+	}
+
+	/// Top left corner of eye `eye` (zero for left) within the frame.
+	pub fn offset(self, eye: u32, width: u32, height: u32) -> (u32, u32) {
+		match self {
+			StereoLayout::Separate | StereoLayout::SideBySide => (eye * width, 0),
+			StereoLayout::TopBottom => (0, eye * height),
+		}
+	}
+}
+
+/// Where one eye of a stereo pair sits and looks.
+#[derive(Debug, Clone, Copy)]
+pub struct Eye {
+	pub position: Vec3f,
+	pub target: Vec3f,
+	/// Horizontal film shift making the eyes agree at the convergence
+	/// distance, see `PerspectiveCamera::shift`.
+	pub shift: f32,
+}
+
+/// Parallel pair of eyes `interocular` apart, with off-axis frusta so objects
+/// at the `convergence` distance appear at the screen plane.
+#[derive(Debug, Clone, Copy)]
+pub struct StereoRig {
+	pub interocular: f32,
+	pub convergence: f32,
+}
+
+impl StereoRig {
+	/// Left and right eyes of a rig centred on a camera at `position`.
+	pub fn eyes(&self, position: Vec3f, target: Vec3f, up: Vec3f) -> [Eye; 2] {
+		let (right, _) = film_axes((target - position).unit(), up);
+
+		[-0.5, 0.5].map(|side| {
+			let offset = right * (side * self.interocular);
+			Eye {
+				position: position + offset,
+				target: target + offset,
+				shift: -side * self.interocular / self.convergence,
+			}
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::camera::{Camera, Fov, PerspectiveCamera};
+
+	#[test]
+	fn lays_out_both_eyes() {
+		assert_eq!(StereoLayout::SideBySide.frame_size(4, 3), (8, 3));
+		assert_eq!(StereoLayout::SideBySide.offset(1, 4, 3), (4, 0));
+		assert_eq!(StereoLayout::TopBottom.frame_size(4, 3), (4, 6));
+		assert_eq!(StereoLayout::TopBottom.offset(1, 4, 3), (0, 3));
+		assert_eq!(StereoLayout::Separate.offset(0, 4, 3), (0, 0));
+		assert_eq!("top-bottom".parse(), Ok(StereoLayout::TopBottom));
+		assert!("anaglyph".parse::<StereoLayout>().is_err());
+	}
+
+	#[test]
+	fn eyes_converge_at_the_screen_plane() {
+		let rig = StereoRig {
+			interocular: 0.064,
+			convergence: 3.,
+		};
+		let (position, target, up) = (
+			Vec3f::new(0., 1., 0.),
+			Vec3f::new(0., 1., 10.),
+			Vec3f::new(0., 1., 0.),
+		);
+		let [left, right] = rig.eyes(position, target, up);
+		assert!((left.position - Vec3f::new(-0.032, 1., 0.)).len() < 1e-6);
+		assert!((right.position - Vec3f::new(0.032, 1., 0.)).len() < 1e-6);
+
+		// Both centre rays pass through the point straight ahead at the
+		// convergence distance.
+		for eye in [left, right] {
+			let mut camera =
+				PerspectiveCamera::new(eye.position, eye.target, up, Fov::Horizontal(1.), 1.);
+			camera.shift = eye.shift;
+			let ray = camera.ray((0.5, 0.5), (0.5, 0.5)).unwrap();
+			let at = ray.origin + ray.direction * (3. / ray.direction.z);
+			assert!((at - Vec3f::new(0., 1., 3.)).len() < 1e-5);
+		}
+	}
+}
@@ -0,0 +1,265 @@
+use std::path::{Path, PathBuf};
+
+use clap::{error::ErrorKind, value_parser, Args, CommandFactory, Parser, Subcommand};
+
+use crate::{loader::RenderSettings, sampler::SamplerKind};
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Path traces a scene into an image")]
+pub struct Cli {
+	#[command(subcommand)]
+	pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+	/// Renders straight to the output image without opening a window
+	Render(RenderOptions),
+	/// Shows the render in a window as it progresses, then writes the output
+	/// image. Closing the window stops early.
+	Preview(RenderOptions),
+}
+
+impl Command {
+	pub fn options(&self) -> &RenderOptions {
+		match self {
+			Command::Render(options) | Command::Preview(options) => options,
+		}
+	}
+}
+
+#[derive(Debug, Args)]
+pub struct RenderOptions {
+	/// Scene to render, a .toml scene file, a pbrt-v3 .pbrt file or a Mitsuba .xml file
+	#[arg(long, default_value = "scenes/avocado.toml")]
+	pub scene: PathBuf,
+
+	/// Image to write, a BMP file. Stereo pairs rendered as separate images
+	/// get `_left` and `_right` added to the name.
+	#[arg(short, long, default_value = "temp.bmp")]
+	pub output: PathBuf,
+
+	/// Width of the image in pixels [default: from the scene]
+	#[arg(long, value_parser = value_parser!(u32).range(1..=16384))]
+	pub width: Option<u32>,
+
+	/// Height of the image in pixels [default: from the scene]
+	#[arg(long, value_parser = value_parser!(u32).range(1..=16384))]
+	pub height: Option<u32>,
+
+	/// Samples per pixel, the most any pixel takes with adaptive sampling
+	/// [default: from the scene]
+	#[arg(long, value_parser = value_parser!(u32).range(1..))]
+	pub spp: Option<u32>,
+
+	/// Most bounces of a path [default: from the scene]
+	#[arg(long, value_parser = value_parser!(u32).range(1..))]
+	pub max_depth: Option<u32>,
+
+	/// Worker threads [default: one per core]
+	#[arg(long, value_parser = value_parser!(u32).range(1..))]
+	pub threads: Option<u32>,
+
+	/// Seed every random decision derives from, so the same inputs always
+	/// render the same image [default: from the scene]
+	#[arg(long)]
+	pub seed: Option<u64>,
+
+	/// One of `independent`, `stratified`, `halton`, `sobol` or `blue-noise`,
+	/// the last meant for previews at one to four samples per pixel
+	/// [default: from the scene]
+	#[arg(long)]
+	pub sampler: Option<SamplerKind>,
+
+	/// Keeps sampling a pixel until the standard error of its mean falls
+	/// below this fraction of it, or `--spp` is reached
+	#[arg(long)]
+	pub adaptive: Option<f32>,
+
+	/// Samples every pixel takes before adaptive sampling may stop, at most
+	/// `--spp`
+	#[arg(long, default_value_t = 16, value_parser = value_parser!(u32).range(1..))]
+	pub min_spp: u32,
+
+	/// Where to write a map of the samples taken per pixel, white being
+	/// `--spp`
+	#[arg(long)]
+	pub sample_map: Option<PathBuf>,
+
+	/// Renders the whole frame one sample per pixel at a time, so the
+	/// preview converges everywhere at once
+	#[arg(long)]
+	pub progressive: bool,
+}
+
+impl RenderOptions {
+	/// Overrides the scene's settings with those given on the command line.
+	pub fn apply(&self, settings: &mut RenderSettings) {
+		if let Some(width) = self.width {
+			settings.width = width;
+		}
+		if let Some(height) = self.height {
+			settings.height = height;
+		}
+		if let Some(spp) = self.spp {
+			settings.spp = spp;
+		}
+		if let Some(max_depth) = self.max_depth {
+			settings.max_depth = max_depth;
+		}
+		if let Some(sampler) = self.sampler {
+			settings.sampler = sampler;
+		}
+		if let Some(seed) = self.seed {
+			settings.seed = seed;
+		}
+	}
+
+	fn validate(&self) -> Result<(), (ErrorKind, String)> {
+		if !self.scene.is_file() {
+			return Err((
+				ErrorKind::ValueValidation,
+				format!("scene '{}' is not a file", self.scene.display()),
+			));
+		}
+
+		for output in [Some(&self.output), self.sample_map.as_ref()]
+			.into_iter()
+			.flatten()
+		{
+			check_output(output)?;
+		}
+
+		if let Some(threshold) = self.adaptive {
+			if threshold.is_nan() || threshold <= 0. {
+				return Err((
+					ErrorKind::ValueValidation,
+					format!("adaptive threshold must be above zero, got {}", threshold),
+				));
+			}
+			if let Some(spp) = self.spp.filter(|spp| self.min_spp > *spp) {
+				return Err((
+					ErrorKind::ArgumentConflict,
+					format!("--min-spp {} is more than --spp {}", self.min_spp, spp),
+				));
+			}
+		}
+
+		Ok(())
+	}
+}
+
+fn check_output(path: &Path) -> Result<(), (ErrorKind, String)> {
+	if path.extension().and_then(|e| e.to_str()) != Some("bmp") {
+		return Err((
+			ErrorKind::ValueValidation,
+			format!(
+				"can only write BMP images, '{}' should end in .bmp",
+				path.display()
+			),
+		));
+	}
+
+	match path.parent() {
+		Some(parent) if !parent.as_os_str().is_empty() && !parent.is_dir() => Err((
+			ErrorKind::ValueValidation,
+			format!(
+				"directory '{}' for '{}' does not exist",
+				parent.display(),
+				path.display()
+			),
+		)),
+		_ => Ok(()),
+	}
+}
+
+/// Parses the command line, exiting with a usage message when it is invalid.
+pub fn parse() -> Cli {
+	let cli = Cli::parse();
+
+	#[cfg(not(feature = "preview"))]
+	if let Command::Preview(_) = cli.command {
+		Cli::command()
+			.error(
+				ErrorKind::InvalidSubcommand,
+				"this build has no window preview, rebuild with `--features preview`",
+			)
+			.exit();
+	}
+
+	if let Err((kind, message)) = cli.command.options().validate() {
+		Cli::command().error(kind, message).exit();
+	}
+	cli
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn options(args: &[&str]) -> Result<RenderOptions, clap::Error> {
+		// The default scene is one of the examples, so it exists.
+		Cli::try_parse_from(["path-tracing", "render"].iter().chain(args)).map(|cli| {
+			match cli.command {
+				Command::Render(options) | Command::Preview(options) => options,
+			}
+		})
+	}
+
+	#[test]
+	fn overrides_scene_settings() {
+		let options = options(&[
+			"--width",
+			"32",
+			"--spp",
+			"8",
+			"--sampler",
+			"sobol",
+			"--seed",
+			"9",
+			"-o",
+			"a.bmp",
+		])
+		.unwrap();
+		assert_eq!(options.output, PathBuf::from("a.bmp"));
+		assert!(options.validate().is_ok());
+
+		let mut settings = RenderSettings::new();
+		let height = settings.height;
+		options.apply(&mut settings);
+		assert_eq!(
+			(settings.width, settings.height, settings.spp),
+			(32, height, 8)
+		);
+		assert_eq!((settings.sampler, settings.seed), (SamplerKind::Sobol, 9));
+	}
+
+	#[test]
+	fn rejects_values_out_of_range() {
+		assert!(options(&["--width", "0"]).is_err());
+		assert!(options(&["--spp", "-1"]).is_err());
+		assert!(options(&["--sampler", "random"]).is_err());
+		assert!(options(&["--frobnicate"]).is_err());
+	}
+
+	#[test]
+	fn validates_paths_and_thresholds() {
+		let invalid = |args: &[&str]| options(args).unwrap().validate().unwrap_err().0;
+
+		assert_eq!(invalid(&["--scene", "scenes"]), ErrorKind::ValueValidation);
+		assert_eq!(invalid(&["-o", "out.png"]), ErrorKind::ValueValidation);
+		assert_eq!(
+			invalid(&["--sample-map", "missing/map.bmp"]),
+			ErrorKind::ValueValidation
+		);
+		assert_eq!(invalid(&["--adaptive", "0"]), ErrorKind::ValueValidation);
+		assert_eq!(
+			invalid(&["--adaptive", "0.1", "--spp", "4", "--min-spp", "8"]),
+			ErrorKind::ArgumentConflict
+		);
+		assert!(options(&["--adaptive", "0.1", "--spp", "32"])
+			.unwrap()
+			.validate()
+			.is_ok());
+	}
+}
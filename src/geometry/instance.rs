@@ -0,0 +1,164 @@
+use std::sync::{Arc, OnceLock};
+
+use crate::material::Material;
+
+use super::{
+	BoundingBox, Mesh, Motion, Object, Polygon3, Ray, Transform, Vec3f, WithOrigin, WithScale,
+};
+
+/// A shared mesh placed by its own transform and drawn in its own material,
+/// so repeated models keep a single copy of their faces. Rays are moved into
+/// the space of the mesh to be intersected.
+pub struct Instance {
+	mesh: Arc<Mesh>,
+	to_world: Transform,
+	/// Inverse of `to_world`, missing when it flattens the mesh.
+	to_object: Option<Transform>,
+	bounding: BoundingBox,
+	/// Faces moved into world space, made once when first asked for.
+	faces: OnceLock<Vec<Polygon3>>,
+	pub material: Material,
+	motion: Motion,
+}
+
+impl Instance {
+	pub fn new(mesh: Arc<Mesh>, material: Material) -> Self {
+		Self {
+			bounding: mesh.bounding(),
+			mesh,
+			to_world: Transform::IDENTITY,
+			to_object: Some(Transform::IDENTITY),
+			faces: OnceLock::new(),
+			material,
+			motion: Motion::default(),
+		}
+	}
+
+	/// Whether the placement flattens the mesh, which no ray can then hit.
+	pub fn is_singular(&self) -> bool {
+		self.to_object.is_none()
+	}
+}
+
+impl WithOrigin for Instance {}
+impl WithScale for Instance {}
+
+impl Object for Instance {
+	fn faces(&self) -> &[Polygon3] {
+		self.faces.get_or_init(|| {
+			self.mesh
+				.faces()
+				.iter()
+				.map(|f| f.transform(&self.to_world))
+				.collect()
+		})
+	}
+
+	fn bounding(&self) -> &BoundingBox {
+		&self.bounding
+	}
+
+	fn motion(&self) -> Motion {
+		self.motion
+	}
+
+	fn set_motion(&mut self, motion: Motion) {
+		self.motion = motion;
+	}
+
+	fn transform(&mut self, transform: &Transform) {
+		self.to_world = transform.mul(&self.to_world);
+		self.to_object = self.to_world.inverse();
+		self.faces = OnceLock::new();
+		self.bounding = self.mesh.bounding().transform(&self.to_world);
+	}
+
+	fn hit_face(&self, ray: &Ray) -> Option<(usize, Polygon3, Vec3f)> {
+		let to_object = self.to_object.as_ref()?;
+		let local = Ray::new(to_object.point(ray.origin), to_object.vector(ray.direction));
+
+		let (i, point) = self.mesh.hit(&local)?;
+		Some((
+			i,
+			self.mesh.faces()[i].transform(&self.to_world),
+			self.to_world.point(point),
+		))
+	}
+
+	fn material(&self) -> Option<&Material> {
+		Some(&self.material)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::image::Colour;
+	use crate::testing::assert_close;
+
+	/// A unit square in the plane `z = 0`.
+	fn square() -> Instance {
+		let (a, b, c, d) = (
+			Vec3f::new(0., 0., 0.),
+			Vec3f::new(1., 0., 0.),
+			Vec3f::new(1., 1., 0.),
+			Vec3f::new(0., 1., 0.),
+		);
+		let mesh = Mesh::new(vec![Polygon3::new(a, b, c), Polygon3::new(a, c, d)]);
+		let material = Material {
+			albedo: Colour::from_rgb(1., 1., 1.),
+			specular: 0.,
+			metalic: 0.,
+			roughness: 0.,
+			emission: 0.,
+			texture: None,
+		};
+		Instance::new(Arc::new(mesh), material)
+	}
+
+	#[test]
+	fn hits_in_world_space() {
+		let mut instance = square();
+		instance.transform(&Transform::scale(Vec3f::new(2., 4., 1.)));
+		instance.transform(&Transform::rotate(90., Vec3f::new(1., 0., 0.)));
+		instance.transform(&Transform::translate(Vec3f::new(0., 0., 5.)));
+
+		// Now four high, along `z` from 5 to 9, and two wide.
+		let ray = Ray::new(Vec3f::new(1.5, 3., 8.), Vec3f::new(0., -1., 0.));
+		let (_, face, point) = instance.hit_face(&ray).unwrap();
+		assert_close(point, Vec3f::new(1.5, 0., 8.));
+		assert_close(face.normal, Vec3f::new(0., -1., 0.));
+		assert!(instance
+			.hit_face(&Ray::new(Vec3f::new(2.5, 3., 8.), Vec3f::new(0., -1., 0.)))
+			.is_none());
+
+		assert_close(instance.bounding().center(), Vec3f::new(1., 0., 7.));
+		assert_close(instance.bounding().size(), Vec3f::new(2., 0., 4.));
+	}
+
+	#[test]
+	fn caches_world_space_faces() {
+		let mut instance = square();
+		instance.transform(&Transform::translate(Vec3f::new(0., 0., 3.)));
+		let faces = instance.faces();
+		assert_eq!(faces[0].c, Vec3f::new(1., 1., 3.));
+		assert!(std::ptr::eq(faces.as_ptr(), instance.faces().as_ptr()));
+
+		// Moving the instance again gives new faces.
+		instance.transform(&Transform::translate(Vec3f::new(0., 0., 1.)));
+		assert_eq!(instance.faces()[0].c, Vec3f::new(1., 1., 4.));
+	}
+
+	#[test]
+	fn flattened_instances_are_singular() {
+		let mut instance = square();
+		assert!(!instance.is_singular());
+		instance.scale(0.5);
+		assert!(!instance.is_singular());
+
+		instance.transform(&Transform::scale(Vec3f::new(1., 0., 1.)));
+		assert!(instance.is_singular());
+		let ray = Ray::new(Vec3f::new(0.25, 0., 1.), Vec3f::new(0., 0., -1.));
+		assert!(instance.hit_face(&ray).is_none());
+	}
+}
@@ -0,0 +1,199 @@
+use std::ops::Range;
+
+use super::{BoundingBox, Intersect, Polygon3, Ray, Vec3f};
+
+/// Most faces a leaf holds.
+const LEAF_SIZE: usize = 4;
+
+enum Node {
+	Leaf(Range<usize>),
+	/// The first child directly follows its parent, the second one is at the
+	/// stored index.
+	Interior(usize),
+}
+
+/// Faces sorted into a bounding volume hierarchy, shared by every instance
+/// placed from them.
+pub struct Mesh {
+	faces: Vec<Polygon3>,
+	nodes: Vec<(BoundingBox, Node)>,
+}
+
+impl Mesh {
+	/// Builds the hierarchy by splitting the faces in half along the longest
+	/// axis of their centroids, which reorders them.
+	pub fn new(faces: Vec<Polygon3>) -> Self {
+		let mut mesh = Self {
+			faces,
+			nodes: Vec::new(),
+		};
+		if !mesh.faces.is_empty() {
+			mesh.build(0, mesh.faces.len());
+		}
+
+		mesh
+	}
+
+	fn build(&mut self, start: usize, end: usize) {
+		let bounding = BoundingBox::around(&self.faces[start..end]);
+		if end - start <= LEAF_SIZE {
+			self.nodes.push((bounding, Node::Leaf(start..end)));
+			return;
+		}
+
+		let centroid = |f: &Polygon3| (f.a + f.b + f.c) / 3.;
+		let centroids = self.faces[start..end]
+			.iter()
+			.map(|f| Polygon3::new(centroid(f), centroid(f), centroid(f)))
+			.collect::<Vec<_>>();
+		let extent = BoundingBox::around(&centroids).size();
+		let axis = |v: Vec3f| {
+			if extent.x >= extent.y && extent.x >= extent.z {
+				v.x
+			} else if extent.y >= extent.z {
+				v.y
+			} else {
+				v.z
+			}
+		};
+		self.faces[start..end].sort_by(|a, b| axis(centroid(a)).total_cmp(&axis(centroid(b))));
+
+		let node = self.nodes.len();
+		self.nodes.push((bounding, Node::Interior(0)));
+
+		let middle = start + (end - start) / 2;
+		self.build(start, middle);
+		let second = self.nodes.len();
+		self.build(middle, end);
+		self.nodes[node].1 = Node::Interior(second);
+	}
+
+	pub fn faces(&self) -> &[Polygon3] {
+		&self.faces
+	}
+
+	pub fn bounding(&self) -> BoundingBox {
+		match self.nodes.first() {
+			Some((bounding, _)) => bounding.clone(),
+			None => BoundingBox::around(&[]),
+		}
+	}
+
+	/// Nearest face `ray` hits and the point hit, visiting only the nodes
+	/// whose bounds the ray enters before the nearest hit found so far.
+	pub fn hit(&self, ray: &Ray) -> Option<(usize, Vec3f)> {
+		// Distances are in multiples of the direction, which instances scale.
+		let distance =
+			|point: Vec3f| (point - ray.origin).dot(ray.direction) / ray.direction.len_sq();
+
+		let mut closest: Option<(usize, Vec3f, f32)> = None;
+		let mut stack = if self.nodes.is_empty() {
+			vec![]
+		} else {
+			vec![0]
+		};
+		while let Some(index) = stack.pop() {
+			let (bounding, node) = &self.nodes[index];
+			let Some(entry) = bounding.entry(ray) else {
+				continue;
+			};
+			if closest.is_some_and(|(_, _, t)| entry > t) {
+				continue;
+			}
+
+			match node {
+				Node::Leaf(faces) => {
+					for i in faces.clone() {
+						let Some(point) = self.faces[i].intersect(ray) else {
+							continue;
+						};
+						let t = distance(point);
+						if closest.is_none_or(|(_, _, nearest)| t < nearest) {
+							closest = Some((i, point, t));
+						}
+					}
+				}
+				Node::Interior(second) => stack.extend([index + 1, *second]),
+			}
+		}
+
+		closest.map(|(i, point, _)| (i, point))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::geometry::nearest;
+
+	/// Numbers in `[-1, 1)` from a fixed xorshift sequence.
+	fn numbers(mut state: u64) -> impl FnMut() -> f32 {
+		move || {
+			state ^= state << 13;
+			state ^= state >> 7;
+			state ^= state << 17;
+			(state >> 40) as f32 / (1u64 << 23) as f32 - 1.
+		}
+	}
+
+	/// Small triangles scattered through a cube of side 20.
+	fn soup(count: usize) -> Vec<Polygon3> {
+		let mut next = numbers(7);
+		let mut point = |scale: f32| Vec3f::new(next(), next(), next()) * scale;
+		(0..count)
+			.map(|_| {
+				let centre = point(10.);
+				Polygon3::new(centre + point(1.), centre + point(1.), centre + point(1.))
+			})
+			.collect()
+	}
+
+	#[test]
+	fn hits_the_same_face_as_checking_every_one() {
+		let mesh = Mesh::new(soup(500));
+		let mut next = numbers(11);
+		let mut hits = 0;
+		for _ in 0..2000 {
+			let origin = Vec3f::new(next(), next(), next()) * 15.;
+			let target = Vec3f::new(next(), next(), next()) * 5.;
+			let ray = Ray::new(origin, (target - origin) * (next() + 2.));
+
+			let expected = nearest(
+				&ray,
+				mesh.faces()
+					.iter()
+					.enumerate()
+					.filter_map(|(i, f)| f.intersect(&ray).map(|p| (i, p))),
+			);
+			let hit = mesh.hit(&ray);
+			assert_eq!(hit.map(|(i, _)| i), expected.map(|(i, _)| i));
+			hits += hit.is_some() as usize;
+		}
+		// Enough rays hit for the comparison to mean something.
+		assert!(hits > 500, "{}", hits);
+	}
+
+	#[test]
+	fn bounds_every_face() {
+		let faces = soup(100);
+		let mesh = Mesh::new(faces.clone());
+		assert_eq!(mesh.faces().len(), 100);
+		let (bounding, around) = (mesh.bounding(), BoundingBox::around(&faces));
+		assert_eq!(
+			(bounding.center(), bounding.size()),
+			(around.center(), around.size())
+		);
+		for (bounding, node) in &mesh.nodes {
+			if let Node::Leaf(range) = node {
+				assert!(range.len() <= LEAF_SIZE);
+				let inner = BoundingBox::around(&mesh.faces()[range.clone()]);
+				assert_eq!(bounding.union(&inner).size(), bounding.size());
+			}
+		}
+
+		let empty = Mesh::new(Vec::new());
+		assert!(empty
+			.hit(&Ray::new(Vec3f::new(0., 0., 0.), Vec3f::new(0., 0., 1.)))
+			.is_none());
+	}
+}
@@ -10,9 +10,21 @@ pub use ray::*;
 mod object;
 pub use object::*;
 
+mod mesh;
+pub use mesh::*;
+
+mod motion;
+pub use motion::*;
+
+mod instance;
+pub use instance::*;
+
 mod scene;
 pub use scene::*;
 
+mod transform;
+pub use transform::*;
+
 pub trait Intersect<T> {
 	const EPSILON: f32 = 0.0001;
 
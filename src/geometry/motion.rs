@@ -0,0 +1,188 @@
+use super::{Transform, Vec3f};
+
+/// Change of an object's placement while the shutter is open, from where it
+/// is at time zero to where it ends at time one: scaled by `scale` and
+/// turned `angle` degrees about `axis`, both through `pivot`, then moved by
+/// `offset`. Every part changes at a constant rate in between, so an object
+/// may spin more than a full turn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Motion {
+	pub offset: Vec3f,
+	pub axis: Vec3f,
+	pub angle: f32,
+	pub scale: Vec3f,
+	pub pivot: Vec3f,
+}
+
+impl Default for Motion {
+	fn default() -> Self {
+		Self {
+			offset: Vec3f::new(0., 0., 0.),
+			axis: Vec3f::new(0., 1., 0.),
+			angle: 0.,
+			scale: Vec3f::new(1., 1., 1.),
+			pivot: Vec3f::new(0., 0., 0.),
+		}
+	}
+}
+
+impl Motion {
+	/// Straight travel by `offset`.
+	pub fn offset(offset: Vec3f) -> Self {
+		Self {
+			offset,
+			..Self::default()
+		}
+	}
+
+	pub fn is_static(&self) -> bool {
+		self.offset == Vec3f::new(0., 0., 0.)
+			&& self.angle == 0.
+			&& self.scale == Vec3f::new(1., 1., 1.)
+	}
+
+	/// Scale factors reached at `time`.
+	fn scale_at(&self, time: f32) -> Vec3f {
+		Vec3f::new(1., 1., 1.) + (self.scale - Vec3f::new(1., 1., 1.)) * time
+	}
+
+	fn rotation_at(&self, time: f32) -> Transform {
+		if self.angle == 0. {
+			Transform::IDENTITY
+		} else {
+			Transform::rotate(self.angle * time, self.axis)
+		}
+	}
+
+	/// Moves points from where they are at time zero to where they are at
+	/// `time`.
+	pub fn at(&self, time: f32) -> Transform {
+		Transform::translate(self.pivot + self.offset * time)
+			.mul(&self.rotation_at(time))
+			.mul(&Transform::scale(self.scale_at(time)))
+			.mul(&Transform::translate(self.pivot * -1.))
+	}
+
+	/// Inverse of `at`, `None` once the object has shrunk to nothing.
+	pub fn inverse_at(&self, time: f32) -> Option<Transform> {
+		let s = self.scale_at(time);
+		if s.x == 0. || s.y == 0. || s.z == 0. {
+			return None;
+		}
+
+		Some(
+			Transform::translate(self.pivot)
+				.mul(&Transform::scale(Vec3f::new(1. / s.x, 1. / s.y, 1. / s.z)))
+				.mul(&self.rotation_at(-time))
+				.mul(&Transform::translate(
+					(self.pivot + self.offset * time) * -1.,
+				)),
+		)
+	}
+
+	/// Largest factor any length is scaled by during the shutter.
+	pub fn max_scale(&self) -> f32 {
+		[self.scale.x, self.scale.y, self.scale.z, 1.]
+			.into_iter()
+			.fold(0., |max, s| f32::max(max, s.abs()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::assert_close;
+	use crate::{
+		geometry::{BoundingBox, Object, Polygon3, SolidObject},
+		image::Colour,
+		material::Material,
+	};
+
+	fn spin() -> Motion {
+		Motion {
+			offset: Vec3f::new(4., 0., 0.),
+			axis: Vec3f::new(0., 1., 0.),
+			angle: 90.,
+			scale: Vec3f::new(2., 2., 2.),
+			pivot: Vec3f::new(1., 0., 0.),
+		}
+	}
+
+	#[test]
+	fn moves_from_start_to_end() {
+		let motion = spin();
+		let p = Vec3f::new(2., 1., 0.);
+
+		assert_close(motion.at(0.).point(p), p);
+		assert_close(motion.at(1.).point(motion.pivot), Vec3f::new(5., 0., 0.));
+		// Doubled and turned a quarter about `+y`, `+x` goes to `-z`.
+		assert_close(motion.at(1.).point(p), Vec3f::new(5., 2., -2.));
+		assert_close(motion.at(0.5).point(motion.pivot), Vec3f::new(3., 0., 0.));
+	}
+
+	#[test]
+	fn inverse_undoes_the_motion() {
+		let motion = spin();
+		let p = Vec3f::new(-3., 0.5, 7.);
+		for t in [0., 0.25, 0.5, 1.] {
+			let back = motion.inverse_at(t).unwrap();
+			assert_close(back.point(motion.at(t).point(p)), p);
+		}
+
+		let vanishing = Motion {
+			scale: Vec3f::new(0., 1., 1.),
+			..Motion::default()
+		};
+		assert!(vanishing.inverse_at(1.).is_none());
+		assert!(vanishing.inverse_at(0.5).is_some());
+	}
+
+	#[test]
+	fn straight_travel() {
+		let motion = Motion::offset(Vec3f::new(0., 2., 0.));
+		assert!(!motion.is_static());
+		assert!(Motion::default().is_static());
+		assert_close(
+			motion.at(0.5).point(Vec3f::new(1., 1., 1.)),
+			Vec3f::new(1., 2., 1.),
+		);
+		assert_eq!(motion.max_scale(), 1.);
+		assert_eq!(spin().max_scale(), 2.);
+	}
+
+	#[test]
+	fn swept_bounds_hold_every_position() {
+		let faces = vec![Polygon3::new(
+			Vec3f::new(1., 0., 0.),
+			Vec3f::new(3., 0., 0.),
+			Vec3f::new(2., 1., 1.),
+		)];
+		let material = Material {
+			albedo: Colour::from_rgb(1., 1., 1.),
+			specular: 0.,
+			metalic: 0.,
+			roughness: 0.,
+			emission: 0.,
+			texture: None,
+		};
+		let mut object = SolidObject::new(faces.clone(), material);
+		object.set_motion(spin());
+
+		let swept = object.swept_bounding();
+		let contains = |bounds: &BoundingBox, p: Vec3f| {
+			let (min, max) = (
+				bounds.center() - bounds.size() / 2.,
+				bounds.center() + bounds.size() / 2.,
+			);
+			(min.x..=max.x).contains(&p.x)
+				&& (min.y..=max.y).contains(&p.y)
+				&& (min.z..=max.z).contains(&p.z)
+		};
+		for k in 0..=20 {
+			let moved = spin().at(k as f32 / 20.);
+			for p in [faces[0].a, faces[0].b, faces[0].c] {
+				assert!(contains(&swept, moved.point(p)));
+			}
+		}
+	}
+}
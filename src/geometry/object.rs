@@ -1,37 +1,26 @@
 use crate::{image::Colour, material::Material};
 
-use super::{Intersect, Polygon3, Ray, Vec3f};
+use super::{Intersect, Motion, Polygon3, Ray, Transform, Vec3f};
 
 pub trait WithOrigin: Object {
+	/// Moves the object so its bounds are centred on `origin`.
+	#[allow(dead_code)]
 	fn move_to(&mut self, origin: Vec3f) {
-		let center = self.bounding().0 + (self.bounding().1 - self.bounding().0) / 2.;
-
-		self.set_faces(
-			self.faces()
-				.iter()
-				.map(|f| {
-					Polygon3::new(
-						f.a - center + origin,
-						f.b - center + origin,
-						f.c - center + origin,
-					)
-				})
-				.collect(),
-		);
-
-		self.update_bounding_box();
+		let offset = origin - self.bounding().center();
+		self.transform(&Transform::translate(offset));
 	}
 }
 
 pub trait WithScale: Object {
+	/// Scales the object by `n` about the origin.
 	fn scale(&mut self, n: f32) {
-		self.set_faces(self.faces().iter().map(|x| x.scale(n)).collect());
-
-		self.set_bounding(BoundingBox(self.bounding().0 * n, self.bounding().1 * n));
+		self.transform(&Transform::scale(Vec3f::new(n, n, n)));
 	}
 
+	/// Scales uniformly to fit within `x` by `y`.
+	#[allow(dead_code)]
 	fn scale_to(&mut self, x: f32, y: f32) {
-		let size = self.bounding().1 - self.bounding().0;
+		let size = self.bounding().size();
 		let min = f32::min(x / size.x, y / size.y);
 
 		self.scale(min);
@@ -39,43 +28,47 @@ pub trait WithScale: Object {
 }
 
 pub trait Object: Sync + Send {
-	fn faces(&self) -> &Vec<Polygon3>;
+	/// Faces in world space.
+	fn faces(&self) -> &[Polygon3];
 	fn bounding(&self) -> &BoundingBox;
 
-	fn set_faces(&mut self, faces: Vec<Polygon3>);
-	fn set_bounding(&mut self, bounding: BoundingBox);
+	/// How the object moves from where its faces are, at time zero.
+	fn motion(&self) -> Motion;
+	fn set_motion(&mut self, motion: Motion);
 
-	fn update_bounding_box(&mut self) {
-		if self.faces().len() == 0 {
-			self.set_bounding(BoundingBox(Vec3f::new(0., 0., 0.), Vec3f::new(0., 0., 0.)));
-		} else {
-			let mut b = BoundingBox(self.faces()[0].a, self.faces()[0].a);
-			for f in self.faces() {
-				for p in [f.a, f.b, f.c] {
-					if p.x < b.0.x {
-						b.0.x = p.x
-					}
-					if p.y < b.0.y {
-						b.0.y = p.y
-					}
-					if p.z < b.0.z {
-						b.0.z = p.z
-					}
-					if p.x > b.1.x {
-						b.1.x = p.x
-					}
-					if p.y > b.1.y {
-						b.1.y = p.y
-					}
-					if p.z > b.1.z {
-						b.1.z = p.z
-					}
-				}
-			}
-			self.set_bounding(b);
+	/// Bounds covering every position the object takes during the shutter.
+	fn swept_bounding(&self) -> BoundingBox {
+		let motion = self.motion();
+		let start = self.bounding();
+		if motion.is_static() {
+			return start.clone();
+		}
+
+		let swept = start.union(&start.transform(&motion.at(1.)));
+		if motion.angle == 0. {
+			// Every point then moves along a straight line.
+			return swept;
 		}
+
+		// Turning, points stay as close to the travelling pivot as they can
+		// be scaled to.
+		let reach = ((start.size() / 2.).len() + (start.center() - motion.pivot).len())
+			* motion.max_scale();
+		let reach = Vec3f::new(reach, reach, reach);
+		let (a, b) = (motion.pivot, motion.pivot + motion.offset);
+		swept.union(&BoundingBox(
+			Vec3f::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)) - reach,
+			Vec3f::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)) + reach,
+		))
 	}
 
+	/// Moves the faces by `transform`, leaving the motion as it is.
+	fn transform(&mut self, transform: &Transform);
+
+	/// Nearest face `ray` hits, moved into world space, with its index and
+	/// the point hit.
+	fn hit_face(&self, ray: &Ray) -> Option<(usize, Polygon3, Vec3f)>;
+
 	fn material(&self) -> Option<&Material> {
 		None
 	}
@@ -84,6 +77,90 @@ pub trait Object: Sync + Send {
 #[derive(Debug, Clone)]
 pub struct BoundingBox(Vec3f, Vec3f);
 
+impl BoundingBox {
+	/// Smallest box holding `faces`, an empty one at the origin without any.
+	pub fn around(faces: &[Polygon3]) -> Self {
+		let Some(first) = faces.first() else {
+			return BoundingBox(Vec3f::new(0., 0., 0.), Vec3f::new(0., 0., 0.));
+		};
+
+		let mut b = BoundingBox(first.a, first.a);
+		for f in faces {
+			for p in [f.a, f.b, f.c] {
+				b.0 = Vec3f::new(b.0.x.min(p.x), b.0.y.min(p.y), b.0.z.min(p.z));
+				b.1 = Vec3f::new(b.1.x.max(p.x), b.1.y.max(p.y), b.1.z.max(p.z));
+			}
+		}
+		b
+	}
+
+	pub fn center(&self) -> Vec3f {
+		self.0 + (self.1 - self.0) / 2.
+	}
+
+	pub fn size(&self) -> Vec3f {
+		self.1 - self.0
+	}
+
+	/// Box holding this one once moved by `transform`.
+	pub fn transform(&self, transform: &Transform) -> Self {
+		let BoundingBox(min, max) = self;
+		let corners = [0, 1, 2, 3, 4, 5, 6, 7].map(|i| {
+			transform.point(Vec3f::new(
+				if i & 1 == 0 { min.x } else { max.x },
+				if i & 2 == 0 { min.y } else { max.y },
+				if i & 4 == 0 { min.z } else { max.z },
+			))
+		});
+
+		corners[1..].iter().fold(
+			BoundingBox(corners[0], corners[0]),
+			|BoundingBox(min, max), p| {
+				BoundingBox(
+					Vec3f::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+					Vec3f::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+				)
+			},
+		)
+	}
+
+	/// Distance along `ray`, in multiples of its direction, at which it
+	/// enters the box, zero from inside, `None` if it misses or the box is
+	/// behind it.
+	pub fn entry(&self, ray: &Ray) -> Option<f32> {
+		let (mut near, mut far) = (0f32, f32::INFINITY);
+		for (min, max, origin, direction) in [
+			(self.0.x, self.1.x, ray.origin.x, ray.direction.x),
+			(self.0.y, self.1.y, ray.origin.y, ray.direction.y),
+			(self.0.z, self.1.z, ray.origin.z, ray.direction.z),
+		] {
+			let div = 1. / direction;
+			let (a, b) = ((min - origin) * div, (max - origin) * div);
+			let (a, b) = if div >= 0. { (a, b) } else { (b, a) };
+			near = near.max(a);
+			far = far.min(b);
+		}
+
+		(near <= far).then_some(near)
+	}
+
+	/// Smallest box holding both boxes.
+	pub fn union(&self, other: &Self) -> Self {
+		BoundingBox(
+			Vec3f::new(
+				self.0.x.min(other.0.x),
+				self.0.y.min(other.0.y),
+				self.0.z.min(other.0.z),
+			),
+			Vec3f::new(
+				self.1.x.max(other.1.x),
+				self.1.y.max(other.1.y),
+				self.1.z.max(other.1.z),
+			),
+		)
+	}
+}
+
 impl Intersect<Ray> for BoundingBox {
 	fn intersect(&self, r: &Ray) -> Option<Vec3f> {
 		let (mut tmin, mut tmax, tymin, tymax, tzmin, tzmax): (f32, f32, f32, f32, f32, f32);
@@ -139,13 +216,14 @@ pub struct SolidObject {
 	pub material: Material,
 	pub faces: Vec<Polygon3>,
 	pub bounding: BoundingBox,
+	pub motion: Motion,
 }
 
 impl WithOrigin for SolidObject {}
 impl WithScale for SolidObject {}
 
 impl Object for SolidObject {
-	fn faces(&self) -> &Vec<Polygon3> {
+	fn faces(&self) -> &[Polygon3] {
 		&self.faces
 	}
 
@@ -153,12 +231,28 @@ impl Object for SolidObject {
 		&self.bounding
 	}
 
-	fn set_faces(&mut self, faces: Vec<Polygon3>) {
-		self.faces = faces;
+	fn motion(&self) -> Motion {
+		self.motion
+	}
+
+	fn set_motion(&mut self, motion: Motion) {
+		self.motion = motion;
+	}
+
+	fn transform(&mut self, transform: &Transform) {
+		self.faces = self.faces.iter().map(|f| f.transform(transform)).collect();
+		self.bounding = BoundingBox::around(&self.faces);
 	}
 
-	fn set_bounding(&mut self, bounding: BoundingBox) {
-		self.bounding = bounding;
+	fn hit_face(&self, ray: &Ray) -> Option<(usize, Polygon3, Vec3f)> {
+		nearest(
+			ray,
+			self.faces
+				.iter()
+				.enumerate()
+				.filter_map(|(i, polygon)| polygon.intersect(ray).map(|point| (i, point))),
+		)
+		.map(|(i, point)| (i, self.faces[i], point))
 	}
 
 	fn material(&self) -> Option<&Material> {
@@ -166,44 +260,23 @@ impl Object for SolidObject {
 	}
 }
 
-impl SolidObject {
-	pub fn from_gltf<S: AsRef<str>>(path: S) -> Self {
-		let (gltf, buffers, _) = gltf::import(path.as_ref()).expect("Cannot open model");
-
-		let mesh = gltf.meshes().next().expect("No mesh in model");
-		let primitive = mesh.primitives().next().expect("No primitive in model");
-		let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
-
-		let mut faces: Vec<Polygon3> = Vec::new();
-		let positions: Vec<[f32; 3]> = reader
-			.read_positions()
-			.map(|iter| iter.collect())
-			.expect("No positions in model");
-		let indices: Vec<u32> = reader
-			.read_indices()
-			.map(|iter| iter.into_u32().collect())
-			.expect("No indices in model");
-
-		for i in indices.chunks_exact(3) {
-			faces.push(Polygon3::new(
-				positions[i[0] as usize].into(),
-				positions[i[1] as usize].into(),
-				positions[i[2] as usize].into(),
-			));
-		}
-
-		let bound = primitive.bounding_box();
+/// Hit closest to the origin of `ray` among `hits` of faces along it.
+pub fn nearest(ray: &Ray, hits: impl Iterator<Item = (usize, Vec3f)>) -> Option<(usize, Vec3f)> {
+	hits.min_by(|(_, a), (_, b)| {
+		(*a - ray.origin)
+			.len_sq()
+			.total_cmp(&(*b - ray.origin).len_sq())
+	})
+}
 
+impl SolidObject {
+	/// An object made of `faces`, bounded by them.
+	pub fn new(faces: Vec<Polygon3>, material: Material) -> Self {
 		Self {
+			bounding: BoundingBox::around(&faces),
 			faces,
-			bounding: BoundingBox(bound.min.into(), bound.max.into()),
-			material: Material {
-				albedo: Colour::from_rgb(0.8, 0.8, 0.8),
-				specular: 0.,
-				metalic: 0.,
-				roughness: 0.,
-				emission: 0.,
-			},
+			motion: Motion::default(),
+			material,
 		}
 	}
 
@@ -222,29 +295,33 @@ impl SolidObject {
 				),
 			],
 			bounding: BoundingBox(Vec3f::new(0., 0., 0.), Vec3f::new(1., 0., 1.)),
+			motion: Motion::default(),
 			material: Material {
 				albedo: Colour::from_rgb(0.6, 0.6, 0.6),
 				specular: 0.,
 				metalic: 0.,
 				roughness: 0.,
 				emission: 0.,
+				texture: None,
 			},
 		}
 	}
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct Light {
 	pub faces: Vec<Polygon3>,
 	pub bounding: BoundingBox,
 	pub material: Material,
+	pub motion: Motion,
 }
 
 impl WithOrigin for Light {}
 impl WithScale for Light {}
 
 impl Object for Light {
-	fn faces(&self) -> &Vec<Polygon3> {
+	fn faces(&self) -> &[Polygon3] {
 		&self.faces
 	}
 
@@ -252,12 +329,28 @@ impl Object for Light {
 		&self.bounding
 	}
 
-	fn set_faces(&mut self, faces: Vec<Polygon3>) {
-		self.faces = faces;
+	fn motion(&self) -> Motion {
+		self.motion
 	}
 
-	fn set_bounding(&mut self, bounding: BoundingBox) {
-		self.bounding = bounding;
+	fn set_motion(&mut self, motion: Motion) {
+		self.motion = motion;
+	}
+
+	fn transform(&mut self, transform: &Transform) {
+		self.faces = self.faces.iter().map(|f| f.transform(transform)).collect();
+		self.bounding = BoundingBox::around(&self.faces);
+	}
+
+	fn hit_face(&self, ray: &Ray) -> Option<(usize, Polygon3, Vec3f)> {
+		nearest(
+			ray,
+			self.faces
+				.iter()
+				.enumerate()
+				.filter_map(|(i, polygon)| polygon.intersect(ray).map(|point| (i, point))),
+		)
+		.map(|(i, point)| (i, self.faces[i], point))
 	}
 
 	fn material(&self) -> Option<&Material> {
@@ -265,6 +358,7 @@ impl Object for Light {
 	}
 }
 
+#[allow(dead_code)]
 impl Light {
 	pub fn plane() -> Self {
 		Self {
@@ -281,13 +375,80 @@ impl Light {
 				),
 			],
 			bounding: BoundingBox(Vec3f::new(0., 0., 0.), Vec3f::new(1., 0., 1.)),
+			motion: Motion::default(),
 			material: Material {
 				albedo: Colour::from_rgb(1., 1., 1.),
 				emission: 1.,
 				metalic: 0.,
 				roughness: 0.,
 				specular: 0.,
+				texture: None,
 			},
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::assert_close;
+
+	#[test]
+	fn moves_to_an_origin() {
+		let mut plane = SolidObject::plane();
+		plane.move_to(Vec3f::new(3., 1., -2.));
+		assert_close(plane.bounding().center(), Vec3f::new(3., 1., -2.));
+		assert_close(plane.bounding().size(), Vec3f::new(1., 0., 1.));
+		assert_close(plane.faces[0].a, Vec3f::new(2.5, 1., -2.5));
+
+		let mut light = Light::plane();
+		light.move_to(Vec3f::new(0., 5., 0.));
+		assert_close(light.bounding().center(), Vec3f::new(0., 5., 0.));
+	}
+
+	#[test]
+	fn scales_about_the_origin() {
+		let mut plane = SolidObject::plane();
+		plane.scale(3.);
+		assert_close(plane.bounding().size(), Vec3f::new(3., 0., 3.));
+		assert_close(plane.bounding().center(), Vec3f::new(1.5, 0., 1.5));
+
+		// The tighter of the two sides decides.
+		let mut light = Light::plane();
+		light.transform(&Transform::scale(Vec3f::new(2., 1., 1.)));
+		light.scale_to(4., 10.);
+		assert_close(light.bounding().size(), Vec3f::new(4., 0., 2.));
+	}
+
+	#[test]
+	fn bounds_follow_transforms() {
+		let bounds = SolidObject::plane().bounding;
+		let turned = bounds.transform(&Transform::rotate(45., Vec3f::new(0., 1., 0.)));
+		let diagonal = 2f32.sqrt();
+		assert_close(turned.size(), Vec3f::new(diagonal, 0., diagonal));
+		assert_close(turned.center(), Vec3f::new(diagonal / 2., 0., 0.));
+
+		let both = bounds.union(&bounds.transform(&Transform::translate(Vec3f::new(0., 2., 0.))));
+		assert_close(both.size(), Vec3f::new(1., 2., 1.));
+		assert_close(BoundingBox::around(&[]).size(), Vec3f::new(0., 0., 0.));
+	}
+
+	#[test]
+	fn rays_enter_bounds() {
+		let bounds = BoundingBox(Vec3f::new(1., 1., 1.), Vec3f::new(2., 3., 4.));
+		let entry = |origin: [f32; 3], direction: [f32; 3]| {
+			bounds.entry(&Ray::new(origin.into(), direction.into()))
+		};
+
+		assert_eq!(entry([0., 2., 2.], [1., 0., 0.]), Some(1.));
+		// Distances are in multiples of the direction.
+		assert_eq!(entry([0., 2., 2.], [0.5, 0., 0.]), Some(2.));
+		assert_eq!(entry([3., 2., 2.], [-1., 0., 0.]), Some(1.));
+		assert_eq!(entry([1.5, 2., 2.], [0., 0., 1.]), Some(0.));
+		assert_eq!(entry([0., 0., 0.], [1., 1., 1.]), Some(1.));
+
+		assert_eq!(entry([3., 2., 2.], [1., 0., 0.]), None);
+		assert_eq!(entry([0., 0., 2.], [1., 0., 0.]), None);
+		assert_eq!(entry([0., 2., 2.], [1., 2., 0.]), None);
+	}
+}
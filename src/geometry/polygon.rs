@@ -1,6 +1,8 @@
 use std::ops::Add;
 
-use super::{Intersect, Ray, Vec3f};
+use crate::image::Colour;
+
+use super::{Intersect, Ray, Transform, Vec3f};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Polygon3 {
@@ -8,6 +10,13 @@ pub struct Polygon3 {
 	pub b: Vec3f,
 	pub c: Vec3f,
 	pub normal: Vec3f,
+	/// Normals at `a`, `b` and `c`, interpolated across the face for
+	/// shading.
+	pub normals: Option<[Vec3f; 3]>,
+	/// Texture coordinates at `a`, `b` and `c`.
+	pub uvs: Option<[(f32, f32); 3]>,
+	/// Linear RGB colours at `a`, `b` and `c`.
+	pub colours: Option<[[f32; 3]; 3]>,
 }
 
 impl Polygon3 {
@@ -17,17 +26,103 @@ impl Polygon3 {
 			b,
 			c,
 			normal: (a - b).cross(a - c).unit(),
+			normals: None,
+			uvs: None,
+			colours: None,
+		}
+	}
+
+	pub fn with_normals(self, normals: [Vec3f; 3]) -> Self {
+		Self {
+			normals: Some(normals.map(|n| n.unit())),
+			..self
+		}
+	}
+
+	pub fn with_uvs(self, uvs: [(f32, f32); 3]) -> Self {
+		Self {
+			uvs: Some(uvs),
+			..self
+		}
+	}
+
+	pub fn with_colours(self, colours: [[f32; 3]; 3]) -> Self {
+		Self {
+			colours: Some(colours),
+			..self
 		}
 	}
 
-	pub fn scale(self, n: f32) -> Self {
+	/// The face moved by `transform`, with its vertex normals.
+	pub fn transform(self, transform: &Transform) -> Self {
 		Self {
-			a: self.a * n,
-			b: self.b * n,
-			c: self.c * n,
-			normal: self.normal,
+			normals: self
+				.normals
+				.map(|normals| normals.map(|n| transform.normal(n))),
+			uvs: self.uvs,
+			colours: self.colours,
+			..Self::new(
+				transform.point(self.a),
+				transform.point(self.b),
+				transform.point(self.c),
+			)
+		}
+	}
+
+	/// Weights of `a`, `b` and `c` at `point` on the face.
+	pub fn barycentric(&self, point: Vec3f) -> [f32; 3] {
+		let (e1, e2, p) = (self.b - self.a, self.c - self.a, point - self.a);
+		let (d11, d12, d22) = (e1.dot(e1), e1.dot(e2), e2.dot(e2));
+		let (dp1, dp2) = (p.dot(e1), p.dot(e2));
+		let denominator = d11 * d22 - d12 * d12;
+		if denominator == 0. {
+			return [1., 0., 0.];
+		}
+
+		let v = (d22 * dp1 - d12 * dp2) / denominator;
+		let w = (d11 * dp2 - d12 * dp1) / denominator;
+		[1. - v - w, v, w]
+	}
+
+	/// Normal at `point`, interpolated from the vertex normals if there are
+	/// any.
+	pub fn shading_normal(&self, point: Vec3f) -> Vec3f {
+		match self.normals {
+			Some([na, nb, nc]) => {
+				let [wa, wb, wc] = self.barycentric(point);
+				let n = na * wa + nb * wb + nc * wc;
+				if n.len_sq() > 0. {
+					n.unit()
+				} else {
+					self.normal
+				}
+			}
+			None => self.normal,
 		}
 	}
+
+	/// Texture coordinates at `point`, zero without any.
+	pub fn uv(&self, point: Vec3f) -> (f32, f32) {
+		match self.uvs {
+			Some([ta, tb, tc]) => {
+				let [wa, wb, wc] = self.barycentric(point);
+				(
+					ta.0 * wa + tb.0 * wb + tc.0 * wc,
+					ta.1 * wa + tb.1 * wb + tc.1 * wc,
+				)
+			}
+			None => (0., 0.),
+		}
+	}
+
+	/// Colour at `point`, interpolated from the vertex colours if there are
+	/// any.
+	pub fn colour(&self, point: Vec3f) -> Option<Colour> {
+		self.colours.map(|[ca, cb, cc]| {
+			let [wa, wb, wc] = self.barycentric(point);
+			Colour::from(ca) * wa + Colour::from(cb) * wb + Colour::from(cc) * wc
+		})
+	}
 }
 
 impl Add<Vec3f> for Polygon3 {
@@ -38,7 +133,7 @@ impl Add<Vec3f> for Polygon3 {
 			a: self.a + other,
 			b: self.b + other,
 			c: self.c + other,
-			normal: self.normal,
+			..self
 		}
 	}
 }
@@ -58,7 +153,7 @@ impl Intersect<Ray> for Polygon3 {
 		let f = 1. / a;
 		let s = r.origin - self.a;
 		let u = f * s.dot(h);
-		if u < 0. || u > 1. {
+		if !(0. ..=1.).contains(&u) {
 			return None;
 		}
 
@@ -76,3 +171,70 @@ impl Intersect<Ray> for Polygon3 {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::assert_close;
+
+	fn triangle() -> Polygon3 {
+		Polygon3::new(
+			Vec3f::new(0., 0., 0.),
+			Vec3f::new(2., 0., 0.),
+			Vec3f::new(0., 2., 0.),
+		)
+	}
+
+	#[test]
+	fn faces_counterclockwise() {
+		assert_eq!(triangle().normal, Vec3f::new(0., 0., 1.));
+		let flipped = Polygon3::new(triangle().a, triangle().c, triangle().b);
+		assert_eq!(flipped.normal, Vec3f::new(0., 0., -1.));
+	}
+
+	#[test]
+	fn interpolates_across_the_face() {
+		let face = triangle()
+			.with_normals([
+				Vec3f::new(0., 0., 1.),
+				Vec3f::new(1., 0., 0.),
+				Vec3f::new(0., 3., 0.),
+			])
+			.with_uvs([(0., 0.), (1., 0.), (0., 1.)])
+			.with_colours([[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]]);
+
+		assert_eq!(face.barycentric(face.b), [0., 1., 0.]);
+		assert_eq!(face.barycentric(Vec3f::new(0.5, 1., 0.)), [0.25, 0.25, 0.5]);
+
+		assert_eq!(face.shading_normal(face.c), Vec3f::new(0., 1., 0.));
+		assert_close(
+			face.shading_normal(Vec3f::new(1., 0., 0.)),
+			Vec3f::new(1., 0., 1.).unit(),
+		);
+		assert_eq!(face.uv(Vec3f::new(0.5, 1., 0.)), (0.25, 0.5));
+		let colour = face.colour(Vec3f::new(0.5, 1., 0.)).unwrap();
+		assert_eq!((colour.r, colour.g, colour.b), (0.25, 0.25, 0.5));
+
+		// Without attributes the face is flat.
+		assert_eq!(triangle().shading_normal(face.c), triangle().normal);
+		assert_eq!(triangle().uv(face.c), (0., 0.));
+		assert!(triangle().colour(face.c).is_none());
+	}
+
+	#[test]
+	fn transforms_vertices_and_normals() {
+		let face = triangle().with_normals([Vec3f::new(1., 0., 1.); 3]);
+		// A mirror image turns the other way round.
+		let mirrored = face.transform(&Transform::scale(Vec3f::new(-1., 1., 1.)));
+		assert_eq!(mirrored.normal, Vec3f::new(0., 0., -1.));
+		assert_close(mirrored.normals.unwrap()[0], Vec3f::new(-1., 0., 1.).unit());
+
+		// Normals stay perpendicular to a stretched surface.
+		let stretched = face.transform(&Transform::scale(Vec3f::new(1., 1., 2.)));
+		assert_close(stretched.normals.unwrap()[0], Vec3f::new(2., 0., 1.).unit());
+
+		let moved = face.transform(&Transform::translate(Vec3f::new(0., 0., 5.)));
+		assert_eq!(moved.c, Vec3f::new(0., 2., 5.));
+		assert_eq!(moved.normal, face.normal);
+	}
+}
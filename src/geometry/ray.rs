@@ -4,10 +4,20 @@ use super::Vec3f;
 pub struct Ray {
 	pub origin: Vec3f,
 	pub direction: Vec3f,
+	/// Moment within the shutter interval the ray travels at.
+	pub time: f32,
 }
 
 impl Ray {
 	pub fn new(origin: Vec3f, direction: Vec3f) -> Self {
-		Self { origin, direction }
+		Self {
+			origin,
+			direction,
+			time: 0.,
+		}
+	}
+
+	pub fn with_time(self, time: f32) -> Self {
+		Self { time, ..self }
 	}
 }
@@ -1,77 +1,439 @@
-use std::cmp::Ordering;
+use std::sync::OnceLock;
 
-use crate::image::Colour;
+use crate::{
+	image::Colour,
+	light::{
+		power_heuristic, AreaLight, Emitter, Environment, Gradient, LightInfo, LightSampler,
+		LightSampling,
+	},
+	material::Material,
+	sampler::Sampler,
+};
 
-use super::{Intersect, Object, Polygon3, Ray, Vec3f};
+use super::{Intersect, Motion, Object, Polygon3, Ray, Vec3f};
 
 pub struct Scene {
 	objects: Vec<Box<dyn Object>>,
+	environment: Box<dyn Environment>,
+	lights: Vec<Box<dyn Emitter>>,
+	light_sampling: LightSampling,
+	/// Built on first use, once objects are no longer moved around.
+	derived_lights: OnceLock<DerivedLights>,
+}
+
+/// Lights that follow from the rest of the scene.
+struct DerivedLights {
+	/// One per face of the emissive objects, numbered after `Scene::lights`.
+	area: Vec<AreaLight>,
+	/// Index of the first area light of each emissive object.
+	object_lights: Vec<Option<usize>>,
+	/// Lights at infinity, the only ones rays leaving the scene can reach.
+	infinite: Vec<usize>,
+	sampler: Box<dyn LightSampler>,
 }
 
 pub struct Hit<'a> {
-	pub object: &'a Box<dyn Object>,
-	pub polygon: &'a Polygon3,
+	pub object: &'a dyn Object,
 	pub point: Vec3f,
+	/// Shading normal on the side of the polygon facing the incoming ray.
+	pub normal: Vec3f,
+	/// Normal of the polygon itself on that side, which tells directions
+	/// leaving the surface from ones going through it.
+	pub geometric_normal: Vec3f,
+	/// Texture coordinates of the point.
+	pub uv: (f32, f32),
+	/// Vertex colour of the point, for meshes that have them.
+	pub colour: Option<Colour>,
+	/// Light index of the polygon hit if it is emissive.
+	pub light: Option<usize>,
+}
+
+/// Shading point a ray was scattered from, kept to weigh light it hits
+/// against direct light sampling.
+#[derive(Clone, Copy)]
+struct Vertex {
+	point: Vec3f,
+	normal: Vec3f,
+	pdf: f32,
 }
 
 impl Scene {
 	pub fn new() -> Self {
 		Self {
 			objects: Vec::new(),
+			environment: Box::new(Gradient {}),
+			lights: Vec::new(),
+			light_sampling: LightSampling::Power,
+			derived_lights: OnceLock::new(),
 		}
 	}
 
-	pub fn add_object(&mut self, object: Box<dyn Object>) {
+	/// Emissive objects stay where they are at time zero, since that is where
+	/// their light is sampled.
+	pub fn add_object(&mut self, mut object: Box<dyn Object>) {
+		if object.material().is_some_and(|m| m.emission == 1.) {
+			object.set_motion(Motion::default());
+		}
 		self.objects.push(object);
+		self.derived_lights = OnceLock::new();
+	}
+
+	pub fn set_environment(&mut self, environment: Box<dyn Environment>) {
+		self.environment = environment;
+		self.derived_lights = OnceLock::new();
+	}
+
+	pub fn add_light(&mut self, light: Box<dyn Emitter>) {
+		self.lights.push(light);
+		self.derived_lights = OnceLock::new();
+	}
+
+	pub fn set_light_sampling(&mut self, light_sampling: LightSampling) {
+		self.light_sampling = light_sampling;
+		self.derived_lights = OnceLock::new();
 	}
 
-	pub fn hit(&self, ray: &Ray) -> Option<Hit> {
+	/// Light `0` is the environment, then come the lights in order of
+	/// addition and last the faces of emissive objects.
+	fn emitter(&self, index: usize) -> &dyn Emitter {
+		match index {
+			0 => &self.environment,
+			i if i <= self.lights.len() => self.lights[i - 1].as_ref(),
+			i => &self.derived_lights().area[i - 1 - self.lights.len()],
+		}
+	}
+
+	fn derived_lights(&self) -> &DerivedLights {
+		self.derived_lights.get_or_init(|| {
+			let mut area = Vec::new();
+			let object_lights = self
+				.objects
+				.iter()
+				.map(|object| {
+					let radiance = object
+						.material()
+						.filter(|m| m.emission == 1.)
+						.map(|m| m.albedo.clone())?;
+					let first = self.lights.len() + 1 + area.len();
+					for face in object.faces().iter() {
+						area.push(AreaLight::new(*face, radiance.clone()));
+					}
+					Some(first)
+				})
+				.collect();
+
+			// Lights at infinity are weighed by what they send through a sphere
+			// around the objects.
+			let radius = self
+				.objects
+				.iter()
+				.map(|object| object.bounding().clone())
+				.reduce(|a, b| a.union(&b))
+				.map_or(0., |bounds| bounds.size().len() / 2.);
+			let lights: Vec<LightInfo> = std::iter::once(&self.environment as &dyn Emitter)
+				.chain(self.lights.iter().map(|light| light.as_ref()))
+				.chain(area.iter().map(|light| light as &dyn Emitter))
+				.map(|light| match light.bounds() {
+					Some(bounds) => LightInfo::Finite(bounds),
+					None => LightInfo::Infinite(light.infinite_power(radius)),
+				})
+				.collect();
+			let infinite = (0..lights.len())
+				.filter(|i| matches!(lights[*i], LightInfo::Infinite(_)))
+				.collect();
+
+			DerivedLights {
+				sampler: self.light_sampling.build(&lights),
+				area,
+				object_lights,
+				infinite,
+			}
+		})
+	}
+
+	fn light_sampler(&self) -> &dyn LightSampler {
+		self.derived_lights().sampler.as_ref()
+	}
+
+	pub fn hit(&self, ray: &Ray) -> Option<Hit<'_>> {
 		let hit_objects = self
 			.objects
 			.iter()
-			.filter(|object| object.bounding().intersect(ray).is_some());
+			.zip(&self.derived_lights().object_lights)
+			.filter(|(object, _)| object.swept_bounding().intersect(ray).is_some());
 
-		let hits = hit_objects.flat_map(|object| {
-			object
-				.faces()
-				.iter()
-				.filter_map(|polygon| polygon.intersect(ray).map(|point| (polygon, point)))
-				.map(|(polygon, point)| Hit {
-					object,
-					polygon,
-					point,
-				})
+		let hits = hit_objects.filter_map(|(object, light)| {
+			// Moving objects are intersected where they are at the ray's time,
+			// by taking the ray back to where they start.
+			let motion = object.motion();
+			let (i, polygon, point) = if motion.is_static() {
+				object.hit_face(ray)?
+			} else {
+				let back = motion.inverse_at(ray.time)?;
+				let local = Ray::new(back.point(ray.origin), back.vector(ray.direction))
+					.with_time(ray.time);
+				let (i, polygon, point) = object.hit_face(&local)?;
+				let moved = motion.at(ray.time);
+				(i, polygon.transform(&moved), moved.point(point))
+			};
+			let facing = if polygon.normal.dot(ray.direction) > 0. {
+				polygon.normal * -1.
+			} else {
+				polygon.normal
+			};
+			let shading = polygon.shading_normal(point);
+
+			Some(Hit {
+				object: object.as_ref(),
+				point,
+				normal: if shading.dot(facing) < 0. {
+					shading * -1.
+				} else {
+					shading
+				},
+				geometric_normal: facing,
+				uv: polygon.uv(point),
+				colour: polygon.colour(point),
+				light: light.map(|first| first + i),
+			})
 		});
 
 		hits.min_by(|x, y| {
-			let d = (x.point - ray.origin).len() - (y.point - ray.origin).len();
-			match (d < 0., d > 0.) {
-				(false, true) => Ordering::Greater,
-				(true, false) => Ordering::Less,
-				_ => Ordering::Equal,
-			}
+			(x.point - ray.origin)
+				.len_sq()
+				.total_cmp(&(y.point - ray.origin).len_sq())
 		})
 	}
 
-	pub fn get_colour(&self, ray: &Ray, depth: usize) -> Colour {
+	pub fn get_colour(&self, ray: &Ray, depth: usize, sampler: &mut dyn Sampler) -> Colour {
+		self.trace(ray, depth, None, sampler)
+	}
+
+	/// `previous` is the bounce that picked `ray` from the material's diffuse
+	/// lobe, `None` for camera rays and mirror-like bounces.
+	fn trace(
+		&self,
+		ray: &Ray,
+		depth: usize,
+		previous: Option<Vertex>,
+		sampler: &mut dyn Sampler,
+	) -> Colour {
 		if depth == 0 {
 			return Colour::from_rgb(0., 0., 0.);
 		}
 
 		if let Some(hit) = self.hit(ray) {
 			if let Some(material) = hit.object.material() {
-				let (scattered, colour) = material.get_scattered(ray, &hit);
-				if let Some(scattered) = scattered {
-					colour * self.get_colour(&scattered, depth - 1)
+				if let (Some(light), Some(previous)) = (hit.light, previous) {
+					return material.albedo.clone() * self.emission_weight(light, ray, previous);
+				}
+
+				let direct = self.sample_light(material, &hit, ray.time, sampler);
+				let reflected = material.get_scattered(ray, &hit, sampler);
+				if let Some(scattered) = reflected.ray {
+					let previous = if reflected.delta {
+						None
+					} else {
+						Some(Vertex {
+							point: hit.point,
+							normal: hit.normal,
+							pdf: material.scattering_pdf(&hit, scattered.direction),
+						})
+					};
+					direct + reflected.colour * self.trace(&scattered, depth - 1, previous, sampler)
 				} else {
-					colour
+					direct + reflected.colour
 				}
 			} else {
 				Colour::from_rgb(0., 0., 0.)
 			}
 		} else {
-			let t = 0.5 * (ray.direction.y + 1.);
-			Colour::from_rgb(1., 1., 1.) * (1. - t) + Colour::from_rgb(0.5, 0.7, 1.) * t
+			self.derived_lights()
+				.infinite
+				.iter()
+				.map(|&i| {
+					let light = self.emitter(i);
+					let radiance = light.radiance(ray.direction);
+					match previous {
+						Some(previous) if radiance.luminance() > 0. => {
+							radiance * self.emission_weight(i, ray, previous)
+						}
+						_ => radiance,
+					}
+				})
+				.fold(Colour::new(), |a, b| a + b)
+		}
+	}
+
+	/// MIS weight of light `index` reached by a ray the material sampled.
+	fn emission_weight(&self, index: usize, ray: &Ray, previous: Vertex) -> f32 {
+		let light_pdf = self
+			.light_sampler()
+			.pmf(previous.point, previous.normal, index)
+			* self.emitter(index).pdf(ray.origin, ray.direction);
+
+		power_heuristic(previous.pdf, light_pdf)
+	}
+
+	/// Next event estimation towards one light picked by the light sampler,
+	/// weighted against the material's own sampling of the same direction.
+	fn sample_light(
+		&self,
+		material: &Material,
+		hit: &Hit,
+		time: f32,
+		sampler: &mut dyn Sampler,
+	) -> Colour {
+		let u_light = sampler.get_1d();
+		let u = sampler.get_2d();
+
+		let (index, select_pdf) = match self.light_sampler().pick(hit.point, hit.normal, u_light) {
+			Some(picked) => picked,
+			None => return Colour::new(),
+		};
+
+		let light = self.emitter(index);
+		let sample = match light.sample(hit.point, u) {
+			Some(sample) if sample.pdf > 0. => sample,
+			_ => return Colour::new(),
+		};
+
+		let bsdf_pdf = material.scattering_pdf(hit, sample.direction);
+		if bsdf_pdf == 0. || self.occluded(hit.point, sample.direction, sample.distance, time) {
+			return Colour::new();
+		}
+
+		let light_pdf = select_pdf * sample.pdf;
+		let weight = if light.is_delta() {
+			1.
+		} else {
+			power_heuristic(light_pdf, bsdf_pdf)
+		};
+		sample.radiance * material.albedo_at(hit) * (bsdf_pdf * weight / light_pdf)
+	}
+
+	fn occluded(&self, point: Vec3f, direction: Vec3f, distance: f32, time: f32) -> bool {
+		match self.hit(&Ray::new(point, direction).with_time(time)) {
+			Some(hit) => {
+				(hit.point - point).len() < distance * (1. - <Polygon3 as Intersect<Ray>>::EPSILON)
+			}
+			None => false,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		geometry::SolidObject,
+		image::Image,
+		light::{EnvironmentMap, Sun},
+	};
+
+	fn triangle(x: f32, z: f32, emission: f32) -> SolidObject {
+		let faces = vec![Polygon3::new(
+			Vec3f::new(x - 1., -1., z),
+			Vec3f::new(x + 1., -1., z),
+			Vec3f::new(x, 1., z),
+		)];
+		SolidObject::new(
+			faces,
+			Material {
+				albedo: Colour::from_rgb(1., 1., 1.),
+				specular: 0.,
+				metalic: 0.,
+				roughness: 0.,
+				emission,
+				texture: None,
+			},
+		)
+	}
+
+	fn emissive_triangle(x: f32, z: f32) -> Box<dyn Object> {
+		Box::new(triangle(x, z, 1.))
+	}
+
+	fn light_hit(scene: &Scene, x: f32) -> Option<usize> {
+		let ray = Ray::new(Vec3f::new(x, 0., 0.), Vec3f::new(0., 0., 1.));
+		scene.hit(&ray).and_then(|hit| hit.light)
+	}
+
+	#[test]
+	fn area_lights_follow_scene_changes() {
+		let mut scene = Scene::new();
+		scene.add_object(emissive_triangle(0., 5.));
+		assert_eq!(light_hit(&scene, 0.), Some(1));
+
+		scene.add_object(emissive_triangle(10., 5.));
+		assert_eq!(light_hit(&scene, 10.), Some(2));
+
+		scene.add_light(Box::new(Sun::new(
+			Vec3f::new(0., 1., 0.),
+			0.01,
+			Colour::new(),
+		)));
+		assert_eq!(light_hit(&scene, 0.), Some(2));
+		assert_eq!(light_hit(&scene, 10.), Some(3));
+		assert_eq!(light_hit(&scene, 5.), None);
+	}
+
+	#[test]
+	fn moving_objects_are_hit_where_they_are_at_the_ray_time() {
+		let mut object = triangle(0., 5., 0.);
+		object.set_motion(Motion::offset(Vec3f::new(10., 0., 0.)));
+		let mut scene = Scene::new();
+		scene.add_object(Box::new(object));
+
+		let hit_at = |x: f32, time: f32| {
+			let ray = Ray::new(Vec3f::new(x, 0., 0.), Vec3f::new(0., 0., 1.)).with_time(time);
+			scene.hit(&ray).map(|hit| hit.point)
+		};
+		assert_eq!(hit_at(0., 0.), Some(Vec3f::new(0., 0., 5.)));
+		assert_eq!(hit_at(0., 1.), None);
+		assert_eq!(hit_at(5., 0.5), Some(Vec3f::new(5., 0., 5.)));
+		assert_eq!(hit_at(10., 1.), Some(Vec3f::new(10., 0., 5.)));
+	}
+
+	#[test]
+	fn emissive_objects_stay_still() {
+		let mut light = triangle(0., 5., 1.);
+		light.set_motion(Motion::offset(Vec3f::new(10., 0., 0.)));
+		let mut scene = Scene::new();
+		scene.add_object(Box::new(light));
+
+		assert_eq!(light_hit(&scene, 0.), Some(1));
+		assert!(scene.objects[0].motion().is_static());
+	}
+
+	#[test]
+	fn black_environments_get_no_light_samples() {
+		let environment = |radiance: f32| {
+			let mut image = Image::new(1, 1);
+			image.set_pixel(0, 0, Colour::from_rgb(radiance, radiance, radiance));
+			Box::new(EnvironmentMap::new(image))
+		};
+		let (point, normal) = (Vec3f::new(0., 0., 0.), Vec3f::new(0., 0., 1.));
+
+		for sampling in [
+			LightSampling::Uniform,
+			LightSampling::Power,
+			LightSampling::Bvh,
+		] {
+			let mut scene = Scene::new();
+			scene.set_light_sampling(sampling);
+			scene.set_environment(environment(0.));
+			scene.add_object(emissive_triangle(0., 5.));
+			scene.add_object(emissive_triangle(10., 5.));
+
+			let sampler = scene.light_sampler();
+			assert_eq!(sampler.pmf(point, normal, 0), 0.);
+			let total = sampler.pmf(point, normal, 1) + sampler.pmf(point, normal, 2);
+			assert!((total - 1.).abs() < 1e-5);
+
+			// A lit environment takes its share again.
+			scene.set_environment(environment(1.));
+			assert!(scene.light_sampler().pmf(point, normal, 0) > 0.);
 		}
 	}
 }
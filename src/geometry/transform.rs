@@ -0,0 +1,392 @@
+use super::Vec3f;
+use crate::camera::film_axes;
+
+/// Row-major 4x4 matrix of an affine transformation.
+#[derive(Debug, Clone, Copy)]
+pub struct Transform([[f32; 4]; 4]);
+
+impl Transform {
+	pub const IDENTITY: Self = Transform([
+		[1., 0., 0., 0.],
+		[0., 1., 0., 0.],
+		[0., 0., 1., 0.],
+		[0., 0., 0., 1.],
+	]);
+
+	/// Sixteen values listed column by column, as pbrt does.
+	pub fn from_columns(values: &[f32]) -> Self {
+		let mut m = [[0.; 4]; 4];
+		for (i, v) in values.iter().enumerate() {
+			m[i % 4][i / 4] = *v;
+		}
+		Transform(m)
+	}
+
+	pub fn from_rows(values: &[f32]) -> Self {
+		let mut m = [[0.; 4]; 4];
+		for (i, v) in values.iter().enumerate() {
+			m[i / 4][i % 4] = *v;
+		}
+		Transform(m)
+	}
+
+	pub fn translate(v: Vec3f) -> Self {
+		let mut m = Self::IDENTITY.0;
+		m[0][3] = v.x;
+		m[1][3] = v.y;
+		m[2][3] = v.z;
+		Transform(m)
+	}
+
+	/// Scales by a possibly different factor along each axis.
+	pub fn scale(v: Vec3f) -> Self {
+		let mut m = Self::IDENTITY.0;
+		m[0][0] = v.x;
+		m[1][1] = v.y;
+		m[2][2] = v.z;
+		Transform(m)
+	}
+
+	/// Rotates `degrees` counterclockwise about `axis`, looking down it.
+	pub fn rotate(degrees: f32, axis: Vec3f) -> Self {
+		let a = axis.unit();
+		let (sin, cos) = degrees.to_radians().sin_cos();
+
+		Transform([
+			[
+				a.x * a.x + (1. - a.x * a.x) * cos,
+				a.x * a.y * (1. - cos) - a.z * sin,
+				a.x * a.z * (1. - cos) + a.y * sin,
+				0.,
+			],
+			[
+				a.x * a.y * (1. - cos) + a.z * sin,
+				a.y * a.y + (1. - a.y * a.y) * cos,
+				a.y * a.z * (1. - cos) - a.x * sin,
+				0.,
+			],
+			[
+				a.x * a.z * (1. - cos) - a.y * sin,
+				a.y * a.z * (1. - cos) + a.x * sin,
+				a.z * a.z + (1. - a.z * a.z) * cos,
+				0.,
+			],
+			[0., 0., 0., 1.],
+		])
+	}
+
+	/// Rotates by Euler angles in degrees, about the x axis first, then y,
+	/// then z.
+	pub fn rotate_euler(degrees: Vec3f) -> Self {
+		Transform::rotate(degrees.z, Vec3f::new(0., 0., 1.))
+			.mul(&Transform::rotate(degrees.y, Vec3f::new(0., 1., 0.)))
+			.mul(&Transform::rotate(degrees.x, Vec3f::new(1., 0., 0.)))
+	}
+
+	/// Rotates by the quaternion `x i + y j + z k + w`, normalised first.
+	pub fn rotate_quaternion([x, y, z, w]: [f32; 4]) -> Self {
+		let length = (x * x + y * y + z * z + w * w).sqrt();
+		let [x, y, z, w] = [x, y, z, w].map(|q| q / length);
+
+		Transform([
+			[
+				1. - 2. * (y * y + z * z),
+				2. * (x * y - z * w),
+				2. * (x * z + y * w),
+				0.,
+			],
+			[
+				2. * (x * y + z * w),
+				1. - 2. * (x * x + z * z),
+				2. * (y * z - x * w),
+				0.,
+			],
+			[
+				2. * (x * z - y * w),
+				2. * (y * z + x * w),
+				1. - 2. * (x * x + y * y),
+				0.,
+			],
+			[0., 0., 0., 1.],
+		])
+	}
+
+	/// World to camera transformation of a camera at `eye` looking at
+	/// `target`, with another up vector when `up` is parallel to the view.
+	pub fn look_at(eye: Vec3f, target: Vec3f, up: Vec3f) -> Self {
+		let forward = (target - eye).unit();
+		let (right, _) = film_axes(forward, up.unit());
+		let up = forward.cross(right);
+
+		let row = |axis: Vec3f| [axis.x, axis.y, axis.z, -axis.dot(eye)];
+		Transform([row(right), row(up), row(forward), [0., 0., 0., 1.]])
+	}
+
+	/// Mirror image through the plane at `origin` facing `normal`.
+	pub fn reflection(origin: Vec3f, normal: Vec3f) -> Self {
+		let n = [normal.x, normal.y, normal.z];
+		let d = 2. * origin.dot(normal);
+
+		let mut m = Self::IDENTITY.0;
+		for i in 0..3 {
+			for j in 0..3 {
+				m[i][j] -= 2. * n[i] * n[j];
+			}
+			m[i][3] = d * n[i];
+		}
+		Transform(m)
+	}
+
+	pub fn mul(&self, other: &Self) -> Self {
+		let mut m = [[0.; 4]; 4];
+		for (i, row) in m.iter_mut().enumerate() {
+			for (j, value) in row.iter_mut().enumerate() {
+				*value = (0..4).map(|k| self.0[i][k] * other.0[k][j]).sum();
+			}
+		}
+		Transform(m)
+	}
+
+	pub fn point(&self, p: Vec3f) -> Vec3f {
+		let m = &self.0;
+		let w = m[3][0] * p.x + m[3][1] * p.y + m[3][2] * p.z + m[3][3];
+		(self.vector(p) + Vec3f::new(m[0][3], m[1][3], m[2][3])) / w
+	}
+
+	pub fn vector(&self, v: Vec3f) -> Vec3f {
+		let m = &self.0;
+		Vec3f::new(
+			m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+			m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+			m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+		)
+	}
+
+	/// Transforms a surface normal by the inverse transpose, so it stays
+	/// perpendicular to transformed tangents. The result is unit length.
+	pub fn normal(&self, n: Vec3f) -> Vec3f {
+		let row = |i: usize| Vec3f::new(self.0[i][0], self.0[i][1], self.0[i][2]);
+		let (r0, r1, r2) = (row(0), row(1), row(2));
+
+		// Rows of the cofactor matrix, the inverse transpose times the
+		// determinant.
+		let n = Vec3f::new(
+			r1.cross(r2).dot(n),
+			r2.cross(r0).dot(n),
+			r0.cross(r1).dot(n),
+		);
+		if self.determinant() < 0. {
+			(n * -1.).unit()
+		} else {
+			n.unit()
+		}
+	}
+
+	/// Determinant of the linear part, negative for mirroring transforms.
+	pub fn determinant(&self) -> f32 {
+		let m = &self.0;
+		m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+			- m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+			+ m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+	}
+
+	/// Gauss-Jordan elimination with partial pivoting, `None` when singular
+	/// or not finite.
+	pub fn inverse(&self) -> Option<Self> {
+		if self.0.iter().flatten().any(|v| !v.is_finite()) {
+			return None;
+		}
+		let mut a = self.0;
+		let mut inv = Self::IDENTITY.0;
+
+		for column in 0..4 {
+			let pivot =
+				(column..4).max_by(|i, j| a[*i][column].abs().total_cmp(&a[*j][column].abs()))?;
+			if a[pivot][column].abs() < 1e-12 {
+				return None;
+			}
+			a.swap(column, pivot);
+			inv.swap(column, pivot);
+
+			let scale = 1. / a[column][column];
+			for j in 0..4 {
+				a[column][j] *= scale;
+				inv[column][j] *= scale;
+			}
+			for row in 0..4 {
+				if row != column {
+					let factor = a[row][column];
+					for j in 0..4 {
+						a[row][j] -= factor * a[column][j];
+						inv[row][j] -= factor * inv[column][j];
+					}
+				}
+			}
+		}
+
+		Some(Transform(inv))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::assert_close;
+
+	fn assert_identity(m: &Transform) {
+		for (i, row) in m.0.iter().enumerate() {
+			for (j, value) in row.iter().enumerate() {
+				let expected = if i == j { 1. } else { 0. };
+				assert!((value - expected).abs() < 1e-5, "{:?}", m);
+			}
+		}
+	}
+
+	/// Transformations of every kind, alone and combined.
+	fn transforms() -> Vec<Transform> {
+		let eye = Vec3f::new(1., 2., 3.);
+		let look_at = Transform::look_at(eye, Vec3f::new(-2., 0., 1.), Vec3f::new(0., 1., 0.));
+		let rotation = Transform::rotate(37., Vec3f::new(1., 2., -1.));
+		let scale = Transform::scale(Vec3f::new(2., -0.5, 3.));
+		let translation = Transform::translate(Vec3f::new(-4., 5., 0.5));
+
+		vec![
+			rotation,
+			scale,
+			translation,
+			look_at,
+			Transform::rotate_euler(Vec3f::new(10., 20., 30.)),
+			Transform::rotate_quaternion([1., 2., 3., 4.]),
+			Transform::reflection(eye, Vec3f::new(0., 1., 1.).unit()),
+			translation.mul(&rotation).mul(&scale),
+			look_at.mul(&scale).mul(&translation),
+		]
+	}
+
+	#[test]
+	fn inverse_undoes_the_transform() {
+		for m in transforms() {
+			let inverse = m.inverse().unwrap();
+			assert_identity(&m.mul(&inverse));
+			assert_identity(&inverse.mul(&m));
+		}
+		assert_identity(&Transform::IDENTITY.inverse().unwrap());
+
+		assert!(Transform::scale(Vec3f::new(1., 0., 1.)).inverse().is_none());
+		let flat = Transform::from_rows(&[
+			1., 2., 3., 0., 2., 4., 6., 0., 0., 0., 1., 0., 0., 0., 0., 1.,
+		]);
+		assert!(flat.inverse().is_none());
+
+		let nowhere = Transform::translate(Vec3f::new(f32::NAN, 0., 0.));
+		assert!(nowhere.inverse().is_none());
+		let eye = Vec3f::new(1., 2., 3.);
+		assert!(Transform::look_at(eye, eye, Vec3f::new(0., 1., 0.))
+			.inverse()
+			.is_none());
+	}
+
+	#[test]
+	fn normals_stay_perpendicular() {
+		let tangents = [Vec3f::new(1., 0., 0.), Vec3f::new(0., 1., 1.)];
+		let n = tangents[0].cross(tangents[1]);
+
+		for m in transforms() {
+			let normal = m.normal(n);
+			assert!((normal.len() - 1.).abs() < 1e-5);
+			for t in tangents {
+				assert!(normal.dot(m.vector(t)).abs() < 1e-5);
+			}
+			// The normal keeps to the side it was on, mirrored or not.
+			let side = m.vector(tangents[0]).cross(m.vector(tangents[1]));
+			let sign = if m.determinant() < 0. { -1. } else { 1. };
+			assert!(normal.dot(side) * sign > 0.);
+		}
+	}
+
+	#[test]
+	fn known_answers() {
+		let p = Vec3f::new(1., 2., 3.);
+
+		let rotation = Transform::rotate(90., Vec3f::new(0., 0., 1.));
+		assert_close(
+			rotation.point(Vec3f::new(1., 0., 0.)),
+			Vec3f::new(0., 1., 0.),
+		);
+		assert_close(
+			Transform::rotate_euler(Vec3f::new(0., 0., 90.)).point(p),
+			rotation.point(p),
+		);
+		// A quarter turn about `+x` and then one about `+y`.
+		assert_close(
+			Transform::rotate_euler(Vec3f::new(90., 90., 0.)).point(Vec3f::new(0., 1., 0.)),
+			Vec3f::new(1., 0., 0.),
+		);
+		let half = std::f32::consts::FRAC_PI_4;
+		let quaternion = Transform::rotate_quaternion([0., 0., half.sin() * 2., half.cos() * 2.]);
+		assert_close(quaternion.point(p), rotation.point(p));
+
+		assert_close(Transform::translate(Vec3f::new(1., 1., 1.)).vector(p), p);
+		assert_close(
+			Transform::reflection(Vec3f::new(0., 1., 0.), Vec3f::new(0., 1., 0.)).point(p),
+			Vec3f::new(1., 0., 3.),
+		);
+
+		let eye = Vec3f::new(1., 2., 3.);
+		let look_at = Transform::look_at(eye, Vec3f::new(1., 2., -1.), Vec3f::new(0., 1., 0.));
+		assert_close(look_at.point(eye), Vec3f::new(0., 0., 0.));
+		assert_close(
+			look_at.point(Vec3f::new(1., 2., 1.)),
+			Vec3f::new(0., 0., 2.),
+		);
+		assert_close(
+			look_at.vector(Vec3f::new(0., 1., 0.)),
+			Vec3f::new(0., 1., 0.),
+		);
+		// Looking straight down the up vector still gives a rotation.
+		let down = Transform::look_at(eye, Vec3f::new(1., -2., 3.), Vec3f::new(0., 1., 0.));
+		assert_close(down.point(Vec3f::new(1., 0., 3.)), Vec3f::new(0., 0., 2.));
+		assert!((down.determinant() - 1.).abs() < 1e-5);
+
+		let values: Vec<f32> = (0..16).map(|v| v as f32).collect();
+		let rows = Transform::from_rows(&values);
+		let columns = Transform::from_columns(&values);
+		assert_eq!(rows.point(Vec3f::new(0., 0., 0.)).x, 3. / 15.);
+		assert_eq!(columns.point(Vec3f::new(0., 0., 0.)).x, 12. / 15.);
+	}
+
+	#[test]
+	fn divides_points_by_w() {
+		// Perspective division by the distance along `z`.
+		let projection = Transform::from_rows(&[
+			1., 0., 0., 0., 0., 1., 0., 0., 0., 0., 1., 0., 0., 0., 1., 0.,
+		]);
+		assert_close(
+			projection.point(Vec3f::new(2., 4., 2.)),
+			Vec3f::new(1., 2., 1.),
+		);
+		// Vectors are directions and are not divided.
+		assert_close(
+			projection.vector(Vec3f::new(2., 4., 2.)),
+			Vec3f::new(2., 4., 2.),
+		);
+	}
+
+	#[test]
+	fn determinant_of_the_linear_part() {
+		assert_eq!(Transform::IDENTITY.determinant(), 1.);
+		assert_eq!(
+			Transform::scale(Vec3f::new(2., 3., -4.)).determinant(),
+			-24.
+		);
+		assert_eq!(
+			Transform::translate(Vec3f::new(5., 6., 7.)).determinant(),
+			1.
+		);
+		assert!((Transform::rotate(33., Vec3f::new(1., 1., 0.)).determinant() - 1.).abs() < 1e-5);
+		assert!(
+			Transform::reflection(Vec3f::new(0., 0., 0.), Vec3f::new(1., 0., 0.)).determinant()
+				< 0.
+		);
+	}
+}
@@ -1,7 +1,7 @@
 use std::ops::{Add, Div, Mul, Sub};
 
 use num::Float;
-use rand::{distributions::uniform::SampleUniform, Rng};
+use rand::distributions::uniform::SampleUniform;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Vec3<T> {
@@ -35,22 +35,13 @@ where
 		}
 	}
 
-	pub fn rand(min: T, max: T) -> Self {
-		let mut rng = rand::thread_rng();
-		Self::new(
-			rng.gen_range(min..max),
-			rng.gen_range(min..max),
-			rng.gen_range(min..max),
-		)
-	}
-
-	pub fn rand_in_unit() -> Self {
-		loop {
-			let v = Self::rand(-T::one(), T::one());
-			if v.len_sq() < T::one() {
-				return v;
-			}
-		}
+	/// Uniformly distributed direction from a 2D sample in `[0, 1)`.
+	pub fn on_unit_sphere(u: (T, T)) -> Self {
+		let two = T::one() + T::one();
+		let z = T::one() - two * u.0;
+		let r = (T::one() - z * z).max(T::zero()).sqrt();
+		let phi = two * T::from(std::f64::consts::PI).unwrap() * u.1;
+		Self::new(r * phi.cos(), r * phi.sin(), z)
 	}
 
 	pub fn reflect(self, n: Vec3<T>) -> Self {
@@ -9,18 +9,18 @@ impl super::ImageFormat for Bmp {
 		let offset = 54;
 		let mut data: Vec<u8> = Vec::with_capacity((pixel_array_size + offset) as usize);
 
-		data.write(&[b'B', b'M'])?;
-		data.write(&(pixel_array_size + offset).to_le_bytes())?;
-		data.write(&(0 as u32).to_le_bytes())?;
-		data.write(&(offset as u32).to_le_bytes())?;
-		data.write(&(40 as u32).to_le_bytes())?;
-		data.write(&(image.width as i32).to_le_bytes())?;
-		data.write(&(-(image.height as i32)).to_le_bytes())?;
-		data.write(&(1 as u16).to_le_bytes())?;
-		data.write(&(24 as u16).to_le_bytes())?;
-		data.write(&(0 as u32).to_le_bytes())?;
-		data.write(&(pixel_array_size).to_le_bytes())?;
-		data.write(&(0 as u128).to_le_bytes())?;
+		data.write_all(b"BM")?;
+		data.write_all(&(pixel_array_size + offset).to_le_bytes())?;
+		data.write_all(&0u32.to_le_bytes())?;
+		data.write_all(&offset.to_le_bytes())?;
+		data.write_all(&40u32.to_le_bytes())?;
+		data.write_all(&(image.width as i32).to_le_bytes())?;
+		data.write_all(&(-(image.height as i32)).to_le_bytes())?;
+		data.write_all(&1u16.to_le_bytes())?;
+		data.write_all(&24u16.to_le_bytes())?;
+		data.write_all(&0u32.to_le_bytes())?;
+		data.write_all(&(pixel_array_size).to_le_bytes())?;
+		data.write_all(&0u128.to_le_bytes())?;
 
 		data.resize((pixel_array_size + offset) as usize, 0);
 
@@ -35,9 +35,7 @@ impl super::ImageFormat for Bmp {
 
 			if x == 0 && y != 0 && extra_bytes > 0 {
 				let fill_offset = (offset + y * row_bytes + image.width * 3) as usize;
-				for i in fill_offset..(fill_offset + extra_bytes as usize) {
-					data[i] = 0;
-				}
+				data[fill_offset..(fill_offset + extra_bytes as usize)].fill(0);
 			}
 		}
 
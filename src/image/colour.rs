@@ -24,6 +24,10 @@ impl Colour {
 		}
 	}
 
+	pub fn luminance(&self) -> f32 {
+		0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+	}
+
 	pub fn sqrt(self) -> Self {
 		Self {
 			r: self.r.sqrt(),
@@ -33,6 +37,21 @@ impl Colour {
 	}
 }
 
+/// Linear value of an sRGB encoded one in `[0, 1]`.
+pub fn srgb_to_linear(c: f32) -> f32 {
+	if c <= 0.04045 {
+		c / 12.92
+	} else {
+		((c + 0.055) / 1.055).powf(2.4)
+	}
+}
+
+impl From<[f32; 3]> for Colour {
+	fn from(c: [f32; 3]) -> Self {
+		Self::from_rgb(c[0], c[1], c[2])
+	}
+}
+
 macro_rules! impl_f32_math {
 	($f:tt, $fn:tt) => {
 		impl $f<f32> for Colour {
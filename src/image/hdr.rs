@@ -0,0 +1,185 @@
+use std::io::{self, ErrorKind};
+
+use super::{Colour, Image};
+
+/// Radiance RGBE (`.hdr`) images, both flat and run-length encoded.
+pub struct Hdr {}
+
+fn invalid<T>(message: &str) -> io::Result<T> {
+	Err(io::Error::new(ErrorKind::InvalidData, message))
+}
+
+fn read_line<'a>(data: &'a [u8], pos: &mut usize) -> io::Result<&'a str> {
+	let start = *pos;
+	let end = data[start..]
+		.iter()
+		.position(|b| *b == b'\n')
+		.map(|i| start + i)
+		.ok_or_else(|| io::Error::new(ErrorKind::UnexpectedEof, "Unterminated header"))?;
+	*pos = end + 1;
+
+	std::str::from_utf8(&data[start..end])
+		.map_err(|_| io::Error::new(ErrorKind::InvalidData, "Header is not valid text"))
+}
+
+fn rgbe_to_colour(p: &[u8]) -> Colour {
+	if p[3] == 0 {
+		return Colour::new();
+	}
+
+	let f = 2f32.powi(p[3] as i32 - (128 + 8));
+	Colour::from_rgb(
+		(p[0] as f32 + 0.5) * f,
+		(p[1] as f32 + 0.5) * f,
+		(p[2] as f32 + 0.5) * f,
+	)
+}
+
+fn read_scanline(data: &[u8], pos: &mut usize, width: usize, out: &mut [u8]) -> io::Result<()> {
+	let rle = (8..0x8000).contains(&width)
+		&& data.len() >= *pos + 4
+		&& data[*pos] == 2
+		&& data[*pos + 1] == 2
+		&& data[*pos + 2] & 0x80 == 0;
+
+	if !rle {
+		let bytes = width * 4;
+		if data.len() < *pos + bytes {
+			return invalid("Truncated scanline");
+		}
+		out.copy_from_slice(&data[*pos..*pos + bytes]);
+		*pos += bytes;
+		return Ok(());
+	}
+
+	if ((data[*pos + 2] as usize) << 8 | data[*pos + 3] as usize) != width {
+		return invalid("Scanline width mismatch");
+	}
+	*pos += 4;
+
+	for channel in 0..4 {
+		let mut x = 0;
+		while x < width {
+			let count = *data
+				.get(*pos)
+				.ok_or_else(|| io::Error::from(ErrorKind::UnexpectedEof))?;
+			*pos += 1;
+
+			if count > 128 {
+				let count = (count - 128) as usize;
+				let value = *data
+					.get(*pos)
+					.ok_or_else(|| io::Error::from(ErrorKind::UnexpectedEof))?;
+				*pos += 1;
+				if x + count > width {
+					return invalid("Run exceeds scanline");
+				}
+				for i in x..x + count {
+					out[i * 4 + channel] = value;
+				}
+				x += count;
+			} else {
+				let count = count as usize;
+				if count == 0 || x + count > width || data.len() < *pos + count {
+					return invalid("Bad scanline run");
+				}
+				for i in 0..count {
+					out[(x + i) * 4 + channel] = data[*pos + i];
+				}
+				*pos += count;
+				x += count;
+			}
+		}
+	}
+
+	Ok(())
+}
+
+impl super::ImageDecoder for Hdr {
+	fn decode(data: &[u8]) -> io::Result<Image> {
+		let mut pos = 0;
+
+		if !read_line(data, &mut pos)?.starts_with("#?") {
+			return invalid("Not a Radiance HDR file");
+		}
+
+		loop {
+			let line = read_line(data, &mut pos)?;
+			if line.is_empty() {
+				break;
+			}
+			if let Some(format) = line.strip_prefix("FORMAT=") {
+				if format != "32-bit_rle_rgbe" {
+					return invalid("Unsupported pixel format");
+				}
+			}
+		}
+
+		let resolution: Vec<&str> = read_line(data, &mut pos)?.split_whitespace().collect();
+		let (height, width) = match resolution[..] {
+			["-Y", h, "+X", w] => (h.parse::<u32>(), w.parse::<u32>()),
+			_ => return invalid("Unsupported image orientation"),
+		};
+		let (height, width) = match (height, width) {
+			(Ok(h), Ok(w)) if h > 0 && w > 0 => (h, w),
+			_ => return invalid("Bad image resolution"),
+		};
+
+		let mut image = Image::new(width, height);
+		let mut scanline = vec![0u8; width as usize * 4];
+
+		for y in 0..height {
+			read_scanline(data, &mut pos, width as usize, &mut scanline)?;
+			for (x, p) in scanline.chunks_exact(4).enumerate() {
+				image.set_pixel(x as u32, y, rgbe_to_colour(p));
+			}
+		}
+
+		Ok(image)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::image::ImageDecoder;
+
+	const HEADER: &[u8] = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n";
+
+	fn assert_rgb(c: &Colour, r: f32, g: f32, b: f32) {
+		assert_eq!((c.r, c.g, c.b), (r, g, b));
+	}
+
+	#[test]
+	fn decodes_flat_pixels() {
+		let data = [HEADER, b"-Y 1 +X 2\n", &[128, 64, 0, 129, 255, 255, 255, 0]].concat();
+		let image = Hdr::decode(&data).unwrap();
+
+		assert_eq!((image.width(), image.height()), (2, 1));
+		assert_rgb(image.get_pixel(0, 0), 128.5 / 128., 64.5 / 128., 0.5 / 128.);
+		assert_rgb(image.get_pixel(1, 0), 0., 0., 0.);
+	}
+
+	#[test]
+	fn decodes_run_length_encoded_scanlines() {
+		let mut data = [HEADER, b"-Y 1 +X 8\n", &[2, 2, 0, 8]].concat();
+		// Red as literal values, the other channels as runs.
+		data.extend([8, 0, 1, 2, 3, 4, 5, 6, 7]);
+		data.extend([128 + 8, 64, 128 + 8, 0, 128 + 8, 136]);
+		let image = Hdr::decode(&data).unwrap();
+
+		assert_eq!((image.width(), image.height()), (8, 1));
+		for x in 0..8 {
+			assert_rgb(image.get_pixel(x, 0), x as f32 + 0.5, 64.5, 0.5);
+		}
+	}
+
+	#[test]
+	fn rejects_bad_files() {
+		assert!(Hdr::decode(b"P6\n2 1\n255\n").is_err());
+		assert!(Hdr::decode(&[HEADER, b"+X 2 -Y 1\n"].concat()).is_err());
+		assert!(Hdr::decode(&[HEADER, b"-Y 1 +X 2\n", &[1, 2, 3]].concat()).is_err());
+		let run_too_long = [HEADER, b"-Y 1 +X 8\n", &[2, 2, 0, 8, 128 + 9, 0]].concat();
+		assert!(Hdr::decode(&run_too_long).is_err());
+	}
+}
@@ -6,9 +6,14 @@ use coordinates::*;
 mod colour;
 pub use colour::*;
 
+mod texture;
+pub use texture::*;
+
 mod bmp;
+mod hdr;
 pub mod formats {
 	pub use super::bmp::*;
+	pub use super::hdr::*;
 }
 
 type ImageIndexCapacity = u32;
@@ -23,6 +28,10 @@ pub trait ImageFormat {
 	fn encode(image: &Image) -> io::Result<Vec<u8>>;
 }
 
+pub trait ImageDecoder {
+	fn decode(data: &[u8]) -> io::Result<Image>;
+}
+
 impl Image {
 	pub fn new(width: ImageIndexCapacity, height: ImageIndexCapacity) -> Self {
 		Self {
@@ -32,6 +41,14 @@ impl Image {
 		}
 	}
 
+	pub fn width(&self) -> ImageIndexCapacity {
+		self.width
+	}
+
+	pub fn height(&self) -> ImageIndexCapacity {
+		self.height
+	}
+
 	pub fn coordinates(&self) -> ImageCoordinates {
 		self.into()
 	}
@@ -44,7 +61,24 @@ impl Image {
 		&self.data[(x + self.width * y) as usize]
 	}
 
-	pub fn into_u8(&self) -> Vec<u8> {
+	/// Copies the `width` by `height` region starting at `(x, y)`.
+	pub fn crop(
+		&self,
+		x: ImageIndexCapacity,
+		y: ImageIndexCapacity,
+		width: ImageIndexCapacity,
+		height: ImageIndexCapacity,
+	) -> Image {
+		let mut image = Image::new(width, height);
+		for (i, j) in image.coordinates().collect::<Vec<_>>() {
+			image.set_pixel(i, j, self.get_pixel(x + i, y + j).clone());
+		}
+
+		image
+	}
+
+	#[cfg_attr(not(feature = "preview"), allow(dead_code))]
+	pub fn to_u8(&self) -> Vec<u8> {
 		let mut data: Vec<u8> = vec![0; (self.width * self.height * 4) as usize];
 
 		let row_bytes = self.width * 4;
@@ -61,3 +95,22 @@ impl Image {
 		data
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn crops_a_region() {
+		let mut image = Image::new(4, 3);
+		for (x, y) in image.coordinates().collect::<Vec<_>>() {
+			image.set_pixel(x, y, Colour::from_rgb(x as f32, y as f32, 0.));
+		}
+
+		let right = image.crop(2, 1, 2, 2);
+		assert_eq!((right.width(), right.height()), (2, 2));
+		assert_eq!(right.get_pixel(0, 0).r, 2.);
+		assert_eq!(right.get_pixel(1, 1).r, 3.);
+		assert_eq!(right.get_pixel(1, 1).g, 2.);
+	}
+}
@@ -0,0 +1,63 @@
+use std::{
+	fmt,
+	io::{self, ErrorKind},
+	path::Path,
+};
+
+use super::{srgb_to_linear, Colour, Image};
+
+/// Colour image wrapped over a surface by its texture coordinates.
+pub struct Texture {
+	image: Image,
+}
+
+impl Texture {
+	/// Opens a PNG or JPEG image, taking its 8-bit values as sRGB.
+	pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+		let decoded = image_rs::open(path)
+			.map_err(|e| match e {
+				image_rs::ImageError::IoError(e) => e,
+				e => io::Error::new(ErrorKind::InvalidData, e),
+			})?
+			.to_rgb8();
+
+		let mut image = Image::new(decoded.width(), decoded.height());
+		for (x, y, pixel) in decoded.enumerate_pixels() {
+			let [r, g, b] = pixel.0.map(|c| srgb_to_linear(c as f32 / 255.));
+			image.set_pixel(x, y, Colour::from_rgb(r, g, b));
+		}
+
+		Ok(Self { image })
+	}
+
+	/// Bilinearly filtered colour at `(u, v)`, repeating outside `[0, 1]²`.
+	/// `v` runs up from the bottom of the image.
+	pub fn sample(&self, (u, v): (f32, f32)) -> Colour {
+		let (width, height) = (self.image.width(), self.image.height());
+		let x = u.rem_euclid(1.) * width as f32 - 0.5;
+		let y = (1. - v.rem_euclid(1.)) * height as f32 - 0.5;
+
+		let (x0, y0) = (x.floor(), y.floor());
+		let (tx, ty) = (x - x0, y - y0);
+		let texel = |x: f32, y: f32| {
+			self.image
+				.get_pixel(
+					(x as i64).rem_euclid(width as i64) as u32,
+					(y as i64).rem_euclid(height as i64) as u32,
+				)
+				.clone()
+		};
+
+		(texel(x0, y0) * (1. - tx) + texel(x0 + 1., y0) * tx) * (1. - ty)
+			+ (texel(x0, y0 + 1.) * (1. - tx) + texel(x0 + 1., y0 + 1.) * tx) * ty
+	}
+}
+
+impl fmt::Debug for Texture {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Texture")
+			.field("width", &self.image.width())
+			.field("height", &self.image.height())
+			.finish()
+	}
+}
@@ -0,0 +1,116 @@
+use std::f32::consts::PI;
+
+use crate::{
+	geometry::{Intersect, Polygon3, Ray, Vec3f},
+	image::Colour,
+};
+
+use super::{DirectionCone, Emitter, LightBounds, LightSample};
+
+/// One triangle of an emissive object, emitting from both sides.
+pub struct AreaLight {
+	polygon: Polygon3,
+	area: f32,
+	radiance: Colour,
+}
+
+impl AreaLight {
+	pub fn new(polygon: Polygon3, radiance: Colour) -> Self {
+		Self {
+			area: (polygon.b - polygon.a).cross(polygon.c - polygon.a).len() / 2.,
+			polygon,
+			radiance,
+		}
+	}
+
+	fn solid_angle_pdf(&self, point: Vec3f, target: Vec3f) -> f32 {
+		let d = target - point;
+		let distance_sq = d.len_sq();
+		let cos = self.polygon.normal.dot(d.unit()).abs();
+		if cos == 0. || self.area == 0. {
+			return 0.;
+		}
+
+		distance_sq / (cos * self.area)
+	}
+}
+
+impl Emitter for AreaLight {
+	fn sample(&self, point: Vec3f, u: (f32, f32)) -> Option<LightSample> {
+		let su = u.0.sqrt();
+		let (b0, b1) = (1. - su, u.1 * su);
+		let p = self.polygon.a * b0 + self.polygon.b * b1 + self.polygon.c * (1. - b0 - b1);
+
+		let pdf = self.solid_angle_pdf(point, p);
+		if pdf == 0. || !pdf.is_finite() {
+			return None;
+		}
+
+		Some(LightSample {
+			direction: (p - point).unit(),
+			distance: (p - point).len(),
+			radiance: self.radiance.clone(),
+			pdf,
+		})
+	}
+
+	fn pdf(&self, point: Vec3f, direction: Vec3f) -> f32 {
+		match self.polygon.intersect(&Ray::new(point, direction)) {
+			Some(p) => self.solid_angle_pdf(point, p),
+			None => 0.,
+		}
+	}
+
+	fn bounds(&self) -> Option<LightBounds> {
+		let Polygon3 { a, b, c, .. } = self.polygon;
+		let min = Vec3f::new(
+			a.x.min(b.x).min(c.x),
+			a.y.min(b.y).min(c.y),
+			a.z.min(b.z).min(c.z),
+		);
+		let max = Vec3f::new(
+			a.x.max(b.x).max(c.x),
+			a.y.max(b.y).max(c.y),
+			a.z.max(b.z).max(c.z),
+		);
+
+		Some(LightBounds {
+			min,
+			max,
+			phi: 2. * PI * self.area * self.radiance.luminance(),
+			normals: DirectionCone::new(self.polygon.normal, 1.),
+			cos_theta_e: 0.,
+			two_sided: true,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn samples_carry_their_solid_angle_density() {
+		let light = AreaLight::new(
+			Polygon3::new(
+				Vec3f::new(-1., 2., -1.),
+				Vec3f::new(1., 2., -1.),
+				Vec3f::new(0., 2., 1.),
+			),
+			Colour::from_rgb(1., 1., 1.),
+		);
+		let point = Vec3f::new(0.2, 0., 0.1);
+
+		for k in 0..25 {
+			let u = ((k % 5) as f32 / 5. + 0.1, (k / 5) as f32 / 5. + 0.1);
+			let sample = light.sample(point, u).unwrap();
+			let pdf = light.pdf(point, sample.direction);
+			assert!((sample.pdf - pdf).abs() < 1e-4 * pdf);
+		}
+
+		// Straight below the centre: distance 2, area 2, facing the point.
+		let pdf = light.pdf(point, Vec3f::new(0., 1., 0.));
+		assert!((pdf - 2.).abs() < 1e-5);
+		assert_eq!(light.pdf(point, Vec3f::new(0., -1., 0.)), 0.);
+	}
+}
@@ -0,0 +1,370 @@
+use std::f32::consts::PI;
+
+use crate::geometry::Vec3f;
+
+use super::{Distribution1D, LightInfo, LightSampler};
+
+/// Set of directions within `acos(cos_theta)` of `w`.
+#[derive(Debug, Clone, Copy)]
+pub struct DirectionCone {
+	pub w: Vec3f,
+	pub cos_theta: f32,
+}
+
+fn cos_sub_clamped(sin_a: f32, cos_a: f32, sin_b: f32, cos_b: f32) -> f32 {
+	if cos_a > cos_b {
+		1.
+	} else {
+		cos_a * cos_b + sin_a * sin_b
+	}
+}
+
+fn sin_sub_clamped(sin_a: f32, cos_a: f32, sin_b: f32, cos_b: f32) -> f32 {
+	if cos_a > cos_b {
+		0.
+	} else {
+		sin_a * cos_b - cos_a * sin_b
+	}
+}
+
+fn safe_sqrt(x: f32) -> f32 {
+	x.max(0.).sqrt()
+}
+
+impl DirectionCone {
+	pub fn new(w: Vec3f, cos_theta: f32) -> Self {
+		Self {
+			w: w.unit(),
+			cos_theta,
+		}
+	}
+
+	pub fn entire_sphere() -> Self {
+		Self::new(Vec3f::new(0., 0., 1.), -1.)
+	}
+
+	pub fn union(self, other: Self) -> Self {
+		let theta_a = self.cos_theta.clamp(-1., 1.).acos();
+		let theta_b = other.cos_theta.clamp(-1., 1.).acos();
+		let theta_d = self.w.dot(other.w).clamp(-1., 1.).acos();
+
+		if (theta_d + theta_b).min(PI) <= theta_a {
+			return self;
+		}
+		if (theta_d + theta_a).min(PI) <= theta_b {
+			return other;
+		}
+
+		let theta_o = (theta_a + theta_d + theta_b) / 2.;
+		if theta_o >= PI {
+			return Self::entire_sphere();
+		}
+
+		let axis = self.w.cross(other.w);
+		if axis.len_sq() == 0. {
+			return Self::entire_sphere();
+		}
+
+		let k = axis.unit();
+		let theta_r = theta_o - theta_a;
+		let w = self.w * theta_r.cos()
+			+ k.cross(self.w) * theta_r.sin()
+			+ k * (k.dot(self.w) * (1. - theta_r.cos()));
+
+		Self::new(w, theta_o.cos())
+	}
+}
+
+/// Spatial and directional extent of the light a group of emitters sends out,
+/// used to estimate how much of it reaches a shading point.
+#[derive(Debug, Clone, Copy)]
+pub struct LightBounds {
+	pub min: Vec3f,
+	pub max: Vec3f,
+	pub phi: f32,
+	pub normals: DirectionCone,
+	/// Emission falls off to zero this far beyond the normal cone.
+	pub cos_theta_e: f32,
+	pub two_sided: bool,
+}
+
+impl LightBounds {
+	pub fn union(self, other: Self) -> Self {
+		if self.phi == 0. {
+			return other;
+		}
+		if other.phi == 0. {
+			return self;
+		}
+
+		Self {
+			min: Vec3f::new(
+				self.min.x.min(other.min.x),
+				self.min.y.min(other.min.y),
+				self.min.z.min(other.min.z),
+			),
+			max: Vec3f::new(
+				self.max.x.max(other.max.x),
+				self.max.y.max(other.max.y),
+				self.max.z.max(other.max.z),
+			),
+			phi: self.phi + other.phi,
+			normals: self.normals.union(other.normals),
+			cos_theta_e: self.cos_theta_e.min(other.cos_theta_e),
+			two_sided: self.two_sided || other.two_sided,
+		}
+	}
+
+	fn centroid(&self) -> Vec3f {
+		(self.min + self.max) / 2.
+	}
+
+	/// Conservative estimate of the power arriving at `point` on a surface
+	/// with `normal`.
+	pub fn importance(&self, point: Vec3f, normal: Vec3f) -> f32 {
+		let pc = self.centroid();
+		let diagonal = (self.max - self.min).len();
+		let distance_sq = (point - pc).len_sq().max(diagonal / 2.);
+
+		let wi = (point - pc).unit();
+		let mut cos_theta_w = self.normals.w.dot(wi);
+		if self.two_sided {
+			cos_theta_w = cos_theta_w.abs();
+		}
+		if cos_theta_w.is_nan() {
+			cos_theta_w = 1.;
+		}
+		let sin_theta_w = safe_sqrt(1. - cos_theta_w * cos_theta_w);
+
+		let radius_sq = (diagonal / 2.) * (diagonal / 2.);
+		let center_distance_sq = (point - pc).len_sq();
+		let cos_theta_b = if center_distance_sq < radius_sq {
+			-1.
+		} else {
+			safe_sqrt(1. - radius_sq / center_distance_sq)
+		};
+		let sin_theta_b = safe_sqrt(1. - cos_theta_b * cos_theta_b);
+
+		let cos_theta_o = self.normals.cos_theta;
+		let sin_theta_o = safe_sqrt(1. - cos_theta_o * cos_theta_o);
+		let cos_theta_x = cos_sub_clamped(sin_theta_w, cos_theta_w, sin_theta_o, cos_theta_o);
+		let sin_theta_x = sin_sub_clamped(sin_theta_w, cos_theta_w, sin_theta_o, cos_theta_o);
+		let cos_theta_p = cos_sub_clamped(sin_theta_x, cos_theta_x, sin_theta_b, cos_theta_b);
+		if cos_theta_p <= self.cos_theta_e {
+			return 0.;
+		}
+
+		let mut importance = self.phi * cos_theta_p / distance_sq;
+
+		if normal.len_sq() > 0. {
+			let cos_theta_i = wi.dot(normal.unit()).abs();
+			if !cos_theta_i.is_nan() {
+				let sin_theta_i = safe_sqrt(1. - cos_theta_i * cos_theta_i);
+				importance *= cos_sub_clamped(sin_theta_i, cos_theta_i, sin_theta_b, cos_theta_b);
+			}
+		}
+
+		importance.max(0.)
+	}
+}
+
+enum Node {
+	Leaf(usize),
+	/// The first child directly follows its parent, the second one is at the
+	/// stored index.
+	Interior(usize),
+}
+
+/// Bounding volume hierarchy over finite lights, traversed stochastically by
+/// each child's importance to the shading point. Infinite lights are picked
+/// besides it by their share of the total power.
+pub struct LightBvh {
+	infinite: Vec<usize>,
+	/// Power of the infinite lights, `None` when they send none.
+	infinite_power: Option<Distribution1D>,
+	/// Chance of picking an infinite light rather than going down the tree.
+	infinite_share: f32,
+	nodes: Vec<(LightBounds, Node)>,
+	/// Branch taken at each level to reach a light, lowest bit first.
+	trails: Vec<u64>,
+}
+
+impl LightBvh {
+	pub fn new(lights: &[LightInfo]) -> Self {
+		let (infinite, power): (Vec<usize>, Vec<f32>) = lights
+			.iter()
+			.enumerate()
+			.filter_map(|(i, light)| match light {
+				LightInfo::Infinite(phi) => Some((i, *phi)),
+				LightInfo::Finite(_) => None,
+			})
+			.unzip();
+		let mut finite: Vec<(usize, LightBounds)> = lights
+			.iter()
+			.enumerate()
+			.filter_map(|(i, light)| match light {
+				LightInfo::Finite(b) if b.phi > 0. => Some((i, *b)),
+				_ => None,
+			})
+			.collect();
+		let infinite_phi: f32 = power.iter().sum();
+		let infinite_power = Some(Distribution1D::new(power)).filter(|d| d.integral() > 0.);
+
+		let mut bvh = Self {
+			infinite,
+			infinite_power,
+			infinite_share: 0.,
+			nodes: Vec::new(),
+			trails: vec![0; lights.len()],
+		};
+		let finite_phi = if finite.is_empty() {
+			0.
+		} else {
+			bvh.build(&mut finite, 0, 0).phi
+		};
+		if infinite_phi > 0. {
+			bvh.infinite_share = infinite_phi / (infinite_phi + finite_phi);
+		}
+
+		bvh
+	}
+
+	fn build(
+		&mut self,
+		lights: &mut [(usize, LightBounds)],
+		trail: u64,
+		depth: u32,
+	) -> LightBounds {
+		if lights.len() == 1 || depth == 63 {
+			let (index, bounds) = lights[0];
+			self.nodes.push((bounds, Node::Leaf(index)));
+			self.trails[index] = trail;
+			return bounds;
+		}
+
+		let (min, max) = lights.iter().fold(
+			(lights[0].1.centroid(), lights[0].1.centroid()),
+			|(min, max), (_, b)| {
+				let c = b.centroid();
+				(
+					Vec3f::new(min.x.min(c.x), min.y.min(c.y), min.z.min(c.z)),
+					Vec3f::new(max.x.max(c.x), max.y.max(c.y), max.z.max(c.z)),
+				)
+			},
+		);
+		let extent = max - min;
+		let axis = |v: Vec3f| {
+			if extent.x >= extent.y && extent.x >= extent.z {
+				v.x
+			} else if extent.y >= extent.z {
+				v.y
+			} else {
+				v.z
+			}
+		};
+		lights.sort_by(|a, b| axis(a.1.centroid()).total_cmp(&axis(b.1.centroid())));
+
+		let node = self.nodes.len();
+		self.nodes.push((lights[0].1, Node::Interior(0)));
+
+		let (first, second) = lights.split_at_mut(lights.len() / 2);
+		let first = self.build(first, trail, depth + 1);
+		let second_index = self.nodes.len();
+		let second = self.build(second, trail | 1 << depth, depth + 1);
+
+		let bounds = first.union(second);
+		self.nodes[node] = (bounds, Node::Interior(second_index));
+		bounds
+	}
+}
+
+impl LightSampler for LightBvh {
+	fn pick(&self, point: Vec3f, normal: Vec3f, mut u: f32) -> Option<(usize, f32)> {
+		let infinite_share = self.infinite_share;
+		if u < infinite_share {
+			let (i, pmf) = self
+				.infinite_power
+				.as_ref()?
+				.sample_discrete(u / infinite_share);
+			return Some((self.infinite[i], pmf * infinite_share));
+		}
+		if self.nodes.is_empty() {
+			return None;
+		}
+
+		u = ((u - infinite_share) / (1. - infinite_share)).min(1. - f32::EPSILON);
+		let mut pmf = 1. - infinite_share;
+		let mut node = 0;
+
+		loop {
+			match self.nodes[node].1 {
+				Node::Leaf(index) => {
+					if node > 0 || self.nodes[node].0.importance(point, normal) > 0. {
+						return Some((index, pmf));
+					}
+					return None;
+				}
+				Node::Interior(second) => {
+					let c0 = self.nodes[node + 1].0.importance(point, normal);
+					let c1 = self.nodes[second].0.importance(point, normal);
+					if c0 == 0. && c1 == 0. {
+						return None;
+					}
+
+					let p0 = c0 / (c0 + c1);
+					if u < p0 {
+						node += 1;
+						pmf *= p0;
+						u = (u / p0).min(1. - f32::EPSILON);
+					} else {
+						node = second;
+						pmf *= 1. - p0;
+						u = ((u - p0) / (1. - p0)).min(1. - f32::EPSILON);
+					}
+				}
+			}
+		}
+	}
+
+	fn pmf(&self, point: Vec3f, normal: Vec3f, index: usize) -> f32 {
+		if let Ok(i) = self.infinite.binary_search(&index) {
+			return match &self.infinite_power {
+				Some(power) => power.discrete_pdf(i) * self.infinite_share,
+				None => 0.,
+			};
+		}
+		if self.nodes.is_empty() {
+			return 0.;
+		}
+
+		let trail = self.trails[index];
+		let mut pmf = 1. - self.infinite_share;
+		let mut node = 0;
+		let mut depth = 0;
+
+		loop {
+			match self.nodes[node].1 {
+				Node::Leaf(leaf) => {
+					let reachable = node > 0 || self.nodes[node].0.importance(point, normal) > 0.;
+					return if leaf == index && reachable { pmf } else { 0. };
+				}
+				Node::Interior(second) => {
+					let c0 = self.nodes[node + 1].0.importance(point, normal);
+					let c1 = self.nodes[second].0.importance(point, normal);
+					if c0 == 0. && c1 == 0. {
+						return 0.;
+					}
+
+					if trail >> depth & 1 == 0 {
+						pmf *= c0 / (c0 + c1);
+						node += 1;
+					} else {
+						pmf *= c1 / (c0 + c1);
+						node = second;
+					}
+					depth += 1;
+				}
+			}
+		}
+	}
+}
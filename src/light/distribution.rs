@@ -0,0 +1,199 @@
+/// Piecewise-constant 1D distribution over `[0, 1)`, sampled by inverting its
+/// CDF.
+#[derive(Debug, Clone)]
+pub struct Distribution1D {
+	func: Vec<f32>,
+	cdf: Vec<f32>,
+	integral: f32,
+}
+
+impl Distribution1D {
+	pub fn new(func: Vec<f32>) -> Self {
+		let n = func.len();
+		let mut cdf = vec![0.; n + 1];
+		for i in 1..=n {
+			cdf[i] = cdf[i - 1] + func[i - 1].abs() / n as f32;
+		}
+
+		let integral = cdf[n];
+		if integral == 0. {
+			for (i, c) in cdf.iter_mut().enumerate().skip(1) {
+				*c = i as f32 / n as f32;
+			}
+		} else {
+			for c in cdf.iter_mut().skip(1) {
+				*c /= integral;
+			}
+		}
+
+		Self {
+			func,
+			cdf,
+			integral,
+		}
+	}
+
+	pub fn count(&self) -> usize {
+		self.func.len()
+	}
+
+	pub fn integral(&self) -> f32 {
+		self.integral
+	}
+
+	fn find(&self, u: f32) -> usize {
+		let i = self.cdf.partition_point(|c| *c <= u);
+		i.saturating_sub(1).min(self.count() - 1)
+	}
+
+	/// Returns a point in `[0, 1)`, its density and the index of the segment it
+	/// falls into.
+	pub fn sample_continuous(&self, u: f32) -> (f32, f32, usize) {
+		let i = self.find(u);
+
+		let mut du = u - self.cdf[i];
+		let width = self.cdf[i + 1] - self.cdf[i];
+		if width > 0. {
+			du /= width;
+		}
+
+		let pdf = if self.integral > 0. {
+			self.func[i].abs() / self.integral
+		} else {
+			0.
+		};
+
+		((i as f32 + du) / self.count() as f32, pdf, i)
+	}
+
+	/// Returns a segment index and the probability of picking it.
+	pub fn sample_discrete(&self, u: f32) -> (usize, f32) {
+		let i = self.find(u);
+		(i, self.discrete_pdf(i))
+	}
+
+	pub fn discrete_pdf(&self, i: usize) -> f32 {
+		self.cdf[i + 1] - self.cdf[i]
+	}
+}
+
+/// Piecewise-constant 2D distribution over `[0, 1)²`, stored as a marginal
+/// density over rows and a conditional density within each row.
+#[derive(Debug, Clone)]
+pub struct Distribution2D {
+	conditional: Vec<Distribution1D>,
+	marginal: Distribution1D,
+}
+
+impl Distribution2D {
+	/// `func` holds `width * height` values in row-major order.
+	pub fn new(func: &[f32], width: usize, height: usize) -> Self {
+		let conditional: Vec<Distribution1D> = func
+			.chunks_exact(width)
+			.take(height)
+			.map(|row| Distribution1D::new(row.to_vec()))
+			.collect();
+		let marginal = Distribution1D::new(conditional.iter().map(|d| d.integral()).collect());
+
+		Self {
+			conditional,
+			marginal,
+		}
+	}
+
+	/// Returns a point `(u, v)` and its density.
+	pub fn sample(&self, u: (f32, f32)) -> ((f32, f32), f32) {
+		let (v, pdf_v, row) = self.marginal.sample_continuous(u.1);
+		let (u, pdf_u, _) = self.conditional[row].sample_continuous(u.0);
+
+		((u, v), pdf_u * pdf_v)
+	}
+
+	pub fn pdf(&self, (u, v): (f32, f32)) -> f32 {
+		if self.marginal.integral() == 0. {
+			return 0.;
+		}
+
+		let height = self.conditional.len();
+		let row = ((v * height as f32) as usize).min(height - 1);
+		let conditional = &self.conditional[row];
+		let width = conditional.count();
+		let column = ((u * width as f32) as usize).min(width - 1);
+
+		conditional.func[column] / self.marginal.integral()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::assert_close;
+
+	#[test]
+	fn inverts_the_cdf() {
+		// The second half is three times as likely as the first.
+		let d = Distribution1D::new(vec![1., 3.]);
+		assert_close(d.integral(), 2.);
+
+		let (x, pdf, i) = d.sample_continuous(0.125);
+		assert_close(x, 0.25);
+		assert_close(pdf, 0.5);
+		assert_eq!(i, 0);
+
+		let (x, pdf, i) = d.sample_continuous(0.625);
+		assert_close(x, 0.75);
+		assert_close(pdf, 1.5);
+		assert_eq!(i, 1);
+
+		assert_eq!(d.sample_discrete(0.2), (0, 0.25));
+		assert_eq!(d.sample_discrete(0.3), (1, 0.75));
+	}
+
+	#[test]
+	fn sampled_density_matches_the_function() {
+		let func = vec![0., 2., 5., 1., 0., 4.];
+		let d = Distribution1D::new(func.clone());
+
+		let n = 60000;
+		let mut counts = vec![0; func.len()];
+		for k in 0..n {
+			let (x, pdf, i) = d.sample_continuous((k as f32 + 0.5) / n as f32);
+			assert!((0. ..1.).contains(&x));
+			assert_eq!(i, (x * func.len() as f32) as usize);
+			assert_close(pdf, func[i] / d.integral());
+			counts[i] += 1;
+		}
+
+		for (i, count) in counts.into_iter().enumerate() {
+			let expected = func[i] / d.integral() / func.len() as f32;
+			assert!((count as f32 / n as f32 - expected).abs() < 1e-3);
+		}
+	}
+
+	#[test]
+	fn zero_function_samples_uniformly() {
+		let d = Distribution1D::new(vec![0.; 4]);
+		assert_eq!(d.sample_discrete(0.6), (2, 0.25));
+		assert_eq!(d.sample_continuous(0.6).1, 0.);
+	}
+
+	#[test]
+	fn two_dimensional_samples_carry_their_density() {
+		let func = [1., 0., 2., 3., 1., 1., 0., 4., 6.];
+		let d = Distribution2D::new(&func, 3, 3);
+
+		// Densities average to one over the unit square.
+		let total = (0..3)
+			.flat_map(|y| (0..3).map(move |x| ((x as f32 + 0.5) / 3., (y as f32 + 0.5) / 3.)))
+			.map(|uv| d.pdf(uv) / 9.)
+			.sum::<f32>();
+		assert_close(total, 1.);
+
+		for k in 0..100 {
+			let u = ((k % 10) as f32 / 10. + 0.05, (k / 10) as f32 / 10. + 0.05);
+			let (uv, pdf) = d.sample(u);
+			assert!(pdf > 0.);
+			assert_close(pdf, d.pdf(uv));
+		}
+	}
+}
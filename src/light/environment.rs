@@ -0,0 +1,158 @@
+use std::{f32::consts::PI, fs, io};
+
+use crate::{
+	geometry::Vec3f,
+	image::{formats::Hdr, Colour, Image, ImageDecoder},
+};
+
+use super::{Distribution2D, Environment};
+
+/// Default sky: a vertical blend from white at the horizon to light blue.
+pub struct Gradient {}
+
+impl Environment for Gradient {
+	fn radiance(&self, direction: Vec3f) -> Colour {
+		let t = 0.5 * (direction.unit().y + 1.);
+		Colour::from_rgb(1., 1., 1.) * (1. - t) + Colour::from_rgb(0.5, 0.7, 1.) * t
+	}
+
+	fn sample(&self, u: (f32, f32)) -> (Vec3f, f32) {
+		let y = 1. - 2. * u.0;
+		let r = (1. - y * y).max(0.).sqrt();
+		let phi = 2. * PI * u.1;
+
+		(
+			Vec3f::new(r * phi.cos(), y, r * phi.sin()),
+			self.pdf(Vec3f::new(0., 1., 0.)),
+		)
+	}
+
+	fn pdf(&self, _direction: Vec3f) -> f32 {
+		1. / (4. * PI)
+	}
+}
+
+/// Equirectangular (latitude-longitude) environment map, importance sampled
+/// by luminance.
+pub struct EnvironmentMap {
+	image: Image,
+	distribution: Distribution2D,
+}
+
+/// Maps a direction to latitude-longitude coordinates in `[0, 1)²`, `v = 0`
+/// being straight up.
+pub fn direction_to_uv(direction: Vec3f) -> (f32, f32) {
+	let d = direction.unit();
+	let theta = d.y.clamp(-1., 1.).acos();
+	let phi = d.z.atan2(d.x) + PI;
+
+	(phi / (2. * PI), theta / PI)
+}
+
+pub fn uv_to_direction((u, v): (f32, f32)) -> Vec3f {
+	let theta = v * PI;
+	let phi = u * 2. * PI - PI;
+
+	Vec3f::new(
+		theta.sin() * phi.cos(),
+		theta.cos(),
+		theta.sin() * phi.sin(),
+	)
+}
+
+impl EnvironmentMap {
+	pub fn new(image: Image) -> Self {
+		let (width, height) = (image.width() as usize, image.height() as usize);
+
+		let mut func = Vec::with_capacity(width * height);
+		for y in 0..height {
+			let sin_theta = (PI * (y as f32 + 0.5) / height as f32).sin();
+			for x in 0..width {
+				func.push(image.get_pixel(x as u32, y as u32).luminance() * sin_theta);
+			}
+		}
+
+		Self {
+			distribution: Distribution2D::new(&func, width, height),
+			image,
+		}
+	}
+
+	pub fn open<S: AsRef<str>>(path: S) -> io::Result<Self> {
+		let data = fs::read(path.as_ref())?;
+		Ok(Self::new(Hdr::decode(&data)?))
+	}
+}
+
+impl Environment for EnvironmentMap {
+	fn radiance(&self, direction: Vec3f) -> Colour {
+		let (u, v) = direction_to_uv(direction);
+		let x = ((u * self.image.width() as f32) as u32).min(self.image.width() - 1);
+		let y = ((v * self.image.height() as f32) as u32).min(self.image.height() - 1);
+
+		self.image.get_pixel(x, y).clone()
+	}
+
+	fn sample(&self, u: (f32, f32)) -> (Vec3f, f32) {
+		let (uv, pdf) = self.distribution.sample(u);
+		let sin_theta = (uv.1 * PI).sin();
+		if pdf == 0. || sin_theta == 0. {
+			return (Vec3f::new(0., 1., 0.), 0.);
+		}
+
+		(uv_to_direction(uv), pdf / (2. * PI * PI * sin_theta))
+	}
+
+	fn pdf(&self, direction: Vec3f) -> f32 {
+		let uv = direction_to_uv(direction);
+		let sin_theta = (uv.1 * PI).sin();
+		if sin_theta == 0. {
+			return 0.;
+		}
+
+		self.distribution.pdf(uv) / (2. * PI * PI * sin_theta)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn directions_round_trip_through_uv() {
+		for d in [
+			Vec3f::new(1., 0., 0.),
+			Vec3f::new(0., 0.5, -1.),
+			Vec3f::new(-0.3, -0.8, 0.2),
+		] {
+			let back = uv_to_direction(direction_to_uv(d));
+			assert!((back - d.unit()).len() < 1e-5);
+		}
+		assert_eq!(direction_to_uv(Vec3f::new(0., 1., 0.)).1, 0.);
+	}
+
+	#[test]
+	fn samples_carry_their_density() {
+		// One bright texel, the rest dim.
+		let mut image = Image::new(8, 4);
+		for y in 0..4 {
+			for x in 0..8 {
+				image.set_pixel(x, y, Colour::from_rgb(0.1, 0.1, 0.1));
+			}
+		}
+		image.set_pixel(5, 2, Colour::from_rgb(20., 20., 20.));
+		let map = EnvironmentMap::new(image);
+
+		let mut bright = 0;
+		for k in 0..400 {
+			let u = ((k % 20) as f32 / 20. + 0.025, (k / 20) as f32 / 20. + 0.025);
+			let (direction, pdf) = map.sample(u);
+			let expected = map.pdf(direction);
+			assert!((pdf - expected).abs() <= 1e-3 * expected);
+			if map.radiance(direction).r == 20. {
+				bright += 1;
+			}
+		}
+		assert!(bright > 200);
+	}
+}
@@ -0,0 +1,257 @@
+use std::{
+	fs,
+	io::{self, ErrorKind},
+};
+
+use crate::geometry::Vec3f;
+
+/// IESNA LM-63 photometric data for a type C luminaire: candela values over
+/// vertical angles from the nadir and horizontal angles around it.
+#[derive(Debug, Clone)]
+pub struct IesProfile {
+	vertical: Vec<f32>,
+	horizontal: Vec<f32>,
+	/// Indexed `[horizontal][vertical]`.
+	candela: Vec<Vec<f32>>,
+	max_candela: f32,
+}
+
+fn invalid<T>(message: &str) -> io::Result<T> {
+	Err(io::Error::new(ErrorKind::InvalidData, message))
+}
+
+/// Interpolation position of `x` within ascending `angles`.
+fn locate(angles: &[f32], x: f32) -> (usize, usize, f32) {
+	if angles.len() == 1 || x <= angles[0] {
+		return (0, 0, 0.);
+	}
+
+	let i = angles.partition_point(|a| *a <= x);
+	if i >= angles.len() {
+		let last = angles.len() - 1;
+		return (last, last, 0.);
+	}
+
+	let (a, b) = (angles[i - 1], angles[i]);
+	(i - 1, i, if b > a { (x - a) / (b - a) } else { 0. })
+}
+
+impl IesProfile {
+	pub fn open<S: AsRef<str>>(path: S) -> io::Result<Self> {
+		Self::parse(&fs::read_to_string(path.as_ref())?)
+	}
+
+	pub fn parse(text: &str) -> io::Result<Self> {
+		let mut lines = text.lines();
+
+		let tilt = loop {
+			match lines.next() {
+				Some(line) if line.trim_start().starts_with("TILT=") => {
+					break line.trim()[5..].to_string()
+				}
+				Some(_) => continue,
+				None => return invalid("Missing TILT line"),
+			}
+		};
+
+		let mut numbers = lines.flat_map(|l| l.split(|c: char| c.is_whitespace() || c == ','));
+		let mut next = move || -> io::Result<f32> {
+			loop {
+				match numbers.next() {
+					Some("") => continue,
+					Some(n) => {
+						return n.parse().map_err(|_| {
+							io::Error::new(ErrorKind::InvalidData, "Bad number in photometric data")
+						})
+					}
+					None => return invalid("Truncated photometric data"),
+				}
+			}
+		};
+
+		if tilt == "INCLUDE" {
+			next()?;
+			let count = next()? as usize;
+			for _ in 0..count * 2 {
+				next()?;
+			}
+		} else if tilt != "NONE" {
+			return invalid("External TILT files are not supported");
+		}
+
+		let _lamps = next()?;
+		let _lumens = next()?;
+		let multiplier = next()?;
+		let vertical_count = next()? as usize;
+		let horizontal_count = next()? as usize;
+		let photometric_type = next()? as u32;
+		let _units = next()?;
+		for _ in 0..3 {
+			next()?;
+		}
+		let ballast = next()?;
+		let _ballast_lamp = next()?;
+		let _watts = next()?;
+
+		if photometric_type != 1 {
+			return invalid("Only type C photometry is supported");
+		}
+		if vertical_count == 0 || horizontal_count == 0 {
+			return invalid("Photometric data has no angles");
+		}
+
+		let vertical = (0..vertical_count)
+			.map(|_| next())
+			.collect::<io::Result<Vec<f32>>>()?;
+		let horizontal = (0..horizontal_count)
+			.map(|_| next())
+			.collect::<io::Result<Vec<f32>>>()?;
+		let candela = (0..horizontal_count)
+			.map(|_| {
+				(0..vertical_count)
+					.map(|_| next().map(|c| c * multiplier * ballast))
+					.collect::<io::Result<Vec<f32>>>()
+			})
+			.collect::<io::Result<Vec<Vec<f32>>>>()?;
+
+		let max_candela = candela.iter().flatten().fold(0f32, |a, b| a.max(*b));
+
+		Ok(Self {
+			vertical,
+			horizontal,
+			candela,
+			max_candela,
+		})
+	}
+
+	pub fn max_candela(&self) -> f32 {
+		self.max_candela
+	}
+
+	/// Intensity towards `direction` in a frame whose `-y` axis is the nadir
+	/// and `+x` the zero horizontal angle.
+	pub fn candela(&self, direction: Vec3f) -> f32 {
+		let d = direction.unit();
+		let gamma = (-d.y).clamp(-1., 1.).acos().to_degrees();
+		let mut c = d.z.atan2(d.x).to_degrees();
+		if c < 0. {
+			c += 360.;
+		}
+
+		// Symmetric data only covers part of the horizontal circle.
+		let last = *self.horizontal.last().unwrap_or(&0.);
+		if self.horizontal.first() == Some(&90.) {
+			// Mirrored across the 90-270 plane.
+			if c < 90. {
+				c = 180. - c;
+			} else if c > 270. {
+				c = 540. - c;
+			}
+		} else if last == 0. {
+			c = 0.;
+		} else if last == 90. {
+			c = if c > 270. {
+				360. - c
+			} else if c > 180. {
+				c - 180.
+			} else if c > 90. {
+				180. - c
+			} else {
+				c
+			};
+		} else if last == 180. && c > 180. {
+			c = 360. - c;
+		}
+
+		// No light leaves outside the measured vertical range.
+		let first = *self.vertical.first().unwrap_or(&0.);
+		if gamma < first || gamma > *self.vertical.last().unwrap_or(&180.) {
+			return 0.;
+		}
+
+		let (h0, h1, th) = locate(&self.horizontal, c);
+		let (v0, v1, tv) = locate(&self.vertical, gamma);
+
+		let at = |h: usize| self.candela[h][v0] * (1. - tv) + self.candela[h][v1] * tv;
+		at(h0) * (1. - th) + at(h1) * th
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::assert_close;
+
+	/// Quadrant-symmetric type C data, doubled by its multiplier.
+	const PROFILE: &str = "IESNA:LM-63-2002
+[TEST] Known answers
+TILT=NONE
+1 1000 2 3 2 1 1 0 0 0
+1 1 100
+0 45 90
+0 90
+100 80 0
+50 40 0
+";
+
+	fn towards(gamma: f32, c: f32) -> Vec3f {
+		let (gamma, c) = (gamma.to_radians(), c.to_radians());
+		Vec3f::new(gamma.sin() * c.cos(), -gamma.cos(), gamma.sin() * c.sin())
+	}
+
+	#[test]
+	fn interpolates_candela() {
+		let profile = IesProfile::parse(PROFILE).unwrap();
+		assert_eq!(profile.max_candela(), 200.);
+
+		assert_close(profile.candela(Vec3f::new(0., -1., 0.)), 200.);
+		assert_close(profile.candela(towards(22.5, 0.)), 180.);
+		assert_close(profile.candela(towards(45., 90.)), 80.);
+		assert_close(profile.candela(towards(45., 45.)), 120.);
+		assert_close(profile.candela(towards(90., 0.)), 0.);
+	}
+
+	#[test]
+	fn mirrors_symmetric_data() {
+		let profile = IesProfile::parse(PROFILE).unwrap();
+		assert_close(profile.candela(towards(45., 180.)), 160.);
+		assert_close(profile.candela(towards(45., 270.)), 80.);
+		assert_close(profile.candela(towards(45., 315.)), 120.);
+	}
+
+	#[test]
+	fn mirrors_data_across_the_90_270_plane() {
+		let text = PROFILE.replace("0 45 90\n0 90\n", "0 45 90\n90 270\n");
+		let profile = IesProfile::parse(&text).unwrap();
+		assert_close(profile.candela(towards(45., 90.)), 160.);
+		assert_close(profile.candela(towards(45., 180.)), 120.);
+		assert_close(profile.candela(towards(45., 270.)), 80.);
+		assert_close(profile.candela(towards(45., 0.)), 120.);
+		assert_close(profile.candela(towards(45., 45.)), 140.);
+		assert_close(profile.candela(towards(45., 315.)), 100.);
+	}
+
+	#[test]
+	fn no_light_outside_the_vertical_range() {
+		let profile = IesProfile::parse(&PROFILE.replace("0 45 90\n", "10 45 90\n")).unwrap();
+		assert_eq!(profile.candela(Vec3f::new(0., -1., 0.)), 0.);
+		assert_close(profile.candela(towards(27.5, 0.)), 180.);
+		assert_eq!(profile.candela(Vec3f::new(0., 1., 0.)), 0.);
+	}
+
+	#[test]
+	fn skips_included_tilt_data() {
+		let text = PROFILE.replace("TILT=NONE\n", "TILT=INCLUDE\n1\n2\n0 90\n1 1\n");
+		let profile = IesProfile::parse(&text).unwrap();
+		assert_close(profile.candela(Vec3f::new(0., -1., 0.)), 200.);
+	}
+
+	#[test]
+	fn rejects_bad_data() {
+		assert!(IesProfile::parse("IESNA:LM-63-2002\n1 2 3\n").is_err());
+		assert!(IesProfile::parse(&PROFILE.replace("TILT=NONE", "TILT=lamp.tlt")).is_err());
+		assert!(IesProfile::parse(&PROFILE.replace("0 0 0\n", "0 0 0 x\n")).is_err());
+		assert!(IesProfile::parse(&PROFILE.replace("3 2 1 1", "3 2 2 1")).is_err());
+		assert!(IesProfile::parse(&PROFILE.replace("50 40 0\n", "50\n")).is_err());
+	}
+}
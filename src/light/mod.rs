@@ -0,0 +1,131 @@
+use std::f32::consts::PI;
+
+use crate::{geometry::Vec3f, image::Colour};
+
+mod area;
+pub use area::*;
+
+mod bvh;
+pub use bvh::*;
+
+mod distribution;
+pub use distribution::*;
+
+mod environment;
+pub use environment::*;
+
+mod ies;
+pub use ies::*;
+
+mod point;
+pub use point::*;
+
+mod sampler;
+pub use sampler::*;
+
+mod sky;
+pub use sky::*;
+
+/// Radiance arriving from infinitely far away, seen by rays that leave the
+/// scene.
+pub trait Environment: Sync + Send {
+	fn radiance(&self, direction: Vec3f) -> Colour;
+
+	/// Picks a direction towards the environment from two uniform numbers,
+	/// returning it with its solid angle density.
+	fn sample(&self, u: (f32, f32)) -> (Vec3f, f32);
+
+	fn pdf(&self, direction: Vec3f) -> f32;
+}
+
+pub struct LightSample {
+	pub direction: Vec3f,
+	pub distance: f32,
+	pub radiance: Colour,
+	/// Solid angle density of `direction` as seen from the shading point.
+	pub pdf: f32,
+}
+
+/// A light that the integrator can sample directly from a shading point.
+pub trait Emitter: Sync + Send {
+	fn sample(&self, point: Vec3f, u: (f32, f32)) -> Option<LightSample>;
+
+	/// Density with which `sample` would pick `direction` from `point`.
+	fn pdf(&self, point: Vec3f, direction: Vec3f) -> f32;
+
+	/// Radiance seen by rays that leave the scene towards `direction`.
+	fn radiance(&self, _direction: Vec3f) -> Colour {
+		Colour::new()
+	}
+
+	/// Lights reduced to a point or direction can only be reached by
+	/// sampling them, `sample` then returns irradiance-like radiance with a
+	/// density of one.
+	fn is_delta(&self) -> bool {
+		false
+	}
+
+	/// Extent of the emitted light, `None` for lights at infinity.
+	fn bounds(&self) -> Option<LightBounds> {
+		None
+	}
+
+	/// Estimate of the power a light at infinity sends into a scene held in
+	/// a sphere of `radius`.
+	fn infinite_power(&self, _radius: f32) -> f32 {
+		0.
+	}
+}
+
+impl Emitter for Box<dyn Environment> {
+	fn sample(&self, _point: Vec3f, u: (f32, f32)) -> Option<LightSample> {
+		let (direction, pdf) = self.as_ref().sample(u);
+		if pdf == 0. {
+			return None;
+		}
+
+		Some(LightSample {
+			direction,
+			distance: f32::INFINITY,
+			radiance: self.as_ref().radiance(direction),
+			pdf,
+		})
+	}
+
+	fn pdf(&self, _point: Vec3f, direction: Vec3f) -> f32 {
+		self.as_ref().pdf(direction)
+	}
+
+	fn radiance(&self, direction: Vec3f) -> Colour {
+		self.as_ref().radiance(direction)
+	}
+
+	/// The average radiance over directions spread evenly across the sphere,
+	/// arriving over the whole disc the scene presents to each.
+	fn infinite_power(&self, radius: f32) -> f32 {
+		let (rows, columns) = (64, 128);
+		let mut total = 0.;
+		for row in 0..rows {
+			let y = 1. - 2. * (row as f32 + 0.5) / rows as f32;
+			let r = (1. - y * y).max(0.).sqrt();
+			for column in 0..columns {
+				let phi = 2. * PI * (column as f32 + 0.5) / columns as f32;
+				let direction = Vec3f::new(r * phi.cos(), y, r * phi.sin());
+				total += self.as_ref().radiance(direction).luminance();
+			}
+		}
+
+		4. * PI * PI * radius * radius * total / (rows * columns) as f32
+	}
+}
+
+/// Multiple importance sampling weight for a sample drawn with density `f`
+/// when another strategy could have produced it with density `g`.
+pub fn power_heuristic(f: f32, g: f32) -> f32 {
+	let (f, g) = (f * f, g * g);
+	if f + g == 0. {
+		0.
+	} else {
+		f / (f + g)
+	}
+}
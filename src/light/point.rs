@@ -0,0 +1,221 @@
+use std::f32::consts::PI;
+
+use crate::{geometry::Vec3f, image::Colour};
+
+use super::{DirectionCone, Emitter, IesProfile, LightBounds, LightSample};
+
+/// Orthonormal frame with `down` as `-y`, for looking up photometric data.
+fn to_local(down: Vec3f, direction: Vec3f) -> Vec3f {
+	let y = down * -1.;
+	let a = if y.x.abs() > 0.9 {
+		Vec3f::new(0., 0., 1.)
+	} else {
+		Vec3f::new(1., 0., 0.)
+	};
+	let z = a.cross(y).unit();
+	let x = y.cross(z);
+
+	Vec3f::new(direction.dot(x), direction.dot(y), direction.dot(z))
+}
+
+fn profile_scale(profile: &Option<IesProfile>, down: Vec3f, direction: Vec3f) -> f32 {
+	match profile {
+		Some(profile) if profile.max_candela() > 0. => {
+			profile.candela(to_local(down, direction)) / profile.max_candela()
+		}
+		Some(_) => 0.,
+		None => 1.,
+	}
+}
+
+/// Light emitted from a single point, optionally shaped by a photometric
+/// profile whose nadir points along `down`.
+pub struct PointLight {
+	pub position: Vec3f,
+	pub intensity: Colour,
+	pub down: Vec3f,
+	pub profile: Option<IesProfile>,
+}
+
+impl PointLight {
+	pub fn new(position: Vec3f, intensity: Colour) -> Self {
+		Self {
+			position,
+			intensity,
+			down: Vec3f::new(0., -1., 0.),
+			profile: None,
+		}
+	}
+}
+
+impl Emitter for PointLight {
+	fn sample(&self, point: Vec3f, _u: (f32, f32)) -> Option<LightSample> {
+		let d = self.position - point;
+		let distance = d.len();
+		let direction = d / distance;
+		let scale = profile_scale(&self.profile, self.down, direction * -1.);
+
+		Some(LightSample {
+			direction,
+			distance,
+			radiance: self.intensity.clone() * (scale / (distance * distance)),
+			pdf: 1.,
+		})
+	}
+
+	fn pdf(&self, _point: Vec3f, _direction: Vec3f) -> f32 {
+		0.
+	}
+
+	fn is_delta(&self) -> bool {
+		true
+	}
+
+	fn bounds(&self) -> Option<LightBounds> {
+		Some(LightBounds {
+			min: self.position,
+			max: self.position,
+			phi: 4. * PI * self.intensity.luminance(),
+			normals: DirectionCone::entire_sphere(),
+			cos_theta_e: 0.,
+			two_sided: false,
+		})
+	}
+}
+
+/// Point light restricted to a cone, fading out between the inner and outer
+/// angles. An attached profile is oriented with its nadir along the spot.
+pub struct SpotLight {
+	pub position: Vec3f,
+	pub direction: Vec3f,
+	pub intensity: Colour,
+	pub profile: Option<IesProfile>,
+	cos_inner: f32,
+	cos_outer: f32,
+}
+
+impl SpotLight {
+	/// Angles are half-angles of the cone in radians.
+	pub fn new(
+		position: Vec3f,
+		direction: Vec3f,
+		inner: f32,
+		outer: f32,
+		intensity: Colour,
+	) -> Self {
+		Self {
+			position,
+			direction: direction.unit(),
+			intensity,
+			profile: None,
+			cos_inner: inner.min(outer).cos(),
+			cos_outer: outer.cos(),
+		}
+	}
+
+	fn falloff(&self, direction: Vec3f) -> f32 {
+		let cos = direction.dot(self.direction);
+		if cos >= self.cos_inner {
+			return 1.;
+		}
+		if cos <= self.cos_outer {
+			return 0.;
+		}
+
+		let t = (cos - self.cos_outer) / (self.cos_inner - self.cos_outer);
+		t * t * (3. - 2. * t)
+	}
+}
+
+impl Emitter for SpotLight {
+	fn sample(&self, point: Vec3f, _u: (f32, f32)) -> Option<LightSample> {
+		let d = self.position - point;
+		let distance = d.len();
+		let direction = d / distance;
+		let outgoing = direction * -1.;
+		let scale = self.falloff(outgoing) * profile_scale(&self.profile, self.direction, outgoing);
+		if scale == 0. {
+			return None;
+		}
+
+		Some(LightSample {
+			direction,
+			distance,
+			radiance: self.intensity.clone() * (scale / (distance * distance)),
+			pdf: 1.,
+		})
+	}
+
+	fn pdf(&self, _point: Vec3f, _direction: Vec3f) -> f32 {
+		0.
+	}
+
+	fn is_delta(&self) -> bool {
+		true
+	}
+
+	fn bounds(&self) -> Option<LightBounds> {
+		let theta_e = self.cos_outer.acos() - self.cos_inner.acos();
+
+		Some(LightBounds {
+			min: self.position,
+			max: self.position,
+			phi: 4. * PI * self.intensity.luminance(),
+			normals: DirectionCone::new(self.direction, self.cos_inner),
+			cos_theta_e: theta_e.cos(),
+			two_sided: false,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const PROFILE: &str = "TILT=NONE
+1 1000 1 2 1 1 1 0 0 0
+1 1 100
+0 90
+0
+100 0
+";
+
+	#[test]
+	fn spot_fades_between_its_angles() {
+		let spot = SpotLight::new(
+			Vec3f::new(0., 2., 0.),
+			Vec3f::new(0., -1., 0.),
+			0.2,
+			0.4,
+			Colour::from_rgb(8., 8., 8.),
+		);
+
+		let below = spot.sample(Vec3f::new(0., 0., 0.), (0., 0.)).unwrap();
+		assert_eq!(below.radiance.r, 2.);
+		assert_eq!(below.distance, 2.);
+
+		let edge = spot.sample(Vec3f::new(2. * 0.3f32.tan(), 0., 0.), (0., 0.));
+		let edge = edge.unwrap().radiance.r;
+		assert!(edge > 0. && edge < 2.);
+
+		assert!(spot.sample(Vec3f::new(2., 0., 0.), (0., 0.)).is_none());
+	}
+
+	#[test]
+	fn profile_shapes_a_point_light() {
+		let mut light = PointLight::new(Vec3f::new(0., 1., 0.), Colour::from_rgb(1., 1., 1.));
+		light.profile = Some(IesProfile::parse(PROFILE).unwrap());
+
+		let below = light.sample(Vec3f::new(0., 0., 0.), (0., 0.)).unwrap();
+		assert_eq!(below.radiance.r, 1.);
+
+		// Halfway to the horizon the profile has fallen to half.
+		let side = light.sample(Vec3f::new(1., 0., 0.), (0., 0.)).unwrap();
+		assert!((side.radiance.r - 0.5 / 2.).abs() < 1e-4);
+
+		// Turning the nadir sideways moves the bright spot with it.
+		light.down = Vec3f::new(1., 0., 0.);
+		let side = light.sample(Vec3f::new(2., 1., 0.), (0., 0.)).unwrap();
+		assert!((side.radiance.r - 1. / 4.).abs() < 1e-4);
+	}
+}
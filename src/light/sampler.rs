@@ -0,0 +1,249 @@
+use std::str::FromStr;
+
+use crate::geometry::Vec3f;
+
+use super::{Distribution1D, LightBounds, LightBvh};
+
+/// Chooses which light next event estimation samples from a shading point.
+pub trait LightSampler: Sync + Send {
+	/// Returns a light index and the probability of having picked it.
+	fn pick(&self, point: Vec3f, normal: Vec3f, u: f32) -> Option<(usize, f32)>;
+
+	fn pmf(&self, point: Vec3f, normal: Vec3f, index: usize) -> f32;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightSampling {
+	Uniform,
+	/// Proportional to emitted power.
+	Power,
+	/// Light BVH weighing distance and orientation to the shading point.
+	Bvh,
+}
+
+impl FromStr for LightSampling {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"uniform" => Ok(LightSampling::Uniform),
+			"power" => Ok(LightSampling::Power),
+			"bvh" => Ok(LightSampling::Bvh),
+			_ => Err(format!("Unknown light sampling '{}'", s)),
+		}
+	}
+}
+
+/// What the light samplers know of a light.
+#[derive(Debug, Clone, Copy)]
+pub enum LightInfo {
+	/// A light in the scene, bounded in space and direction.
+	Finite(LightBounds),
+	/// A light at infinity with an estimate of the power it sends into the
+	/// scene, which lights nothing when zero.
+	Infinite(f32),
+}
+
+impl LightInfo {
+	pub fn phi(&self) -> f32 {
+		match self {
+			LightInfo::Finite(bounds) => bounds.phi,
+			LightInfo::Infinite(phi) => *phi,
+		}
+	}
+}
+
+impl LightSampling {
+	/// `lights` holds one entry per light.
+	pub fn build(self, lights: &[LightInfo]) -> Box<dyn LightSampler> {
+		match self {
+			LightSampling::Uniform => Box::new(UniformLightSampler::new(lights)),
+			LightSampling::Power => Box::new(PowerLightSampler::new(lights)),
+			LightSampling::Bvh => Box::new(LightBvh::new(lights)),
+		}
+	}
+}
+
+/// Picks alike among the lights that emit anything.
+pub struct UniformLightSampler {
+	lights: Vec<usize>,
+}
+
+impl UniformLightSampler {
+	pub fn new(lights: &[LightInfo]) -> Self {
+		Self {
+			lights: (0..lights.len())
+				.filter(|i| lights[*i].phi() > 0.)
+				.collect(),
+		}
+	}
+}
+
+impl LightSampler for UniformLightSampler {
+	fn pick(&self, _point: Vec3f, _normal: Vec3f, u: f32) -> Option<(usize, f32)> {
+		if self.lights.is_empty() {
+			return None;
+		}
+
+		let count = self.lights.len();
+		let i = ((u * count as f32) as usize).min(count - 1);
+		Some((self.lights[i], 1. / count as f32))
+	}
+
+	fn pmf(&self, _point: Vec3f, _normal: Vec3f, index: usize) -> f32 {
+		if self.lights.binary_search(&index).is_ok() {
+			1. / self.lights.len() as f32
+		} else {
+			0.
+		}
+	}
+}
+
+pub struct PowerLightSampler {
+	distribution: Option<Distribution1D>,
+}
+
+impl PowerLightSampler {
+	pub fn new(lights: &[LightInfo]) -> Self {
+		let power = lights.iter().map(LightInfo::phi).collect();
+		let distribution = Some(Distribution1D::new(power)).filter(|d| d.integral() > 0.);
+
+		Self { distribution }
+	}
+}
+
+impl LightSampler for PowerLightSampler {
+	fn pick(&self, _point: Vec3f, _normal: Vec3f, u: f32) -> Option<(usize, f32)> {
+		Some(self.distribution.as_ref()?.sample_discrete(u))
+	}
+
+	fn pmf(&self, _point: Vec3f, _normal: Vec3f, index: usize) -> f32 {
+		match &self.distribution {
+			Some(distribution) if index < distribution.count() => distribution.discrete_pdf(index),
+			_ => 0.,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		geometry::Polygon3,
+		image::Colour,
+		light::{AreaLight, Emitter},
+	};
+
+	/// An environment sending `environment` power followed by three
+	/// triangles facing down, the last one far away and the middle one four
+	/// times as bright.
+	fn lights(environment: f32) -> Vec<LightInfo> {
+		let triangle = |x: f32, y: f32, brightness: f32| {
+			let light = AreaLight::new(
+				Polygon3::new(
+					Vec3f::new(x, y, 0.),
+					Vec3f::new(x + 1., y, 0.),
+					Vec3f::new(x, y, 1.),
+				),
+				Colour::from_rgb(brightness, brightness, brightness),
+			);
+			LightInfo::Finite(light.bounds().unwrap())
+		};
+
+		vec![
+			LightInfo::Infinite(environment),
+			triangle(0., 2., 1.),
+			triangle(2., 2., 4.),
+			triangle(40., 2., 1.),
+		]
+	}
+
+	/// Total power of the triangles of `lights`.
+	fn finite_phi(lights: &[LightInfo]) -> f32 {
+		lights[1..].iter().map(LightInfo::phi).sum()
+	}
+
+	fn check(sampler: &dyn LightSampler, count: usize) -> Vec<f32> {
+		let (point, normal) = (Vec3f::new(0., 0., 0.), Vec3f::new(0., 1., 0.));
+		let pmfs: Vec<f32> = (0..count).map(|i| sampler.pmf(point, normal, i)).collect();
+		assert!((pmfs.iter().sum::<f32>() - 1.).abs() < 1e-5);
+
+		let n = 10000;
+		let mut picks = vec![0; count];
+		for k in 0..n {
+			let (i, pmf) = sampler
+				.pick(point, normal, (k as f32 + 0.5) / n as f32)
+				.unwrap();
+			assert!((pmf - pmfs[i]).abs() < 1e-6);
+			picks[i] += 1;
+		}
+		for i in 0..count {
+			assert!((picks[i] as f32 / n as f32 - pmfs[i]).abs() < 1e-3);
+		}
+
+		pmfs
+	}
+
+	#[test]
+	fn uniform_picks_every_light_alike() {
+		let pmfs = check(LightSampling::Uniform.build(&lights(1.)).as_ref(), 4);
+		assert_eq!(pmfs, vec![0.25; 4]);
+	}
+
+	#[test]
+	fn power_picks_by_emitted_power() {
+		let lights = lights(1.);
+		let environment = finite_phi(&lights);
+		let lights = [&[LightInfo::Infinite(environment)], &lights[1..]].concat();
+		let pmfs = check(LightSampling::Power.build(&lights).as_ref(), 4);
+
+		// The environment sends as much as the triangles together.
+		assert!((pmfs[0] - 0.5).abs() < 1e-6);
+		assert!((pmfs[2] - 4. * pmfs[1]).abs() < 1e-5);
+		assert!((pmfs[1] - pmfs[3]).abs() < 1e-6);
+	}
+
+	#[test]
+	fn bvh_prefers_nearby_lights() {
+		let lights = lights(1.);
+		let environment = 3. * finite_phi(&lights);
+		let lights = [&[LightInfo::Infinite(environment)], &lights[1..]].concat();
+		let pmfs = check(LightSampling::Bvh.build(&lights).as_ref(), 4);
+
+		// Infinite lights are picked by their share of the power.
+		assert!((pmfs[0] - 0.75).abs() < 1e-6);
+		assert!(pmfs[1] > 100. * pmfs[3]);
+		assert!(pmfs[2] > 100. * pmfs[3]);
+	}
+
+	#[test]
+	fn black_environments_are_never_picked() {
+		for sampling in [
+			LightSampling::Uniform,
+			LightSampling::Power,
+			LightSampling::Bvh,
+		] {
+			let pmfs = check(sampling.build(&lights(0.)).as_ref(), 4);
+			assert_eq!(pmfs[0], 0., "{:?}", sampling);
+		}
+	}
+
+	#[test]
+	fn no_lights_picks_nothing() {
+		for sampling in [
+			LightSampling::Uniform,
+			LightSampling::Power,
+			LightSampling::Bvh,
+		] {
+			let sampler = sampling.build(&[]);
+			let origin = Vec3f::new(0., 0., 0.);
+			assert!(sampler.pick(origin, origin, 0.5).is_none());
+		}
+	}
+
+	#[test]
+	fn parses_names() {
+		assert_eq!("bvh".parse(), Ok(LightSampling::Bvh));
+		assert!("random".parse::<LightSampling>().is_err());
+	}
+}
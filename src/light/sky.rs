@@ -0,0 +1,295 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use crate::{
+	geometry::Vec3f,
+	image::{Colour, Image},
+};
+
+use super::{uv_to_direction, Emitter, Environment, EnvironmentMap, LightSample};
+
+/// Preetham sky luminance is in kcd/m², radiance is returned in cd/m² so it
+/// can be paired with a physical camera exposure. Scene files expose for
+/// daylight by default when they use the sky.
+const LUMINANCE_SCALE: f32 = 1000.;
+
+/// Angular radius of the sun as seen from the earth.
+const SUN_ANGULAR_RADIUS: f32 = 0.00465;
+
+/// Luminance of the sun disc outside the atmosphere, in kcd/m².
+const SUN_LUMINANCE: f32 = 1.6e6;
+
+const SKY_MAP_WIDTH: u32 = 256;
+const SKY_MAP_HEIGHT: u32 = 128;
+
+pub fn sun_direction(elevation: f32, azimuth: f32) -> Vec3f {
+	Vec3f::new(
+		elevation.cos() * azimuth.sin(),
+		elevation.sin(),
+		elevation.cos() * azimuth.cos(),
+	)
+}
+
+/// Perez sky luminance distribution.
+#[derive(Debug, Clone, Copy)]
+struct Perez([f32; 5]);
+
+impl Perez {
+	fn f(&self, theta: f32, gamma: f32) -> f32 {
+		let [a, b, c, d, e] = self.0;
+		(1. + a * (b / theta.cos()).exp()) * (1. + c * (d * gamma).exp() + e * gamma.cos().powi(2))
+	}
+}
+
+fn xyy_to_rgb(x: f32, y: f32, luminance: f32) -> Colour {
+	if y == 0. {
+		return Colour::new();
+	}
+
+	let cx = x / y * luminance;
+	let cz = (1. - x - y) / y * luminance;
+
+	Colour::from_rgb(
+		(3.2406 * cx - 1.5372 * luminance - 0.4986 * cz).max(0.),
+		(-0.9689 * cx + 1.8758 * luminance + 0.0415 * cz).max(0.),
+		(0.0557 * cx - 0.2040 * luminance + 1.0570 * cz).max(0.),
+	)
+}
+
+/// Preetham et al. analytic daylight model. Radiance is evaluated exactly,
+/// sampling goes through a tabulated copy of the sky.
+pub struct Sky {
+	sun: Vec3f,
+	turbidity: f32,
+	perez: [Perez; 3],
+	zenith: [f32; 3],
+	map: EnvironmentMap,
+}
+
+impl Sky {
+	/// Angles are in radians, `azimuth` turning from `+z` towards `+x`.
+	/// Turbidity ranges from about 2 for a clear sky to 10 for haze.
+	pub fn new(elevation: f32, azimuth: f32, turbidity: f32) -> Self {
+		let t = turbidity;
+		let theta_s = FRAC_PI_2 - elevation.clamp(0., FRAC_PI_2);
+
+		let chi = (4. / 9. - t / 120.) * (PI - 2. * theta_s);
+		let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+
+		let (t2, th, th2, th3) = (t * t, theta_s, theta_s * theta_s, theta_s.powi(3));
+		let zenith_x = t2 * (0.00166 * th3 - 0.00375 * th2 + 0.00209 * th)
+			+ t * (-0.02903 * th3 + 0.06377 * th2 - 0.03202 * th + 0.00394)
+			+ (0.11693 * th3 - 0.21196 * th2 + 0.06052 * th + 0.25886);
+		let zenith_y = t2 * (0.00275 * th3 - 0.00610 * th2 + 0.00317 * th)
+			+ t * (-0.04214 * th3 + 0.08970 * th2 - 0.04153 * th + 0.00516)
+			+ (0.15346 * th3 - 0.26756 * th2 + 0.06670 * th + 0.26688);
+
+		let perez = [
+			Perez([
+				0.1787 * t - 1.4630,
+				-0.3554 * t + 0.4275,
+				-0.0227 * t + 5.3251,
+				0.1206 * t - 2.5771,
+				-0.0670 * t + 0.3703,
+			]),
+			Perez([
+				-0.0193 * t - 0.2592,
+				-0.0665 * t + 0.0008,
+				-0.0004 * t + 0.2125,
+				-0.0641 * t - 0.8989,
+				-0.0033 * t + 0.0452,
+			]),
+			Perez([
+				-0.0167 * t - 0.2608,
+				-0.0950 * t + 0.0092,
+				-0.0079 * t + 0.2102,
+				-0.0441 * t - 1.6537,
+				-0.0109 * t + 0.0529,
+			]),
+		];
+
+		let mut sky = Self {
+			sun: sun_direction(elevation, azimuth),
+			turbidity,
+			perez,
+			zenith: [zenith_luminance, zenith_x, zenith_y],
+			map: EnvironmentMap::new(Image::new(1, 1)),
+		};
+
+		let mut image = Image::new(SKY_MAP_WIDTH, SKY_MAP_HEIGHT);
+		for (x, y) in image.coordinates().collect::<Vec<_>>() {
+			let uv = (
+				(x as f32 + 0.5) / SKY_MAP_WIDTH as f32,
+				(y as f32 + 0.5) / SKY_MAP_HEIGHT as f32,
+			);
+			image.set_pixel(x, y, sky.radiance(uv_to_direction(uv)));
+		}
+		sky.map = EnvironmentMap::new(image);
+
+		sky
+	}
+
+	/// Sun disc matching this sky's sun position and turbidity.
+	pub fn sun(&self) -> Sun {
+		let cos_theta = self.sun.y.max(0.);
+		let theta = cos_theta.acos().to_degrees();
+		let mass = 1. / (cos_theta + 0.15 * (93.885 - theta).max(0.001).powf(-1.253));
+
+		let beta = 0.04608 * self.turbidity - 0.04586;
+		let transmittance = |lambda: f32| {
+			let rayleigh = (-0.008735 * lambda.powf(-4.08) * mass).exp();
+			let aerosol = (-beta * lambda.powf(-1.3) * mass).exp();
+			rayleigh * aerosol
+		};
+
+		let radiance = Colour::from_rgb(
+			transmittance(0.68),
+			transmittance(0.55),
+			transmittance(0.44),
+		) * (SUN_LUMINANCE * LUMINANCE_SCALE);
+
+		Sun::new(self.sun, SUN_ANGULAR_RADIUS, radiance)
+	}
+}
+
+impl Environment for Sky {
+	fn radiance(&self, direction: Vec3f) -> Colour {
+		let d = direction.unit();
+		let theta = d.y.clamp(0.001, 1.).acos();
+		let gamma = d.dot(self.sun).clamp(-1., 1.).acos();
+		let theta_s = self.sun.y.clamp(0., 1.).acos();
+
+		let [luminance, x, y] = [0, 1, 2]
+			.map(|i| self.zenith[i] * self.perez[i].f(theta, gamma) / self.perez[i].f(0., theta_s));
+
+		xyy_to_rgb(x, y, luminance.max(0.) * LUMINANCE_SCALE)
+	}
+
+	fn sample(&self, u: (f32, f32)) -> (Vec3f, f32) {
+		self.map.sample(u)
+	}
+
+	fn pdf(&self, direction: Vec3f) -> f32 {
+		self.map.pdf(direction)
+	}
+}
+
+/// Directional light with the angular size of a small disc, so it casts
+/// slightly soft shadows and can be seen by rays leaving the scene.
+pub struct Sun {
+	direction: Vec3f,
+	cos_radius: f32,
+	radiance: Colour,
+}
+
+impl Sun {
+	pub fn new(direction: Vec3f, angular_radius: f32, radiance: Colour) -> Self {
+		Self {
+			direction: direction.unit(),
+			cos_radius: angular_radius.cos(),
+			radiance,
+		}
+	}
+
+	fn cone_pdf(&self) -> f32 {
+		1. / (2. * PI * (1. - self.cos_radius))
+	}
+}
+
+impl Emitter for Sun {
+	fn sample(&self, _point: Vec3f, u: (f32, f32)) -> Option<LightSample> {
+		let cos_theta = 1. - u.0 * (1. - self.cos_radius);
+		let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+		let phi = 2. * PI * u.1;
+
+		let w = self.direction;
+		let a = if w.x.abs() > 0.9 {
+			Vec3f::new(0., 1., 0.)
+		} else {
+			Vec3f::new(1., 0., 0.)
+		};
+		let v = w.cross(a).unit();
+		let u = w.cross(v);
+
+		Some(LightSample {
+			direction: u * (sin_theta * phi.cos()) + v * (sin_theta * phi.sin()) + w * cos_theta,
+			distance: f32::INFINITY,
+			radiance: self.radiance.clone(),
+			pdf: self.cone_pdf(),
+		})
+	}
+
+	fn pdf(&self, _point: Vec3f, direction: Vec3f) -> f32 {
+		if direction.unit().dot(self.direction) >= self.cos_radius {
+			self.cone_pdf()
+		} else {
+			0.
+		}
+	}
+
+	fn radiance(&self, direction: Vec3f) -> Colour {
+		if direction.unit().dot(self.direction) >= self.cos_radius {
+			self.radiance.clone()
+		} else {
+			Colour::new()
+		}
+	}
+
+	fn infinite_power(&self, radius: f32) -> f32 {
+		PI * radius * radius * self.radiance.luminance() / self.cone_pdf()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn sun_direction_follows_elevation_and_azimuth() {
+		let d = sun_direction(0., 0.);
+		assert!((d - Vec3f::new(0., 0., 1.)).len() < 1e-6);
+		let d = sun_direction(0., FRAC_PI_2);
+		assert!((d - Vec3f::new(1., 0., 0.)).len() < 1e-6);
+		let d = sun_direction(FRAC_PI_2, 1.);
+		assert!((d - Vec3f::new(0., 1., 0.)).len() < 1e-6);
+	}
+
+	#[test]
+	fn zenith_matches_the_preetham_luminance() {
+		// With the sun overhead and a turbidity of 2 the zenith luminance is
+		// 15.5 kcd/m².
+		let sky = Sky::new(FRAC_PI_2, 0., 2.);
+		let luminance = sky.radiance(Vec3f::new(0., 1., 0.)).luminance();
+		assert!(
+			(luminance - 15500.7).abs() < 0.01 * 15500.7,
+			"{}",
+			luminance
+		);
+	}
+
+	#[test]
+	fn sky_is_brightest_towards_the_sun() {
+		let elevation = 0.3;
+		let sky = Sky::new(elevation, 0., 3.);
+		let towards = sky.radiance(sun_direction(elevation + 0.1, 0.)).luminance();
+		let away = sky.radiance(sun_direction(elevation + 0.1, PI)).luminance();
+		assert!(towards > 2. * away);
+
+		// Clear skies are blue overhead.
+		let zenith = sky.radiance(Vec3f::new(0., 1., 0.));
+		assert!(zenith.b > zenith.r);
+	}
+
+	#[test]
+	fn sun_samples_stay_inside_its_disc() {
+		let sun = Sky::new(0.5, 1., 2.).sun();
+		let point = Vec3f::new(0., 0., 0.);
+		for k in 0..50 {
+			let u = (k as f32 / 50., (k * 7 % 50) as f32 / 50.);
+			let sample = sun.sample(point, u).unwrap();
+			assert!(sample.pdf > 0.);
+			assert_eq!(sun.pdf(point, sample.direction), sample.pdf);
+			assert!(sun.radiance(sample.direction).r > 0.);
+		}
+		assert_eq!(sun.pdf(point, Vec3f::new(0., -1., 0.)), 0.);
+	}
+}
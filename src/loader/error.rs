@@ -0,0 +1,74 @@
+use std::{
+	error::Error,
+	fmt::{self, Display},
+	io::{self, ErrorKind},
+};
+
+/// Why a model could not be loaded.
+#[derive(Debug)]
+pub enum LoadError {
+	Io(io::Error),
+	/// Malformed data, with where it was found when the format has lines.
+	Parse(String),
+	/// Data the mesh needs but lacks, such as vertex positions.
+	MissingAttribute(&'static str),
+	/// A glTF primitive drawing points or lines rather than triangles.
+	UnsupportedMode(String),
+}
+
+impl Display for LoadError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			LoadError::Io(e) => e.fmt(f),
+			LoadError::Parse(message) => f.write_str(message),
+			LoadError::MissingAttribute(name) => write!(f, "Missing {}", name),
+			LoadError::UnsupportedMode(mode) => write!(f, "Unsupported primitive mode {}", mode),
+		}
+	}
+}
+
+impl Error for LoadError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match self {
+			LoadError::Io(e) => Some(e),
+			_ => None,
+		}
+	}
+}
+
+impl From<io::Error> for LoadError {
+	fn from(e: io::Error) -> Self {
+		LoadError::Io(e)
+	}
+}
+
+impl From<LoadError> for io::Error {
+	fn from(e: LoadError) -> Self {
+		match e {
+			LoadError::Io(e) => e,
+			e => io::Error::new(ErrorKind::InvalidData, e.to_string()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn converts_to_io_errors() {
+		let missing: io::Error = LoadError::Io(io::Error::from(ErrorKind::NotFound)).into();
+		assert_eq!(missing.kind(), ErrorKind::NotFound);
+
+		let malformed: io::Error = LoadError::MissingAttribute("vertex positions").into();
+		assert_eq!(malformed.kind(), ErrorKind::InvalidData);
+		assert_eq!(malformed.to_string(), "Missing vertex positions");
+
+		let mode = LoadError::UnsupportedMode("Lines".to_string());
+		assert_eq!(mode.to_string(), "Unsupported primitive mode Lines");
+		assert!(mode.source().is_none());
+		assert!(LoadError::Io(io::Error::from(ErrorKind::NotFound))
+			.source()
+			.is_some());
+	}
+}
@@ -0,0 +1,213 @@
+use std::path::Path;
+
+use gltf::mesh::Mode;
+
+use crate::geometry::{Polygon3, SolidObject, Vec3f};
+
+use super::{mesh_material, LoadError};
+
+/// Corner indices of the triangles `mode` draws from `count` vertices.
+fn triangles(mode: Mode, count: usize) -> Result<Vec<[usize; 3]>, LoadError> {
+	let corners = count.saturating_sub(2);
+
+	Ok(match mode {
+		Mode::Triangles => (0..count / 3)
+			.map(|i| [3 * i, 3 * i + 1, 3 * i + 2])
+			.collect(),
+		// Every other triangle of a strip is flipped to keep the winding.
+		Mode::TriangleStrip => (0..corners)
+			.map(|i| {
+				if i % 2 == 0 {
+					[i, i + 1, i + 2]
+				} else {
+					[i + 1, i, i + 2]
+				}
+			})
+			.collect(),
+		Mode::TriangleFan => (0..corners).map(|i| [0, i + 1, i + 2]).collect(),
+		mode => return Err(LoadError::UnsupportedMode(format!("{:?}", mode))),
+	})
+}
+
+/// Reads the first mesh of a glTF model as an object in a plain grey
+/// material. Primitives without indices draw their vertices in order.
+pub fn load_gltf<P: AsRef<Path>>(path: P) -> Result<SolidObject, LoadError> {
+	let (gltf, buffers, _) = gltf::import(path.as_ref()).map_err(|e| match e {
+		gltf::Error::Io(e) => LoadError::Io(e),
+		e => LoadError::Parse(e.to_string()),
+	})?;
+
+	let mesh = gltf
+		.meshes()
+		.next()
+		.ok_or_else(|| LoadError::Parse("No mesh in model".to_string()))?;
+
+	let mut faces = Vec::new();
+	for primitive in mesh.primitives() {
+		let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+
+		let positions: Vec<Vec3f> = reader
+			.read_positions()
+			.ok_or(LoadError::MissingAttribute("vertex positions"))?
+			.map(Vec3f::from)
+			.collect();
+		let indices: Vec<usize> = match reader.read_indices() {
+			Some(indices) => indices.into_u32().map(|i| i as usize).collect(),
+			None => (0..positions.len()).collect(),
+		};
+
+		for [a, b, c] in triangles(primitive.mode(), indices.len())? {
+			let corner = |i: usize| {
+				positions
+					.get(indices[i])
+					.copied()
+					.ok_or_else(|| LoadError::Parse("Vertex index out of range".to_string()))
+			};
+			faces.push(Polygon3::new(corner(a)?, corner(b)?, corner(c)?));
+		}
+	}
+
+	Ok(SolidObject::new(faces, mesh_material()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::loader::tests::scratch;
+
+	/// A model of one primitive drawing the four corners of a unit square
+	/// with `mode`, by the `indices` given if any.
+	fn model(
+		name: &str,
+		mode: u32,
+		indices: Option<&[u16]>,
+		attribute: &str,
+	) -> std::path::PathBuf {
+		let mut buffer = Vec::new();
+		for [x, y] in [[0f32, 0.], [1., 0.], [1., 1.], [0., 1.]] {
+			for v in [x, y, 0.] {
+				buffer.extend(v.to_le_bytes());
+			}
+		}
+		for i in indices.unwrap_or(&[]) {
+			buffer.extend(i.to_le_bytes());
+		}
+
+		let count = indices.map_or(0, |i| i.len());
+		let (indices, index_view, index_accessor) = match count {
+			0 => ("", String::new(), String::new()),
+			_ => (
+				r#", "indices": 1"#,
+				format!(
+					r#", {{ "buffer": 0, "byteOffset": 48, "byteLength": {} }}"#,
+					2 * count
+				),
+				format!(
+					r#", {{ "bufferView": 1, "componentType": 5123, "count": {}, "type": "SCALAR" }}"#,
+					count
+				),
+			),
+		};
+		let json = format!(
+			r#"{{
+	"asset": {{ "version": "2.0" }},
+	"buffers": [{{ "uri": "model.bin", "byteLength": {} }}],
+	"bufferViews": [{{ "buffer": 0, "byteLength": 48 }}{}],
+	"accessors": [
+		{{ "bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3", "min": [0, 0, 0], "max": [1, 1, 0] }}{}
+	],
+	"meshes": [{{ "primitives": [{{ "attributes": {{ "{}": 0 }}, "mode": {}{} }}] }}]
+}}"#,
+			buffer.len(),
+			index_view,
+			index_accessor,
+			attribute,
+			mode,
+			indices
+		);
+
+		let directory = scratch(
+			name,
+			&[("model.gltf", json.as_bytes()), ("model.bin", &buffer)],
+		);
+		directory.join("model.gltf")
+	}
+
+	fn corners(object: &SolidObject) -> Vec<[Vec3f; 3]> {
+		object.faces.iter().map(|f| [f.a, f.b, f.c]).collect()
+	}
+
+	#[test]
+	fn lists_the_triangles_of_each_mode() {
+		assert_eq!(
+			triangles(Mode::Triangles, 7).unwrap(),
+			[[0, 1, 2], [3, 4, 5]]
+		);
+		assert_eq!(
+			triangles(Mode::TriangleStrip, 5).unwrap(),
+			[[0, 1, 2], [2, 1, 3], [2, 3, 4]]
+		);
+		assert_eq!(
+			triangles(Mode::TriangleFan, 5).unwrap(),
+			[[0, 1, 2], [0, 2, 3], [0, 3, 4]]
+		);
+		assert!(triangles(Mode::TriangleStrip, 2).unwrap().is_empty());
+		assert!(matches!(
+			triangles(Mode::Lines, 4),
+			Err(LoadError::UnsupportedMode(mode)) if mode == "Lines"
+		));
+	}
+
+	#[test]
+	fn reads_indexed_and_unindexed_primitives() {
+		let a = Vec3f::new(0., 0., 0.);
+		let b = Vec3f::new(1., 0., 0.);
+		let c = Vec3f::new(1., 1., 0.);
+		let d = Vec3f::new(0., 1., 0.);
+
+		let indexed = load_gltf(model(
+			"gltf-indexed",
+			4,
+			Some(&[0, 1, 2, 0, 2, 3]),
+			"POSITION",
+		))
+		.unwrap();
+		assert_eq!(corners(&indexed), [[a, b, c], [a, c, d]]);
+
+		let fan = load_gltf(model("gltf-fan", 6, None, "POSITION")).unwrap();
+		assert_eq!(corners(&fan), [[a, b, c], [a, c, d]]);
+
+		let strip = load_gltf(model("gltf-strip", 5, Some(&[1, 2, 0, 3]), "POSITION")).unwrap();
+		assert_eq!(corners(&strip), [[b, c, a], [a, c, d]]);
+		for face in &strip.faces {
+			assert_eq!(face.normal, Vec3f::new(0., 0., 1.));
+		}
+	}
+
+	#[test]
+	fn returns_typed_errors() {
+		assert!(matches!(
+			load_gltf(model("gltf-lines", 1, None, "POSITION")),
+			Err(LoadError::UnsupportedMode(_))
+		));
+		// Validation turns primitives without positions away first.
+		assert!(matches!(
+			load_gltf(model("gltf-normals", 4, None, "NORMAL")),
+			Err(LoadError::Parse(message)) if message.contains("POSITION")
+		));
+		assert!(matches!(
+			load_gltf(model("gltf-range", 4, Some(&[0, 1, 9]), "POSITION")),
+			Err(LoadError::Parse(message)) if message == "Vertex index out of range"
+		));
+
+		let directory = scratch("gltf-broken", &[("model.gltf", b"{ not json")]);
+		assert!(matches!(
+			load_gltf(directory.join("model.gltf")),
+			Err(LoadError::Parse(_))
+		));
+		assert!(matches!(
+			load_gltf(directory.join("missing.gltf")),
+			Err(LoadError::Io(_))
+		));
+	}
+}
@@ -0,0 +1,1005 @@
+use std::{
+	collections::HashMap,
+	f32::consts::PI,
+	fs,
+	io::{self, ErrorKind},
+	path::Path,
+};
+
+use roxmltree::{Document, Node};
+
+use crate::{
+	camera::{Aperture, CameraDescription, Fov, Projection, ThinLens},
+	geometry::{Polygon3, Scene, SolidObject, Transform, Vec3f},
+	image::Colour,
+	light::{PointLight, SpotLight},
+	material::Material,
+	sampler::SamplerKind,
+};
+
+use super::{
+	conductor_reflectance, constant_environment, distant_light, emissive, load_obj_faces,
+	load_ply_faces, place_camera, scaled_environment, LoadError, RenderSettings, SceneDescription,
+};
+
+/// Rings of the meshes standing in for spheres; twice as many segments go
+/// around them.
+const SPHERE_RINGS: usize = 16;
+
+/// Axis the field of view of a sensor spans.
+#[derive(Debug, Clone, Copy)]
+enum FovAxis {
+	X,
+	Y,
+	Diagonal,
+	Smaller,
+	Larger,
+}
+
+/// Complex index of refraction of the conductors Mitsuba knows by name, at
+/// red, green and blue.
+fn named_conductor(name: &str) -> Option<(Colour, Colour)> {
+	let (eta, k) = match name {
+		"Au" => ([0.143, 0.374, 1.442], [3.983, 2.385, 1.603]),
+		"Ag" => ([0.155, 0.117, 0.138], [4.828, 3.122, 2.147]),
+		"Al" => ([1.657, 0.880, 0.521], [9.224, 6.270, 4.837]),
+		"Cu" => ([0.200, 0.924, 1.102], [3.912, 2.452, 2.142]),
+		"Cr" => ([4.360, 2.910, 1.650], [5.196, 4.231, 3.755]),
+		_ => return None,
+	};
+	Some((eta.into(), k.into()))
+}
+
+/// Numbers separated by commas or whitespace.
+fn parse_numbers(text: &str) -> Option<Vec<f32>> {
+	text.split(|c: char| c == ',' || c.is_whitespace())
+		.filter(|s| !s.is_empty())
+		.map(|s| s.parse().ok())
+		.collect()
+}
+
+/// Triangles of a unit sphere around the origin.
+fn sphere() -> Vec<Polygon3> {
+	let point = |ring: usize, segment: usize| {
+		let theta = PI * ring as f32 / SPHERE_RINGS as f32;
+		let phi = PI * segment as f32 / SPHERE_RINGS as f32;
+		Vec3f::new(
+			theta.sin() * phi.cos(),
+			theta.sin() * phi.sin(),
+			theta.cos(),
+		)
+	};
+
+	let mut faces = Vec::new();
+	for ring in 0..SPHERE_RINGS {
+		for segment in 0..2 * SPHERE_RINGS {
+			let (a, b) = (point(ring, segment), point(ring, segment + 1));
+			let (c, d) = (point(ring + 1, segment), point(ring + 1, segment + 1));
+			if ring > 0 {
+				faces.push(Polygon3::new(a, c, b));
+			}
+			if ring < SPHERE_RINGS - 1 {
+				faces.push(Polygon3::new(b, c, d));
+			}
+		}
+	}
+	faces
+}
+
+/// Triangles of the quads with the given corners.
+fn quads(corners: &[Vec3f], quads: &[[usize; 4]]) -> Vec<Polygon3> {
+	quads
+		.iter()
+		.flat_map(|q| {
+			[
+				Polygon3::new(corners[q[0]], corners[q[1]], corners[q[2]]),
+				Polygon3::new(corners[q[0]], corners[q[2]], corners[q[3]]),
+			]
+		})
+		.collect()
+}
+
+struct Importer<'a, 'input> {
+	path: &'a Path,
+	document: &'a Document<'input>,
+	defaults: Vec<(String, String)>,
+	warnings: Vec<String>,
+	settings: RenderSettings,
+	camera: CameraDescription,
+	/// Field of view in degrees.
+	fov: (f32, FovAxis),
+	scene: Scene,
+	/// Reflection applied to the world when the sensor would mirror it,
+	/// which the cameras here cannot do.
+	world: Transform,
+	bsdfs: HashMap<String, Material>,
+}
+
+impl<'a, 'input> Importer<'a, 'input> {
+	fn location(&self, node: Node) -> String {
+		let position = self.document.text_pos_at(node.range().start);
+		format!("{}:{}:{}", self.path.display(), position.row, position.col)
+	}
+
+	fn error(&self, node: Node, message: &str) -> io::Error {
+		io::Error::new(
+			ErrorKind::InvalidData,
+			format!("{}: {}", self.location(node), message),
+		)
+	}
+
+	fn warn(&mut self, node: Node, message: &str) {
+		let warning = format!("{}: {}", self.location(node), message);
+		self.warnings.push(warning);
+	}
+
+	/// Attribute `name` with `$` references to defaults substituted.
+	fn attribute(&self, node: Node, name: &str) -> Option<String> {
+		let mut value = node.attribute(name)?.to_string();
+		for (key, default) in &self.defaults {
+			value = value.replace(&format!("${}", key), default);
+		}
+		Some(value)
+	}
+
+	fn ty(&self, node: Node) -> String {
+		self.attribute(node, "type").unwrap_or_default()
+	}
+
+	fn numbers(&self, node: Node, attribute: &str) -> io::Result<Option<Vec<f32>>> {
+		self.attribute(node, attribute)
+			.map(|text| {
+				parse_numbers(&text)
+					.ok_or_else(|| self.error(node, &format!("Invalid numbers '{}'", text)))
+			})
+			.transpose()
+	}
+
+	/// The child element declaring parameter `name`.
+	fn param<'n>(&self, node: Node<'n, 'input>, name: &str) -> Option<Node<'n, 'input>> {
+		node.children()
+			.find(|c| c.is_element() && c.attribute("name") == Some(name))
+	}
+
+	fn float(&self, node: Node, name: &str) -> io::Result<Option<f32>> {
+		let Some(param) = self.param(node, name) else {
+			return Ok(None);
+		};
+		match self.numbers(param, "value")?.as_deref() {
+			Some(&[value]) => Ok(Some(value)),
+			_ => Err(self.error(param, &format!("'{}' needs a single number", name))),
+		}
+	}
+
+	fn string(&self, node: Node, name: &str) -> Option<String> {
+		self.param(node, name)
+			.and_then(|param| self.attribute(param, "value"))
+	}
+
+	/// A point or vector given by a `value` or by `x`, `y` and `z`.
+	fn vector(&self, node: Node, default: f32) -> io::Result<Vec3f> {
+		if let Some(values) = self.numbers(node, "value")? {
+			return match values[..] {
+				[v] => Ok(Vec3f::new(v, v, v)),
+				[x, y, z] => Ok(Vec3f::new(x, y, z)),
+				_ => Err(self.error(node, "Expected one or three numbers")),
+			};
+		}
+
+		let mut v = [default; 3];
+		for (i, axis) in ["x", "y", "z"].iter().enumerate() {
+			if let Some(values) = self.numbers(node, axis)? {
+				match values[..] {
+					[value] => v[i] = value,
+					_ => return Err(self.error(node, &format!("Invalid '{}'", axis))),
+				}
+			}
+		}
+		Ok(v.into())
+	}
+
+	fn point(&self, node: Node, name: &str) -> io::Result<Option<Vec3f>> {
+		self.param(node, name)
+			.map(|param| self.vector(param, 0.))
+			.transpose()
+	}
+
+	/// Spectrum parameter `name`, given as RGB or as a single value.
+	fn colour(&mut self, node: Node, name: &str, default: Colour) -> io::Result<Colour> {
+		let Some(param) = self.param(node, name) else {
+			return Ok(default);
+		};
+
+		match (
+			param.tag_name().name(),
+			self.numbers(param, "value")?.as_deref(),
+		) {
+			("rgb", Some(&[r, g, b])) => Ok(Colour::from_rgb(r, g, b)),
+			("rgb" | "spectrum" | "float", Some(&[v])) => Ok(Colour::from_rgb(v, v, v)),
+			("rgb" | "spectrum" | "float", _) => Err(self.error(
+				param,
+				&format!("Expected an RGB triple or a single value for '{}'", name),
+			)),
+			(tag, _) => {
+				self.warn(
+					param,
+					&format!(
+						"Unsupported {} value for '{}', using the default",
+						tag, name
+					),
+				);
+				Ok(default)
+			}
+		}
+	}
+
+	/// Transformation `name`, its operations applied in order.
+	fn transform(&self, node: Node, name: &str) -> io::Result<Option<Transform>> {
+		let Some(transform) = self.param(node, name) else {
+			return Ok(None);
+		};
+
+		let mut m = Transform::IDENTITY;
+		for op in transform.children().filter(|c| c.is_element()) {
+			let step = match op.tag_name().name() {
+				"translate" => Transform::translate(self.vector(op, 0.)?),
+				"scale" => Transform::scale(self.vector(op, 1.)?),
+				"rotate" => {
+					let angle = self
+						.numbers(op, "angle")?
+						.and_then(|a| a.first().copied())
+						.ok_or_else(|| self.error(op, "Rotation without 'angle'"))?;
+					Transform::rotate(angle, self.vector(op, 0.)?)
+				}
+				"matrix" => match self.numbers(op, "value")? {
+					Some(values) if values.len() == 16 => Transform::from_rows(&values),
+					_ => return Err(self.error(op, "A matrix needs sixteen numbers")),
+				},
+				"lookat" => {
+					let look_at = |attribute| -> io::Result<Vec3f> {
+						match self.numbers(op, attribute)?.as_deref() {
+							Some(&[x, y, z]) => Ok(Vec3f::new(x, y, z)),
+							None if attribute == "up" => Ok(Vec3f::new(0., 1., 0.)),
+							_ => Err(self.error(op, &format!("Invalid '{}'", attribute))),
+						}
+					};
+					Transform::look_at(look_at("origin")?, look_at("target")?, look_at("up")?)
+						.inverse()
+						.ok_or_else(|| self.error(op, "Degenerate look-at"))?
+				}
+				tag => return Err(self.error(op, &format!("Unknown transformation '{}'", tag))),
+			};
+			m = step.mul(&m);
+		}
+		Ok(Some(m))
+	}
+
+	fn object_to_world(&self, node: Node) -> io::Result<Transform> {
+		let to_world = self
+			.transform(node, "to_world")?
+			.unwrap_or(Transform::IDENTITY);
+		Ok(self.world.mul(&to_world))
+	}
+
+	fn element(&mut self, node: Node) -> io::Result<()> {
+		match node.tag_name().name() {
+			"default" => {}
+			"integrator" => {
+				if !matches!(self.ty(node).as_str(), "path" | "volpath") {
+					let message = format!(
+						"Unsupported integrator '{}', using path tracing",
+						self.ty(node)
+					);
+					self.warn(node, &message);
+				}
+				// Mitsuba's default of -1 leaves paths unbounded.
+				if let Some(depth) = self.float(node, "max_depth")? {
+					if depth > 0. {
+						self.settings.max_depth = depth as u32;
+					}
+				}
+			}
+			"sensor" => self.sensor(node)?,
+			"bsdf" => {
+				let material = self.bsdf(node)?;
+				match node.attribute("id") {
+					Some(id) => {
+						self.bsdfs.insert(id.to_string(), material);
+					}
+					None => self.warn(node, "Ignoring a BSDF without an 'id'"),
+				}
+			}
+			"shape" => self.shape(node)?,
+			"emitter" => self.emitter(node)?,
+			tag => {
+				let message = format!("Unsupported element '{}'", tag);
+				self.warn(node, &message);
+			}
+		}
+
+		Ok(())
+	}
+
+	fn sensor(&mut self, node: Node) -> io::Result<()> {
+		let camera_to_world = self
+			.transform(node, "to_world")?
+			.unwrap_or(Transform::IDENTITY);
+		// Sensors look down z with x to the left of the film.
+		self.world = place_camera(&camera_to_world, &mut self.camera, false);
+
+		let ty = self.ty(node);
+		if !matches!(ty.as_str(), "perspective" | "thinlens") {
+			self.warn(
+				node,
+				&format!("Unsupported sensor '{}', using perspective", ty),
+			);
+		}
+		self.camera.projection = Projection::Perspective;
+
+		let axis = match self.string(node, "fov_axis").as_deref() {
+			None | Some("x") => FovAxis::X,
+			Some("y") => FovAxis::Y,
+			Some("diagonal") => FovAxis::Diagonal,
+			Some("smaller") => FovAxis::Smaller,
+			Some("larger") => FovAxis::Larger,
+			Some(axis) => {
+				return Err(self.error(node, &format!("Unknown field of view axis '{}'", axis)))
+			}
+		};
+		let fov = match (self.float(node, "fov")?, self.string(node, "focal_length")) {
+			(Some(fov), _) => fov,
+			// Focal lengths are for 35mm film, 36mm across the axis.
+			(None, focal_length) => {
+				let focal_length = focal_length.unwrap_or_else(|| "50mm".to_string());
+				let millimetres: f32 =
+					focal_length.trim_end_matches("mm").parse().map_err(|_| {
+						self.error(node, &format!("Invalid focal length '{}'", focal_length))
+					})?;
+				2. * (18. / millimetres).atan().to_degrees()
+			}
+		};
+		self.fov = (fov, axis);
+
+		let radius = self.float(node, "aperture_radius")?.unwrap_or(0.);
+		self.camera.lens = if ty == "thinlens" && radius > 0. {
+			Some(ThinLens {
+				radius,
+				focus_distance: self.float(node, "focus_distance")?.unwrap_or(1.),
+				aperture: Aperture::Disc,
+			})
+		} else {
+			None
+		};
+
+		for child in node.children().filter(|c| c.is_element()) {
+			match child.tag_name().name() {
+				"sampler" => {
+					let ty = self.ty(child);
+					self.settings.sampler = match ty.as_str() {
+						"independent" => SamplerKind::Independent,
+						"stratified" | "multijitter" => SamplerKind::Stratified,
+						"ldsampler" => SamplerKind::Sobol,
+						ty => {
+							self.warn(child, &format!("Unsupported sampler '{}', using sobol", ty));
+							SamplerKind::Sobol
+						}
+					};
+					self.settings.spp =
+						self.float(child, "sample_count")?.unwrap_or(4.).max(1.) as u32;
+					if let Some(seed) = self.float(child, "seed")? {
+						self.settings.seed = seed as u64;
+					}
+				}
+				"film" => {
+					self.settings.width =
+						self.float(child, "width")?.unwrap_or(768.).max(1.) as u32;
+					self.settings.height =
+						self.float(child, "height")?.unwrap_or(576.).max(1.) as u32;
+				}
+				_ => {}
+			}
+		}
+
+		Ok(())
+	}
+
+	fn bsdf(&mut self, node: Node) -> io::Result<Material> {
+		let grey = |v: f32| Colour::from_rgb(v, v, v);
+		let diffuse = |albedo: Colour| Material {
+			albedo,
+			specular: 0.,
+			metalic: 0.,
+			roughness: 0.,
+			emission: 0.,
+			texture: None,
+		};
+		let mirror = |albedo: Colour, roughness: f32| Material {
+			albedo,
+			specular: 1.,
+			metalic: 1.,
+			roughness,
+			emission: 0.,
+			texture: None,
+		};
+
+		let ty = self.ty(node);
+		match ty.as_str() {
+			"twosided" | "mask" | "bumpmap" | "normalmap" => {
+				if ty != "twosided" {
+					self.warn(node, &format!("Ignoring the '{}' of a BSDF", ty));
+				}
+				match node
+					.children()
+					.find(|c| c.is_element() && c.tag_name().name() == "bsdf")
+				{
+					Some(nested) => self.bsdf(nested),
+					None => Err(self.error(node, &format!("'{}' without a nested BSDF", ty))),
+				}
+			}
+			"diffuse" => Ok(diffuse(self.colour(node, "reflectance", grey(0.5))?)),
+			"plastic" | "roughplastic" => Ok(Material {
+				specular: 1.,
+				..diffuse(self.colour(node, "diffuse_reflectance", grey(0.5))?)
+			}),
+			"conductor" | "roughconductor" => {
+				let reflectance = match self.string(node, "material").as_deref() {
+					None | Some("none") if self.param(node, "eta").is_none() => grey(1.),
+					None | Some("none") => conductor_reflectance(
+						self.colour(node, "eta", grey(0.))?,
+						self.colour(node, "k", grey(1.))?,
+					),
+					Some(name) => match named_conductor(name) {
+						Some((eta, k)) => conductor_reflectance(eta, k),
+						None => {
+							self.warn(
+								node,
+								&format!("Unknown conductor '{}', using a perfect mirror", name),
+							);
+							grey(1.)
+						}
+					},
+				};
+				let roughness = match ty.as_str() {
+					"conductor" => 0.,
+					_ => match (self.float(node, "alpha_u")?, self.float(node, "alpha_v")?) {
+						(Some(u), Some(v)) => (u + v) / 2.,
+						_ => self.float(node, "alpha")?.unwrap_or(0.1),
+					},
+				};
+				let tint = self.colour(node, "specular_reflectance", grey(1.))?;
+				Ok(mirror(reflectance * tint, roughness))
+			}
+			"dielectric" | "thindielectric" | "roughdielectric" => {
+				self.warn(
+					node,
+					"Refraction is not supported, rendering the dielectric as a mirror",
+				);
+				Ok(mirror(
+					self.colour(node, "specular_reflectance", grey(1.))?,
+					0.,
+				))
+			}
+			ty => {
+				self.warn(node, &format!("Unsupported BSDF '{}', using diffuse", ty));
+				Ok(diffuse(grey(0.5)))
+			}
+		}
+	}
+
+	fn shape(&mut self, node: Node) -> io::Result<()> {
+		let directory = self.path.parent().unwrap_or(Path::new(""));
+		let load = |loader: fn(&Path) -> Result<Vec<Polygon3>, LoadError>| {
+			let filename = self
+				.string(node, "filename")
+				.ok_or_else(|| self.error(node, "Shape without a 'filename'"))?;
+			loader(&directory.join(&filename))
+				.map_err(|e| self.error(node, &format!("Cannot load '{}': {}", filename, e)))
+		};
+
+		let ty = self.ty(node);
+		let faces = match ty.as_str() {
+			"obj" => load(|path| load_obj_faces(path))?,
+			"ply" => load(|path| load_ply_faces(path))?,
+			"rectangle" => quads(
+				&[
+					Vec3f::new(-1., -1., 0.),
+					Vec3f::new(1., -1., 0.),
+					Vec3f::new(1., 1., 0.),
+					Vec3f::new(-1., 1., 0.),
+				],
+				&[[0, 1, 2, 3]],
+			),
+			"cube" => {
+				let corners: Vec<Vec3f> = (0..8)
+					.map(|i| {
+						let side = |bit: usize| if i & bit == 0 { -1. } else { 1. };
+						Vec3f::new(side(1), side(2), side(4))
+					})
+					.collect();
+				quads(
+					&corners,
+					&[
+						[0, 2, 3, 1],
+						[4, 5, 7, 6],
+						[0, 1, 5, 4],
+						[2, 6, 7, 3],
+						[0, 4, 6, 2],
+						[1, 3, 7, 5],
+					],
+				)
+			}
+			"sphere" => {
+				let center = self
+					.point(node, "center")?
+					.unwrap_or(Vec3f::new(0., 0., 0.));
+				let radius = self.float(node, "radius")?.unwrap_or(1.);
+				sphere()
+					.into_iter()
+					.map(|f| {
+						Polygon3::new(
+							center + f.a * radius,
+							center + f.b * radius,
+							center + f.c * radius,
+						)
+						.with_normals([f.a, f.b, f.c])
+					})
+					.collect()
+			}
+			ty => {
+				self.warn(node, &format!("Unsupported shape '{}'", ty));
+				return Ok(());
+			}
+		};
+
+		let mut material = Material {
+			albedo: Colour::from_rgb(0.5, 0.5, 0.5),
+			specular: 0.,
+			metalic: 0.,
+			roughness: 0.,
+			emission: 0.,
+			texture: None,
+		};
+		for child in node.children().filter(|c| c.is_element()) {
+			match child.tag_name().name() {
+				"bsdf" => material = self.bsdf(child)?,
+				"ref" => {
+					let id = self.attribute(child, "id").unwrap_or_default();
+					material = self
+						.bsdfs
+						.get(&id)
+						.cloned()
+						.ok_or_else(|| self.error(child, &format!("Unknown BSDF '{}'", id)))?;
+				}
+				"emitter" if self.ty(child) != "area" => {
+					let message = format!("Unsupported shape emitter '{}'", self.ty(child));
+					self.warn(child, &message);
+				}
+				_ => {}
+			}
+		}
+		// An emitter wins over the surface's BSDF, whatever their order.
+		if let Some(emitter) = node
+			.children()
+			.find(|c| c.tag_name().name() == "emitter" && self.ty(*c) == "area")
+		{
+			material = emissive(self.colour(emitter, "radiance", Colour::from_rgb(1., 1., 1.))?);
+		}
+
+		let m = self.object_to_world(node)?;
+		let faces = faces.into_iter().map(|f| f.transform(&m)).collect();
+		self.scene
+			.add_object(Box::new(SolidObject::new(faces, material)));
+
+		Ok(())
+	}
+
+	fn emitter(&mut self, node: Node) -> io::Result<()> {
+		let one = Colour::from_rgb(1., 1., 1.);
+		let m = self.object_to_world(node)?;
+
+		match self.ty(node).as_str() {
+			"point" => {
+				let position = self
+					.point(node, "position")?
+					.unwrap_or(Vec3f::new(0., 0., 0.));
+				let intensity = self.colour(node, "intensity", one)?;
+				self.scene
+					.add_light(Box::new(PointLight::new(m.point(position), intensity)));
+			}
+			"spot" => {
+				let intensity = self.colour(node, "intensity", one)?;
+				let cutoff = self.float(node, "cutoff_angle")?.unwrap_or(20.);
+				let beam_width = self.float(node, "beam_width")?.unwrap_or(cutoff * 0.75);
+				self.scene.add_light(Box::new(SpotLight::new(
+					m.point(Vec3f::new(0., 0., 0.)),
+					m.vector(Vec3f::new(0., 0., 1.)),
+					beam_width.to_radians(),
+					cutoff.to_radians(),
+					intensity,
+				)));
+			}
+			"directional" => {
+				let direction = match self.point(node, "direction")? {
+					Some(direction) => m.vector(direction),
+					None => m.vector(Vec3f::new(0., 0., 1.)),
+				};
+				let irradiance = self.colour(node, "irradiance", one)?;
+				self.scene
+					.add_light(Box::new(distant_light(direction * -1., irradiance)));
+			}
+			"constant" => {
+				let radiance = self.colour(node, "radiance", one)?;
+				self.scene
+					.set_environment(Box::new(constant_environment(radiance)));
+			}
+			"envmap" => {
+				let filename = self
+					.string(node, "filename")
+					.ok_or_else(|| self.error(node, "Environment map without a 'filename'"))?;
+				if !filename.ends_with(".hdr") {
+					self.warn(
+						node,
+						&format!(
+							"Only .hdr environment maps are supported, ignoring '{}'",
+							filename
+						),
+					);
+					return Ok(());
+				}
+
+				let scale = self.float(node, "scale")?.unwrap_or(1.);
+				let path = self.path.parent().unwrap_or(Path::new("")).join(&filename);
+				let environment = scaled_environment(&path, Colour::from_rgb(scale, scale, scale))
+					.map_err(|e| self.error(node, &format!("Cannot load '{}': {}", filename, e)))?;
+				if self.param(node, "to_world").is_some() {
+					self.warn(
+						node,
+						"Environment maps are used without their transformation",
+					);
+				}
+				self.scene.set_environment(Box::new(environment));
+			}
+			"area" => self.warn(node, "Area emitters need to be attached to a shape"),
+			ty => {
+				let message = format!("Unsupported emitter '{}'", ty);
+				self.warn(node, &message);
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/// Imports a Mitsuba 2 or 3 XML scene: the sensor, meshes and basic shapes,
+/// their BSDFs and emitters. Anything else is skipped with a warning.
+pub fn load_mitsuba<P: AsRef<Path>>(path: P) -> io::Result<SceneDescription> {
+	let path = path.as_ref();
+	let source = fs::read_to_string(path)?;
+	let document = Document::parse(&source).map_err(|e| {
+		let position = e.pos();
+		io::Error::new(
+			ErrorKind::InvalidData,
+			format!(
+				"{}:{}:{}: {}",
+				path.display(),
+				position.row,
+				position.col,
+				e
+			),
+		)
+	})?;
+
+	let root = document.root_element();
+	if root.tag_name().name() != "scene" {
+		return Err(io::Error::new(
+			ErrorKind::InvalidData,
+			format!("{}: Expected a <scene> element", path.display()),
+		));
+	}
+
+	let mut scene = Scene::new();
+	// Mitsuba scenes are dark where no emitter is given.
+	scene.set_environment(Box::new(constant_environment(Colour::new())));
+
+	let mut importer = Importer {
+		path,
+		document: &document,
+		defaults: Vec::new(),
+		warnings: Vec::new(),
+		settings: RenderSettings {
+			width: 768,
+			height: 576,
+			spp: 4,
+			..RenderSettings::new()
+		},
+		camera: CameraDescription::new(),
+		fov: (39.6, FovAxis::X),
+		scene,
+		world: Transform::IDENTITY,
+		bsdfs: HashMap::new(),
+	};
+
+	let elements: Vec<Node> = root.children().filter(|c| c.is_element()).collect();
+	for node in elements.iter().filter(|n| n.tag_name().name() == "default") {
+		if let (Some(name), Some(value)) = (node.attribute("name"), node.attribute("value")) {
+			importer
+				.defaults
+				.push((name.to_string(), value.to_string()));
+		}
+	}
+	// Longest first, so `$spp` is not taken for `$sp` followed by `p`.
+	importer
+		.defaults
+		.sort_by_key(|(name, _)| usize::MAX - name.len());
+
+	// The sensor decides how the world is placed, wherever it appears.
+	if let Some(sensor) = elements.iter().find(|n| n.tag_name().name() == "sensor") {
+		importer.sensor(*sensor)?;
+	}
+	for node in elements.iter().filter(|n| n.tag_name().name() != "sensor") {
+		importer.element(*node)?;
+	}
+
+	let Importer {
+		mut camera,
+		fov: (fov, axis),
+		settings,
+		scene,
+		warnings,
+		..
+	} = importer;
+	let (width, height) = (settings.width as f32, settings.height as f32);
+	let fov = fov.to_radians();
+	camera.fov = match axis {
+		FovAxis::X => Fov::Horizontal(fov),
+		FovAxis::Y => Fov::Vertical(fov),
+		FovAxis::Diagonal => {
+			let tan = (fov / 2.).tan() * width / width.hypot(height);
+			Fov::Horizontal(2. * tan.atan())
+		}
+		FovAxis::Smaller if width <= height => Fov::Horizontal(fov),
+		FovAxis::Larger if width >= height => Fov::Horizontal(fov),
+		FovAxis::Smaller | FovAxis::Larger => Fov::Vertical(fov),
+	};
+
+	Ok(SceneDescription {
+		scene,
+		camera,
+		settings,
+		warnings,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::assert_close;
+	use crate::{
+		geometry::Ray,
+		loader::tests::{hit_point, scratch},
+	};
+
+	fn load(name: &str, source: &str) -> io::Result<SceneDescription> {
+		let directory = scratch(name, &[("scene.xml", source.as_bytes())]);
+		load_mitsuba(directory.join("scene.xml"))
+	}
+
+	#[test]
+	fn parses_numbers() {
+		assert_eq!(parse_numbers("1, 2.5 -3"), Some(vec![1., 2.5, -3.]));
+		assert_eq!(parse_numbers(" 4\n\t5 "), Some(vec![4., 5.]));
+		assert_eq!(parse_numbers(""), Some(vec![]));
+		assert_eq!(parse_numbers("1, x"), None);
+	}
+
+	#[test]
+	fn spheres_are_closed_and_round() {
+		let faces = sphere();
+		assert_eq!(faces.len(), 4 * SPHERE_RINGS * (SPHERE_RINGS - 1));
+		for face in &faces {
+			for p in [face.a, face.b, face.c] {
+				assert!((p.len() - 1.).abs() < 1e-5);
+			}
+			// Every triangle faces away from the centre.
+			let normal = (face.b - face.a).cross(face.c - face.a);
+			assert!(normal.dot(face.a + face.b + face.c) >= 0.);
+		}
+	}
+
+	#[test]
+	fn imports_the_sensor() {
+		let description = load(
+			"mitsuba-sensor",
+			r#"<scene version="3.0.0">
+	<default name="spp" value="32"/>
+	<default name="sp" value="8"/>
+	<sensor type="thinlens">
+		<float name="fov" value="60"/>
+		<string name="fov_axis" value="smaller"/>
+		<float name="aperture_radius" value="0.2"/>
+		<float name="focus_distance" value="3"/>
+		<transform name="to_world">
+			<lookat origin="1, 2, 5" target="1, 2, 0" up="0, 1, 0"/>
+		</transform>
+		<sampler type="independent">
+			<integer name="sample_count" value="$spp"/>
+			<integer name="seed" value="$sp"/>
+		</sampler>
+		<film type="hdrfilm">
+			<integer name="width" value="300"/>
+			<integer name="height" value="200"/>
+		</film>
+	</sensor>
+	<integer name="ignored" value="1"/>
+</scene>"#,
+		)
+		.unwrap();
+
+		let settings = description.settings;
+		assert_eq!((settings.width, settings.height), (300, 200));
+		assert_eq!(
+			(settings.sampler, settings.spp),
+			(SamplerKind::Independent, 32)
+		);
+		assert_eq!(settings.seed, 8);
+
+		let camera = description.camera;
+		assert_close(camera.position, Vec3f::new(1., 2., 5.));
+		assert_close(camera.target, Vec3f::new(1., 2., 4.));
+		assert_close(camera.up, Vec3f::new(0., 1., 0.));
+		assert!(
+			matches!(camera.fov, Fov::Vertical(fov) if (fov - 60f32.to_radians()).abs() < 1e-6)
+		);
+		let lens = camera.lens.unwrap();
+		assert_eq!((lens.radius, lens.focus_distance), (0.2, 3.));
+
+		assert_eq!(description.warnings.len(), 1);
+		assert!(
+			description.warnings[0].ends_with(":21:2: Unsupported element 'integer'"),
+			"{}",
+			description.warnings[0]
+		);
+	}
+
+	#[test]
+	fn converts_the_diagonal_field_of_view() {
+		let description = load(
+			"mitsuba-diagonal",
+			r#"<scene version="3.0.0">
+	<sensor type="perspective">
+		<float name="fov" value="90"/>
+		<string name="fov_axis" value="diagonal"/>
+		<film type="hdrfilm">
+			<integer name="width" value="400"/>
+			<integer name="height" value="300"/>
+		</film>
+	</sensor>
+</scene>"#,
+		)
+		.unwrap();
+
+		// Half the diagonal of 500 is 250 at a distance of 250, and half the
+		// width is 200 at that distance.
+		let expected = 2. * (200f32 / 250.).atan();
+		assert!(
+			matches!(description.camera.fov, Fov::Horizontal(fov) if (fov - expected).abs() < 1e-6)
+		);
+	}
+
+	#[test]
+	fn imports_shapes_and_emitters() {
+		let description = load(
+			"mitsuba-shapes",
+			r#"<scene version="3.0.0">
+	<bsdf type="twosided" id="red">
+		<bsdf type="diffuse">
+			<rgb name="reflectance" value="0.8, 0.1, 0.1"/>
+		</bsdf>
+	</bsdf>
+	<shape type="rectangle">
+		<ref id="red"/>
+		<transform name="to_world">
+			<scale value="2"/>
+			<translate z="-1"/>
+		</transform>
+	</shape>
+	<shape type="cube">
+		<bsdf type="conductor"/>
+		<transform name="to_world">
+			<scale value="0.5"/>
+			<translate y="-5"/>
+		</transform>
+	</shape>
+	<shape type="sphere">
+		<point name="center" x="0" y="5" z="0"/>
+		<float name="radius" value="2"/>
+		<emitter type="area">
+			<rgb name="radiance" value="3, 3, 3"/>
+		</emitter>
+	</shape>
+	<shape type="disk"/>
+</scene>"#,
+		)
+		.unwrap();
+		let scene = &description.scene;
+
+		let hit = |origin: [f32; 3]| {
+			scene
+				.hit(&Ray::new(origin.into(), Vec3f::new(0., 0., -1.)))
+				.unwrap()
+		};
+
+		let rectangle = hit([0., 1.5, 5.]);
+		assert_close(rectangle.point, Vec3f::new(0., 1.5, -1.));
+		assert_eq!(rectangle.object.material().unwrap().albedo.r, 0.8);
+
+		let cube = hit([0., -5., 5.]);
+		assert_close(cube.point, Vec3f::new(0., -5., 0.5));
+		assert_eq!(cube.object.material().unwrap().metalic, 1.);
+
+		// The pole of the sphere is one of its vertices.
+		let sphere = hit([0., 5., 5.]);
+		assert_close(sphere.point, Vec3f::new(0., 5., 2.));
+		assert!(sphere.light.is_some());
+		assert_eq!(sphere.object.material().unwrap().albedo.g, 3.);
+		// Shading follows the round surface rather than its facets.
+		let side = hit([0.7, 5.9, 5.]);
+		assert_close(side.normal, (side.point - Vec3f::new(0., 5., 0.)).unit());
+		assert!((side.normal - side.geometric_normal).len() > 1e-3);
+
+		assert_eq!(hit_point(scene, [0., 2.5, 5.], [0., 0., -1.]), None);
+		assert_eq!(description.warnings.len(), 1);
+		assert!(description.warnings[0].ends_with(":28:2: Unsupported shape 'disk'"));
+	}
+
+	#[test]
+	fn reports_where_errors_are() {
+		let error = |name: &str, source: &str| load(name, source).err().unwrap().to_string();
+
+		let message = error("mitsuba-root", "<shape type=\"cube\"/>");
+		assert!(
+			message.ends_with(": Expected a <scene> element"),
+			"{}",
+			message
+		);
+
+		let message = error("mitsuba-xml", "<scene>\n\t<shape type=\"cube\">\n</scene>");
+		assert!(message.contains(":3:1: "), "{}", message);
+
+		let message = error(
+			"mitsuba-ref",
+			"<scene>\n\t<shape type=\"cube\">\n\t\t<ref id=\"gold\"/>\n\t</shape>\n</scene>",
+		);
+		assert!(
+			message.ends_with(":3:3: Unknown BSDF 'gold'"),
+			"{}",
+			message
+		);
+
+		let message = error(
+			"mitsuba-float",
+			"<scene>\n\t<sensor type=\"perspective\">\n\t\t<float name=\"fov\" value=\"1 2\"/>\n\t</sensor>\n</scene>",
+		);
+		assert!(
+			message.ends_with(":3:3: 'fov' needs a single number"),
+			"{}",
+			message
+		);
+
+		let message = error(
+			"mitsuba-mesh",
+			"<scene>\n\t<shape type=\"obj\">\n\t\t<string name=\"filename\" value=\"missing.obj\"/>\n\t</shape>\n</scene>",
+		);
+		assert!(
+			message.contains(":2:2: Cannot load 'missing.obj'"),
+			"{}",
+			message
+		);
+
+		let message = error(
+			"mitsuba-look-at",
+			"<scene>\n\t<sensor type=\"perspective\">\n\t\t<transform name=\"to_world\">\n\t\t\t<lookat origin=\"0 0 1\" target=\"0 0 1\"/>\n\t\t</transform>\n\t</sensor>\n</scene>",
+		);
+		assert!(message.ends_with(":4:4: Degenerate look-at"), "{}", message);
+	}
+}
@@ -0,0 +1,211 @@
+use std::{
+	f32::consts::PI,
+	fs,
+	io::{self, ErrorKind},
+	path::Path,
+};
+
+use crate::{
+	camera::CameraDescription,
+	geometry::{Scene, Transform, Vec3f},
+	image::{formats::Hdr, Colour, Image, ImageDecoder},
+	light::{EnvironmentMap, Sun},
+	material::Material,
+	sampler::SamplerKind,
+};
+
+mod error;
+pub use error::*;
+
+mod gltf;
+pub use self::gltf::*;
+
+mod mitsuba;
+pub use mitsuba::*;
+
+mod obj;
+pub use obj::*;
+
+mod pbrt;
+pub use pbrt::*;
+
+mod ply;
+pub use ply::*;
+
+mod scene_file;
+pub use scene_file::*;
+
+mod stl;
+pub use stl::*;
+
+/// Render settings a scene carries, which the command line can override.
+#[derive(Debug, Clone, Copy)]
+pub struct RenderSettings {
+	pub width: u32,
+	pub height: u32,
+	pub spp: u32,
+	pub max_depth: u32,
+	pub sampler: SamplerKind,
+	pub seed: u64,
+}
+
+impl RenderSettings {
+	pub fn new() -> Self {
+		Self {
+			width: 640,
+			height: 320,
+			spp: 50,
+			max_depth: 30,
+			sampler: SamplerKind::Sobol,
+			seed: 0,
+		}
+	}
+}
+
+/// A scene along with the camera and settings to render it with.
+pub struct SceneDescription {
+	pub scene: Scene,
+	pub camera: CameraDescription,
+	pub settings: RenderSettings,
+	/// Parts of the file that were skipped or approximated.
+	pub warnings: Vec<String>,
+}
+
+/// Loads a scene description, picking the format from the file extension.
+pub fn load<P: AsRef<Path>>(path: P) -> io::Result<SceneDescription> {
+	let path = path.as_ref();
+
+	match path.extension().and_then(|e| e.to_str()) {
+		Some("toml") => load_scene_file(path),
+		Some("pbrt") => load_pbrt(path),
+		Some("xml") => load_mitsuba(path),
+		_ => Err(io::Error::new(
+			ErrorKind::InvalidInput,
+			format!(
+				"Unknown scene format '{}', expected a .toml, .pbrt or Mitsuba .xml scene file",
+				path.display()
+			),
+		)),
+	}
+}
+
+/// Angular radius given to directional lights, that of the sun.
+const DISTANT_RADIUS: f32 = 0.00465;
+
+/// Directional light towards `direction` giving `irradiance`, spread over a
+/// small disc.
+fn distant_light(direction: Vec3f, irradiance: Colour) -> Sun {
+	let solid_angle = 2. * PI * (1. - DISTANT_RADIUS.cos());
+	Sun::new(direction, DISTANT_RADIUS, irradiance / solid_angle)
+}
+
+fn constant_environment(radiance: Colour) -> EnvironmentMap {
+	let mut image = Image::new(1, 1);
+	image.set_pixel(0, 0, radiance);
+	EnvironmentMap::new(image)
+}
+
+/// Environment map from an `.hdr` image, tinted by `scale`.
+fn scaled_environment(path: &Path, scale: Colour) -> io::Result<EnvironmentMap> {
+	let mut image = Hdr::decode(&fs::read(path)?)?;
+	for y in 0..image.height() {
+		for x in 0..image.width() {
+			let colour = image.get_pixel(x, y).clone() * scale.clone();
+			image.set_pixel(x, y, colour);
+		}
+	}
+	Ok(EnvironmentMap::new(image))
+}
+
+/// Metal reflectance at normal incidence from its complex index of
+/// refraction.
+fn conductor_reflectance(eta: Colour, k: Colour) -> Colour {
+	let one = Colour::from_rgb(1., 1., 1.);
+	let k2 = k.clone() * k;
+
+	((eta.clone() - one.clone()) * (eta.clone() - one.clone()) + k2.clone())
+		/ ((eta.clone() + one.clone()) * (eta + one) + k2)
+}
+
+/// Material of a surface emitting `radiance`.
+fn emissive(radiance: Colour) -> Material {
+	Material {
+		albedo: radiance,
+		specular: 0.,
+		metalic: 0.,
+		roughness: 0.,
+		emission: 1.,
+		texture: None,
+	}
+}
+
+/// Places `camera` by the `camera_to_world` transformation, looking down its
+/// z axis with y up and x pointing to the right of the film when `x_right`,
+/// or to the left. Cameras here cannot mirror the image, so for a
+/// transformation that does, the returned reflection is to be applied to the
+/// world instead.
+fn place_camera(
+	camera_to_world: &Transform,
+	camera: &mut CameraDescription,
+	x_right: bool,
+) -> Transform {
+	let position = camera_to_world.point(Vec3f::new(0., 0., 0.));
+	let forward = camera_to_world.vector(Vec3f::new(0., 0., 1.)).unit();
+	let up = camera_to_world.vector(Vec3f::new(0., 1., 0.)).unit();
+	camera.position = position;
+	camera.target = position + forward;
+	camera.up = up;
+
+	if (camera_to_world.determinant() < 0.) == x_right {
+		Transform::reflection(position, forward.cross(up).unit())
+	} else {
+		Transform::IDENTITY
+	}
+}
+
+/// Plain grey material of meshes in formats that carry none.
+fn mesh_material() -> Material {
+	Material {
+		albedo: Colour::from_rgb(0.8, 0.8, 0.8),
+		specular: 0.,
+		metalic: 0.,
+		roughness: 0.,
+		emission: 0.,
+		texture: None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::PathBuf;
+
+	use super::*;
+	use crate::geometry::Ray;
+
+	/// Writes `files` into a fresh directory for the test called `name` and
+	/// returns it.
+	pub(super) fn scratch(name: &str, files: &[(&str, &[u8])]) -> PathBuf {
+		let directory =
+			std::env::temp_dir().join(format!("path-tracing-{}-{}", std::process::id(), name));
+		let _ = fs::remove_dir_all(&directory);
+		fs::create_dir_all(&directory).unwrap();
+		for (file, contents) in files {
+			fs::write(directory.join(file), contents).unwrap();
+		}
+		directory
+	}
+
+	/// Point where a ray from `origin` along `direction` first hits `scene`.
+	pub(super) fn hit_point(scene: &Scene, origin: [f32; 3], direction: [f32; 3]) -> Option<Vec3f> {
+		let ray = Ray::new(origin.into(), direction.into());
+		scene.hit(&ray).map(|hit| hit.point)
+	}
+
+	#[test]
+	fn picks_the_format_from_the_extension() {
+		let directory = scratch("extension", &[("scene.txt", b"")]);
+		let error = load(directory.join("scene.txt")).err().unwrap();
+		assert_eq!(error.kind(), ErrorKind::InvalidInput);
+		assert!(load(directory.join("missing.toml")).is_err());
+	}
+}
@@ -0,0 +1,534 @@
+use std::{
+	collections::HashMap,
+	fs, io,
+	path::{Path, PathBuf},
+	sync::Arc,
+};
+
+use crate::{
+	geometry::{Polygon3, SolidObject, Vec3f},
+	image::{Colour, Texture},
+	material::Material,
+};
+
+use super::{emissive, LoadError};
+
+fn invalid(path: &Path, line: usize, message: &str) -> LoadError {
+	LoadError::Parse(format!("{}:{}: {}", path.display(), line, message))
+}
+
+fn numbers<const N: usize>(
+	words: &[&str],
+	path: &Path,
+	line: usize,
+	what: &str,
+) -> Result<[f32; N], LoadError> {
+	let mut values = [0.; N];
+	for (i, value) in values.iter_mut().enumerate() {
+		*value = words
+			.get(i)
+			.and_then(|w| w.parse().ok())
+			.ok_or_else(|| invalid(path, line, &format!("Invalid {}", what)))?;
+	}
+	Ok(values)
+}
+
+/// A material as an MTL file describes it.
+struct MtlMaterial {
+	diffuse: Colour,
+	specular: Colour,
+	emission: Colour,
+	shininess: f32,
+	ior: f32,
+	opacity: f32,
+	texture: Option<Arc<Texture>>,
+}
+
+impl MtlMaterial {
+	fn new() -> Self {
+		Self {
+			diffuse: Colour::from_rgb(0.8, 0.8, 0.8),
+			specular: Colour::new(),
+			emission: Colour::new(),
+			shininess: 0.,
+			ior: 1.,
+			opacity: 1.,
+			texture: None,
+		}
+	}
+
+	/// Whether light passes through, which the renderer cannot follow.
+	fn refracts(&self) -> bool {
+		self.emission.luminance() == 0. && self.opacity < 1.
+	}
+
+	/// The closest `Material`. Emissive materials become lights, see-through
+	/// ones are rendered as mirrors reflecting as much as the dielectric
+	/// would at normal incidence, and materials with only a specular colour
+	/// become metals. The Phong exponent sets the roughness.
+	fn to_material(&self) -> Material {
+		let roughness = (2. / (self.shininess + 2.)).sqrt();
+
+		if self.emission.luminance() > 0. {
+			emissive(self.emission.clone())
+		} else if self.refracts() {
+			let reflectance = ((self.ior - 1.) / (self.ior + 1.)).powi(2);
+			Material {
+				albedo: Colour::from_rgb(reflectance, reflectance, reflectance),
+				specular: 1.,
+				metalic: 1.,
+				roughness: 0.,
+				emission: 0.,
+				texture: None,
+			}
+		} else if self.diffuse.luminance() == 0. && self.specular.luminance() > 0. {
+			Material {
+				albedo: self.specular.clone(),
+				specular: 1.,
+				metalic: 1.,
+				roughness,
+				emission: 0.,
+				texture: None,
+			}
+		} else {
+			Material {
+				albedo: self.diffuse.clone(),
+				specular: self.specular.luminance(),
+				metalic: 0.,
+				roughness,
+				emission: 0.,
+				texture: self.texture.clone(),
+			}
+		}
+	}
+}
+
+/// Reads the materials of an MTL library, loading each texture once.
+/// Approximated materials are added to `warnings`.
+fn load_mtl(
+	path: &Path,
+	textures: &mut HashMap<PathBuf, Arc<Texture>>,
+	warnings: &mut Vec<String>,
+) -> Result<HashMap<String, Material>, LoadError> {
+	let source = fs::read_to_string(path)
+		.map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+	let directory = path.parent().unwrap_or(Path::new(""));
+
+	let mut materials = HashMap::new();
+	let mut finish = |(name, line, material): (String, usize, MtlMaterial)| {
+		if material.refracts() {
+			warnings.push(format!(
+				"{}:{}: Refraction is not supported, rendering '{}' as a mirror",
+				path.display(),
+				line,
+				name
+			));
+		}
+		materials.insert(name, material.to_material());
+	};
+	let mut current: Option<(String, usize, MtlMaterial)> = None;
+	for (number, line) in source.lines().enumerate() {
+		let line_number = number + 1;
+		let words: Vec<&str> = line.split_whitespace().collect();
+		let Some((&keyword, arguments)) = words.split_first() else {
+			continue;
+		};
+
+		if keyword == "newmtl" {
+			if let Some(material) = current.take() {
+				finish(material);
+			}
+			current = Some((arguments.join(" "), line_number, MtlMaterial::new()));
+			continue;
+		}
+
+		let Some((_, _, material)) = current.as_mut() else {
+			continue;
+		};
+		let colour = |what| numbers::<3>(arguments, path, line_number, what).map(Colour::from);
+		let float = |what| numbers::<1>(arguments, path, line_number, what).map(|[v]| v);
+		match keyword {
+			"Kd" => material.diffuse = colour("diffuse colour")?,
+			"Ks" => material.specular = colour("specular colour")?,
+			"Ke" => material.emission = colour("emission")?,
+			"Ns" => material.shininess = float("shininess")?,
+			"Ni" => material.ior = float("index of refraction")?,
+			"d" => material.opacity = float("dissolve")?,
+			"Tr" => material.opacity = 1. - float("transparency")?,
+			"map_Kd" => {
+				// Options come before the file name.
+				let name = arguments
+					.last()
+					.ok_or_else(|| invalid(path, line_number, "Missing texture file"))?;
+				let texture_path = directory.join(name);
+				let texture = match textures.get(&texture_path) {
+					Some(texture) => texture.clone(),
+					None => {
+						let texture = Arc::new(Texture::open(&texture_path).map_err(|e| {
+							invalid(path, line_number, &format!("Cannot load '{}': {}", name, e))
+						})?);
+						textures.insert(texture_path, texture.clone());
+						texture
+					}
+				};
+				material.texture = Some(texture);
+			}
+			_ => {}
+		}
+	}
+	if let Some(material) = current {
+		finish(material);
+	}
+
+	Ok(materials)
+}
+
+/// Position, texture coordinate and normal indices of a face corner.
+type Corner = (usize, Option<usize>, Option<usize>);
+
+/// Resolves a one-based index, negative ones counting back from the end of
+/// `count` items.
+fn resolve(index: &str, count: usize) -> Option<usize> {
+	match index.parse::<i64>().ok()? {
+		i if i > 0 && i as usize <= count => Some(i as usize - 1),
+		i if i < 0 && i.unsigned_abs() as usize <= count => Some(count - i.unsigned_abs() as usize),
+		_ => None,
+	}
+}
+
+/// Reads a Wavefront OBJ model with the materials of its MTL libraries.
+/// Every group, object and material change starts a new `SolidObject`.
+/// Polygons are split into fans, and vertex normals and texture coordinates
+/// are kept for faces with them at every corner. Libraries that cannot be
+/// read are added to `warnings`, their materials left as the default.
+pub fn load_obj<P: AsRef<Path>>(
+	path: P,
+	warnings: &mut Vec<String>,
+) -> Result<Vec<SolidObject>, LoadError> {
+	read_obj(path.as_ref(), true, warnings)
+}
+
+/// Triangles of every object of an OBJ model, for formats that give the
+/// material themselves.
+pub fn load_obj_faces<P: AsRef<Path>>(path: P) -> Result<Vec<Polygon3>, LoadError> {
+	Ok(read_obj(path.as_ref(), false, &mut Vec::new())?
+		.into_iter()
+		.flat_map(|object| object.faces)
+		.collect())
+}
+
+fn read_obj(
+	path: &Path,
+	with_materials: bool,
+	warnings: &mut Vec<String>,
+) -> Result<Vec<SolidObject>, LoadError> {
+	let source = fs::read_to_string(path)?;
+	let directory = path.parent().unwrap_or(Path::new(""));
+
+	let default_material = MtlMaterial::new().to_material();
+	let mut textures = HashMap::new();
+	let mut materials: HashMap<String, Material> = HashMap::new();
+
+	let mut positions: Vec<Vec3f> = Vec::new();
+	let mut uvs: Vec<(f32, f32)> = Vec::new();
+	let mut normals: Vec<Vec3f> = Vec::new();
+
+	let mut objects = Vec::new();
+	let mut faces: Vec<Polygon3> = Vec::new();
+	let mut material = default_material.clone();
+	let mut finish = |faces: &mut Vec<Polygon3>, material: &Material| {
+		if !faces.is_empty() {
+			objects.push(SolidObject::new(std::mem::take(faces), material.clone()));
+		}
+	};
+
+	for (number, line) in source.lines().enumerate() {
+		let line_number = number + 1;
+		let words: Vec<&str> = line.split_whitespace().collect();
+		let Some((&keyword, arguments)) = words.split_first() else {
+			continue;
+		};
+
+		match keyword {
+			"v" => positions.push(numbers::<3>(arguments, path, line_number, "vertex")?.into()),
+			"vn" => normals.push(numbers::<3>(arguments, path, line_number, "normal")?.into()),
+			"vt" => {
+				let u = numbers::<1>(arguments, path, line_number, "texture coordinate")?[0];
+				let v = match arguments.get(1) {
+					Some(_) => numbers::<2>(arguments, path, line_number, "texture coordinate")?[1],
+					None => 0.,
+				};
+				uvs.push((u, v));
+			}
+			"f" => {
+				let corners = arguments
+					.iter()
+					.map(|corner| {
+						let mut indices = corner.split('/');
+						let out_of_range = || {
+							invalid(
+								path,
+								line_number,
+								&format!("Invalid face vertex '{}'", corner),
+							)
+						};
+						let position = indices
+							.next()
+							.and_then(|i| resolve(i, positions.len()))
+							.ok_or_else(out_of_range)?;
+						let optional = |index: Option<&str>, count| match index {
+							None | Some("") => Ok(None),
+							Some(i) => resolve(i, count).map(Some).ok_or_else(out_of_range),
+						};
+						let uv = optional(indices.next(), uvs.len())?;
+						let normal = optional(indices.next(), normals.len())?;
+						Ok((position, uv, normal))
+					})
+					.collect::<Result<Vec<Corner>, LoadError>>()?;
+				if corners.len() < 3 {
+					return Err(invalid(path, line_number, "A face needs three vertices"));
+				}
+
+				for i in 2..corners.len() {
+					let [a, b, c] = [corners[0], corners[i - 1], corners[i]];
+					let mut face = Polygon3::new(positions[a.0], positions[b.0], positions[c.0]);
+					if let (Some(na), Some(nb), Some(nc)) = (a.2, b.2, c.2) {
+						face = face.with_normals([normals[na], normals[nb], normals[nc]]);
+					}
+					if let (Some(ta), Some(tb), Some(tc)) = (a.1, b.1, c.1) {
+						face = face.with_uvs([uvs[ta], uvs[tb], uvs[tc]]);
+					}
+					faces.push(face);
+				}
+			}
+			"g" | "o" => finish(&mut faces, &material),
+			"usemtl" => {
+				finish(&mut faces, &material);
+				// Unknown names keep the default, as most viewers do.
+				material = materials
+					.get(&arguments.join(" "))
+					.cloned()
+					.unwrap_or_else(|| default_material.clone());
+			}
+			"mtllib" if with_materials => {
+				for library in arguments {
+					match load_mtl(&directory.join(library), &mut textures, warnings) {
+						Ok(library) => materials.extend(library),
+						Err(e) => warnings.push(format!(
+							"{}:{}: {}, using the default material",
+							path.display(),
+							line_number,
+							e
+						)),
+					}
+				}
+			}
+			_ => {}
+		}
+	}
+	finish(&mut faces, &material);
+
+	Ok(objects)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::loader::tests::scratch;
+
+	const SQUARE: &str = "\
+mtllib square.mtl
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 2
+# A quad split into a fan of two triangles.
+usemtl paint
+f 1/1/1 2/2/1 3/3/1 4/4/1
+o light
+usemtl lamp
+f -4 -2 -1
+";
+
+	const MATERIALS: &str = "\
+newmtl paint
+Kd 0.1 0.2 0.3
+Ks 0.5 0.5 0.5
+Ns 98
+newmtl lamp
+Ke 4 4 4
+newmtl glass
+d 0.5
+Ni 1.5
+newmtl gold
+Kd 0 0 0
+Ks 1 0.8 0.3
+";
+
+	fn load(
+		name: &str,
+		files: &[(&str, &[u8])],
+	) -> (Result<Vec<SolidObject>, LoadError>, Vec<String>) {
+		let directory = scratch(name, files);
+		let mut warnings = Vec::new();
+		let objects = load_obj(directory.join("model.obj"), &mut warnings);
+		(objects, warnings)
+	}
+
+	#[test]
+	fn resolves_indices() {
+		assert_eq!(resolve("1", 3), Some(0));
+		assert_eq!(resolve("3", 3), Some(2));
+		assert_eq!(resolve("-1", 3), Some(2));
+		assert_eq!(resolve("-3", 3), Some(0));
+		assert_eq!(resolve("0", 3), None);
+		assert_eq!(resolve("4", 3), None);
+		assert_eq!(resolve("-4", 3), None);
+		assert_eq!(resolve("x", 3), None);
+	}
+
+	#[test]
+	fn reads_faces_with_their_attributes() {
+		let (objects, warnings) = load(
+			"obj-square",
+			&[
+				("model.obj", SQUARE.as_bytes()),
+				("square.mtl", MATERIALS.as_bytes()),
+			],
+		);
+		let objects = objects.unwrap();
+		assert_eq!(warnings.len(), 1);
+		assert!(warnings[0]
+			.ends_with("square.mtl:7: Refraction is not supported, rendering 'glass' as a mirror"));
+		assert_eq!(objects.len(), 2);
+
+		let square = &objects[0].faces;
+		assert_eq!(square.len(), 2);
+		assert_eq!(
+			[square[0].a, square[0].b, square[0].c],
+			[
+				Vec3f::new(0., 0., 0.),
+				Vec3f::new(1., 0., 0.),
+				Vec3f::new(1., 1., 0.)
+			]
+		);
+		assert_eq!(
+			[square[1].a, square[1].b, square[1].c],
+			[
+				Vec3f::new(0., 0., 0.),
+				Vec3f::new(1., 1., 0.),
+				Vec3f::new(0., 1., 0.)
+			]
+		);
+		assert_eq!(square[0].normal, Vec3f::new(0., 0., 1.));
+		assert_eq!(square[1].normals, Some([Vec3f::new(0., 0., 1.); 3]));
+		assert_eq!(square[1].uvs, Some([(0., 0.), (1., 1.), (0., 1.)]));
+
+		// Negative indices count back from the last vertex read.
+		let light = &objects[1].faces;
+		assert_eq!(
+			[light[0].a, light[0].b, light[0].c],
+			[
+				Vec3f::new(0., 0., 0.),
+				Vec3f::new(1., 1., 0.),
+				Vec3f::new(0., 1., 0.)
+			]
+		);
+		assert_eq!((light[0].normals, light[0].uvs), (None, None));
+	}
+
+	#[test]
+	fn converts_mtl_materials() {
+		let directory = scratch("obj-mtl", &[("square.mtl", MATERIALS.as_bytes())]);
+		let mut warnings = Vec::new();
+		let path = directory.join("square.mtl");
+		let materials = load_mtl(&path, &mut HashMap::new(), &mut warnings).unwrap();
+		assert_eq!(materials.len(), 4);
+		assert_eq!(warnings.len(), 1);
+		assert!(warnings[0].contains("square.mtl:7: "), "{}", warnings[0]);
+
+		let paint = &materials["paint"];
+		assert_eq!(
+			(paint.albedo.r, paint.albedo.g, paint.albedo.b),
+			(0.1, 0.2, 0.3)
+		);
+		assert_eq!((paint.specular, paint.metalic), (0.5, 0.));
+		assert!((paint.roughness - 0.02f32.sqrt()).abs() < 1e-6);
+
+		let lamp = &materials["lamp"];
+		assert_eq!((lamp.emission, lamp.albedo.r), (1., 4.));
+
+		let glass = &materials["glass"];
+		assert_eq!((glass.metalic, glass.roughness), (1., 0.));
+		assert!((glass.albedo.r - 0.04).abs() < 1e-6);
+		assert_eq!(
+			(glass.albedo.g, glass.albedo.b),
+			(glass.albedo.r, glass.albedo.r)
+		);
+
+		let gold = &materials["gold"];
+		assert_eq!((gold.metalic, gold.albedo.g), (1., 0.8));
+		assert_eq!(gold.roughness, 1.);
+	}
+
+	#[test]
+	fn assigns_materials_to_objects() {
+		let (objects, _) = load(
+			"obj-materials",
+			&[
+				("model.obj", SQUARE.as_bytes()),
+				("square.mtl", MATERIALS.as_bytes()),
+			],
+		);
+		let objects = objects.unwrap();
+		assert_eq!(objects[0].material.albedo.b, 0.3);
+		assert_eq!(objects[1].material.emission, 1.);
+	}
+
+	#[test]
+	fn warns_about_missing_libraries() {
+		let (objects, warnings) = load("obj-no-mtl", &[("model.obj", SQUARE.as_bytes())]);
+		let objects = objects.unwrap();
+		assert_eq!(warnings.len(), 1);
+		assert!(warnings[0].contains("model.obj:1: "), "{}", warnings[0]);
+		assert!(warnings[0].ends_with(", using the default material"));
+		let default = MtlMaterial::new().to_material();
+		assert_eq!(objects[0].material.albedo.r, default.albedo.r);
+		assert_eq!(objects[1].material.emission, 0.);
+
+		// Formats that give the material themselves do not read libraries.
+		let directory = scratch("obj-faces", &[("model.obj", SQUARE.as_bytes())]);
+		assert_eq!(
+			load_obj_faces(directory.join("model.obj")).unwrap().len(),
+			3
+		);
+	}
+
+	#[test]
+	fn rejects_malformed_models() {
+		let message = |source: &str| {
+			let (objects, _) = load("obj-malformed", &[("model.obj", source.as_bytes())]);
+			objects.err().unwrap().to_string()
+		};
+
+		assert!(message("v 0 0\n").ends_with("model.obj:1: Invalid vertex"));
+		assert!(message("v 0 0 0\nv 1 0 0\nf 1 2\n")
+			.ends_with("model.obj:3: A face needs three vertices"));
+		assert!(message("v 0 0 0\nf 1 2 3\n").ends_with("model.obj:2: Invalid face vertex '2'"));
+		assert!(
+			message("v 0 0 0\nf 1/1 1/1 1/1\n").ends_with("model.obj:2: Invalid face vertex '1/1'")
+		);
+		assert!(matches!(
+			load_obj(
+				std::env::temp_dir().join("path-tracing-missing.obj"),
+				&mut Vec::new()
+			),
+			Err(LoadError::Io(_))
+		));
+	}
+}
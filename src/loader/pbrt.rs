@@ -0,0 +1,1134 @@
+use std::{
+	collections::HashMap,
+	fs,
+	io::{self, ErrorKind},
+	iter::Peekable,
+	path::Path,
+	sync::Arc,
+	vec,
+};
+
+use crate::{
+	camera::{Aperture, CameraDescription, Fov, Projection, ThinLens},
+	geometry::{Instance, Mesh, Object, Polygon3, Scene, SolidObject, Transform, Vec3f},
+	image::Colour,
+	light::{PointLight, SpotLight},
+	material::Material,
+	sampler::SamplerKind,
+};
+
+use super::{
+	conductor_reflectance, constant_environment, distant_light, emissive, load_ply_faces,
+	place_camera, scaled_environment, RenderSettings, SceneDescription,
+};
+
+type Location = (usize, usize);
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+	Word(String),
+	String(String),
+	Number(f32),
+	Open,
+	Close,
+}
+
+struct Token {
+	kind: TokenKind,
+	location: Location,
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+	Number(f32),
+	String(String),
+}
+
+/// A typed parameter such as `"float fov" [ 45 ]`.
+struct Param {
+	ty: String,
+	name: String,
+	values: Vec<Value>,
+	location: Location,
+}
+
+impl Param {
+	fn numbers(&self) -> Vec<f32> {
+		self.values
+			.iter()
+			.filter_map(|v| match v {
+				Value::Number(n) => Some(*n),
+				Value::String(_) => None,
+			})
+			.collect()
+	}
+}
+
+struct Directive {
+	name: String,
+	location: Location,
+	strings: Vec<String>,
+	numbers: Vec<f32>,
+	params: Vec<Param>,
+}
+
+impl Directive {
+	fn param(&self, name: &str) -> Option<&Param> {
+		self.params.iter().find(|p| p.name == name)
+	}
+
+	fn float(&self, name: &str) -> Option<f32> {
+		self.param(name).and_then(|p| p.numbers().first().copied())
+	}
+
+	fn string(&self, name: &str) -> Option<&str> {
+		self.param(name).and_then(|p| match p.values.first() {
+			Some(Value::String(s)) => Some(s.as_str()),
+			_ => None,
+		})
+	}
+
+	fn point(&self, name: &str, default: Vec3f) -> Vec3f {
+		match self.param(name).map(|p| p.numbers()).as_deref() {
+			Some(&[x, y, z]) => Vec3f::new(x, y, z),
+			_ => default,
+		}
+	}
+
+	/// The type named by the first positional string.
+	fn ty(&self) -> &str {
+		self.strings.first().map(|s| s.as_str()).unwrap_or("")
+	}
+}
+
+/// Number of quoted strings a directive takes before its parameters.
+fn positional_strings(directive: &str) -> usize {
+	match directive {
+		"Texture" => 3,
+		"MediumInterface" => 2,
+		"Camera" | "Film" | "Sampler" | "Integrator" | "PixelFilter" | "Accelerator" | "Shape"
+		| "Material" | "MakeNamedMaterial" | "NamedMaterial" | "LightSource"
+		| "AreaLightSource" | "MakeNamedMedium" | "CoordinateSystem" | "CoordSysTransform"
+		| "ObjectBegin" | "ObjectInstance" | "Include" | "Import" => 1,
+		_ => 0,
+	}
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, (Location, String)> {
+	let mut tokens = Vec::new();
+	let mut chars = source.chars().peekable();
+	let (mut line, mut column) = (1, 1);
+
+	while let Some(&c) = chars.peek() {
+		let location = (line, column);
+		let mut next = || {
+			let c = chars.next();
+			if c == Some('\n') {
+				line += 1;
+				column = 1;
+			} else {
+				column += 1;
+			}
+			c
+		};
+
+		match c {
+			c if c.is_whitespace() => {
+				next();
+			}
+			'#' => while !matches!(next(), Some('\n') | None) {},
+			'[' | ']' => {
+				next();
+				tokens.push(Token {
+					kind: if c == '[' {
+						TokenKind::Open
+					} else {
+						TokenKind::Close
+					},
+					location,
+				});
+			}
+			'"' => {
+				next();
+				let mut string = String::new();
+				loop {
+					match next() {
+						Some('"') => break,
+						Some('\\') => string.extend(next()),
+						Some('\n') | None => {
+							return Err((location, "Unterminated string".to_string()))
+						}
+						Some(c) => string.push(c),
+					}
+				}
+				tokens.push(Token {
+					kind: TokenKind::String(string),
+					location,
+				});
+			}
+			_ => {
+				let mut word = String::new();
+				while let Some(&c) = chars.peek() {
+					if c.is_whitespace() || matches!(c, '[' | ']' | '"' | '#') {
+						break;
+					}
+					word.push(c);
+					chars.next();
+					column += 1;
+				}
+
+				let kind = if word.starts_with(|c: char| c.is_ascii_alphabetic()) {
+					match word.as_str() {
+						"true" | "false" => TokenKind::String(word),
+						_ => TokenKind::Word(word),
+					}
+				} else {
+					TokenKind::Number(
+						word.parse()
+							.map_err(|_| (location, format!("Invalid number '{}'", word)))?,
+					)
+				};
+				tokens.push(Token { kind, location });
+			}
+		}
+	}
+
+	Ok(tokens)
+}
+
+type Tokens = Peekable<vec::IntoIter<Token>>;
+
+/// Values following a parameter declaration, single or in brackets.
+fn values(tokens: &mut Tokens, location: Location) -> Result<Vec<Value>, (Location, String)> {
+	let value = |token: Token| match token.kind {
+		TokenKind::Number(n) => Ok(Value::Number(n)),
+		TokenKind::String(s) => Ok(Value::String(s)),
+		_ => Err((token.location, "Expected a value".to_string())),
+	};
+
+	match tokens.next() {
+		Some(Token {
+			kind: TokenKind::Open,
+			..
+		}) => {
+			let mut values = Vec::new();
+			loop {
+				match tokens.next() {
+					Some(Token {
+						kind: TokenKind::Close,
+						..
+					}) => break,
+					Some(token) => values.push(value(token)?),
+					None => return Err((location, "Unterminated list".to_string())),
+				}
+			}
+			Ok(values)
+		}
+		Some(token) => Ok(vec![value(token)?]),
+		None => Err((location, "Missing parameter value".to_string())),
+	}
+}
+
+fn parse(tokens: Vec<Token>) -> Result<Vec<Directive>, (Location, String)> {
+	let mut directives: Vec<Directive> = Vec::new();
+	let mut tokens = tokens.into_iter().peekable();
+
+	while let Some(token) = tokens.next() {
+		let TokenKind::Word(name) = token.kind else {
+			return Err((token.location, "Expected a directive".to_string()));
+		};
+		let mut directive = Directive {
+			strings: Vec::new(),
+			numbers: Vec::new(),
+			params: Vec::new(),
+			location: token.location,
+			name,
+		};
+		let strings = positional_strings(&directive.name);
+
+		while let Some(token) = tokens.next_if(|t| !matches!(t.kind, TokenKind::Word(_))) {
+			match token.kind {
+				TokenKind::String(s) if directive.strings.len() < strings => {
+					directive.strings.push(s)
+				}
+				TokenKind::String(declaration) => {
+					let mut words = declaration.split_whitespace();
+					let (Some(ty), Some(name), None) = (words.next(), words.next(), words.next())
+					else {
+						return Err((
+							token.location,
+							format!("Invalid parameter '{}'", declaration),
+						));
+					};
+					directive.params.push(Param {
+						ty: ty.to_string(),
+						name: name.to_string(),
+						values: values(&mut tokens, token.location)?,
+						location: token.location,
+					});
+				}
+				TokenKind::Number(n) => directive.numbers.push(n),
+				TokenKind::Open => loop {
+					match tokens.next() {
+						Some(Token {
+							kind: TokenKind::Number(n),
+							..
+						}) => directive.numbers.push(n),
+						Some(Token {
+							kind: TokenKind::Close,
+							..
+						}) => break,
+						_ => {
+							return Err((token.location, "Expected a list of numbers".to_string()))
+						}
+					}
+				},
+				_ => return Err((token.location, "Unexpected ']'".to_string())),
+			}
+		}
+
+		directives.push(directive);
+	}
+
+	Ok(directives)
+}
+
+#[derive(Clone)]
+struct Attributes {
+	material: Material,
+	/// Radiance of the `AreaLightSource` shapes are given.
+	area_light: Option<Colour>,
+}
+
+struct Importer<'a> {
+	path: &'a Path,
+	warnings: Vec<String>,
+	settings: RenderSettings,
+	camera: CameraDescription,
+	/// Degrees across the shorter image axis, as pbrt measures it.
+	fov: f32,
+	scene: Scene,
+	/// Current transformation, object to world.
+	ctm: Transform,
+	/// Reflection applied to the world when the camera transform mirrors,
+	/// which the cameras here cannot do.
+	world: Transform,
+	coordinate_systems: HashMap<String, Transform>,
+	attributes: Attributes,
+	/// Saved transformations, with the attributes for `AttributeBegin`.
+	stack: Vec<(Transform, Option<Attributes>)>,
+	materials: HashMap<String, Material>,
+	/// Name of the object being defined between `ObjectBegin` and
+	/// `ObjectEnd`, whose shapes are kept for its instances.
+	object: Option<String>,
+	/// Meshes of every object defined, in the space of the world block.
+	objects: HashMap<String, Vec<(Arc<Mesh>, Material)>>,
+}
+
+impl Importer<'_> {
+	fn error(&self, (line, column): Location, message: &str) -> io::Error {
+		io::Error::new(
+			ErrorKind::InvalidData,
+			format!("{}:{}:{}: {}", self.path.display(), line, column, message),
+		)
+	}
+
+	fn warn(&mut self, (line, column): Location, message: &str) {
+		self.warnings.push(format!(
+			"{}:{}:{}: {}",
+			self.path.display(),
+			line,
+			column,
+			message
+		));
+	}
+
+	fn vector(&self, d: &Directive, count: usize) -> io::Result<Vec3f> {
+		self.numbers(d, count).map(|n| Vec3f::new(n[0], n[1], n[2]))
+	}
+
+	fn numbers<'d>(&self, d: &'d Directive, count: usize) -> io::Result<&'d [f32]> {
+		if d.numbers.len() == count {
+			Ok(&d.numbers)
+		} else {
+			Err(self.error(
+				d.location,
+				&format!("'{}' expects {} numbers", d.name, count),
+			))
+		}
+	}
+
+	/// Spectrum parameter `name`, only RGB values are understood.
+	fn colour(&mut self, d: &Directive, name: &str, default: Colour) -> Colour {
+		let Some(param) = d.param(name) else {
+			return default;
+		};
+
+		match (param.ty.as_str(), &param.numbers()[..]) {
+			("rgb" | "color", &[r, g, b]) => Colour::from_rgb(r, g, b),
+			(ty, _) => {
+				let location = param.location;
+				self.warn(
+					location,
+					&format!("Unsupported {} value for '{}', using the default", ty, name),
+				);
+				default
+			}
+		}
+	}
+
+	fn object_to_world(&self) -> Transform {
+		self.world.mul(&self.ctm)
+	}
+
+	fn directive(&mut self, d: &Directive) -> io::Result<()> {
+		match d.name.as_str() {
+			"Identity" => self.ctm = Transform::IDENTITY,
+			"Translate" => self.ctm = self.ctm.mul(&Transform::translate(self.vector(d, 3)?)),
+			"Scale" => self.ctm = self.ctm.mul(&Transform::scale(self.vector(d, 3)?)),
+			"Rotate" => {
+				let n = self.numbers(d, 4)?;
+				let rotation = Transform::rotate(n[0], Vec3f::new(n[1], n[2], n[3]));
+				self.ctm = self.ctm.mul(&rotation);
+			}
+			"LookAt" => {
+				let n = self.numbers(d, 9)?;
+				let look_at = Transform::look_at(
+					Vec3f::new(n[0], n[1], n[2]),
+					Vec3f::new(n[3], n[4], n[5]),
+					Vec3f::new(n[6], n[7], n[8]),
+				);
+				self.ctm = self.ctm.mul(&look_at);
+			}
+			"Transform" => self.ctm = Transform::from_columns(self.numbers(d, 16)?),
+			"ConcatTransform" => {
+				self.ctm = self.ctm.mul(&Transform::from_columns(self.numbers(d, 16)?))
+			}
+			"CoordinateSystem" => {
+				self.coordinate_systems.insert(d.ty().to_string(), self.ctm);
+			}
+			"CoordSysTransform" => match self.coordinate_systems.get(d.ty()) {
+				Some(m) => self.ctm = *m,
+				None => self.warn(
+					d.location,
+					&format!("Unknown coordinate system '{}'", d.ty()),
+				),
+			},
+			"TransformBegin" => self.stack.push((self.ctm, None)),
+			"AttributeBegin" => self.stack.push((self.ctm, Some(self.attributes.clone()))),
+			"TransformEnd" | "AttributeEnd" => match self.stack.pop() {
+				Some((ctm, attributes)) => {
+					self.ctm = ctm;
+					if let Some(attributes) = attributes {
+						self.attributes = attributes;
+					}
+				}
+				None => self.warn(d.location, &format!("Unmatched '{}'", d.name)),
+			},
+			"Camera" => self.camera_directive(d)?,
+			"Film" => {
+				if d.ty() != "image" {
+					self.warn(
+						d.location,
+						&format!("Unsupported film '{}', using image", d.ty()),
+					);
+				}
+				if let Some(width) = d.float("xresolution") {
+					self.settings.width = (width as u32).max(1);
+				}
+				if let Some(height) = d.float("yresolution") {
+					self.settings.height = (height as u32).max(1);
+				}
+			}
+			"Sampler" => self.sampler_directive(d),
+			"Integrator" => {
+				if !matches!(d.ty(), "path" | "volpath") {
+					self.warn(
+						d.location,
+						&format!("Unsupported integrator '{}', using path tracing", d.ty()),
+					);
+				}
+				self.settings.max_depth = d.float("maxdepth").unwrap_or(5.).max(1.) as u32;
+			}
+			"WorldBegin" => {
+				self.ctm = Transform::IDENTITY;
+				self.coordinate_systems
+					.insert("world".to_string(), Transform::IDENTITY);
+			}
+			// Both sides of every surface are lit and emit.
+			"WorldEnd" | "ReverseOrientation" => {}
+			"Material" => self.attributes.material = self.material(d, d.ty()),
+			"MakeNamedMaterial" => {
+				let ty = d.string("type").unwrap_or("").to_string();
+				let material = self.material(d, &ty);
+				self.materials.insert(d.ty().to_string(), material);
+			}
+			"NamedMaterial" => match self.materials.get(d.ty()) {
+				Some(material) => self.attributes.material = material.clone(),
+				None => self.warn(d.location, &format!("Unknown material '{}'", d.ty())),
+			},
+			"LightSource" => self.light_directive(d)?,
+			"AreaLightSource" => {
+				if d.ty() == "diffuse" {
+					let one = Colour::from_rgb(1., 1., 1.);
+					let radiance = self.colour(d, "L", one.clone()) * self.colour(d, "scale", one);
+					self.attributes.area_light = Some(radiance);
+				} else {
+					self.warn(d.location, &format!("Unsupported area light '{}'", d.ty()));
+				}
+			}
+			"Shape" => self.shape_directive(d)?,
+			"ObjectBegin" => {
+				self.stack.push((self.ctm, Some(self.attributes.clone())));
+				self.objects.insert(d.ty().to_string(), Vec::new());
+				self.object = Some(d.ty().to_string());
+			}
+			"ObjectEnd" => {
+				self.object = None;
+				if let Some((ctm, Some(attributes))) = self.stack.pop() {
+					self.ctm = ctm;
+					self.attributes = attributes;
+				}
+			}
+			"ObjectInstance" => match self.objects.get(d.ty()) {
+				Some(parts) => {
+					// Shapes already carry the transformation they were
+					// defined with, the instance adds its own on top.
+					let m = self.object_to_world();
+					for (mesh, material) in parts {
+						let mut instance = Instance::new(mesh.clone(), material.clone());
+						instance.transform(&m);
+						if instance.is_singular() {
+							return Err(self
+								.error(d.location, "Object instance transformation is singular"));
+						}
+						self.scene.add_object(Box::new(instance));
+					}
+				}
+				None => self.warn(d.location, &format!("Unknown object '{}'", d.ty())),
+			},
+			"PixelFilter" | "Accelerator" | "Texture" | "MakeNamedMedium" | "MediumInterface"
+			| "Include" | "Import" | "ActiveTransform" | "TransformTimes" | "ColorSpace"
+			| "Option" => self.warn(d.location, &format!("Unsupported directive '{}'", d.name)),
+			_ => self.warn(d.location, &format!("Unknown directive '{}'", d.name)),
+		}
+
+		Ok(())
+	}
+
+	fn camera_directive(&mut self, d: &Directive) -> io::Result<()> {
+		let camera_to_world = self
+			.ctm
+			.inverse()
+			.ok_or_else(|| self.error(d.location, "Camera transformation is singular"))?;
+		self.coordinate_systems
+			.insert("camera".to_string(), camera_to_world);
+
+		// Scenes flip handedness with a mirroring camera transform.
+		self.world = place_camera(&camera_to_world, &mut self.camera, true);
+
+		self.camera.projection = match d.ty() {
+			"perspective" => Projection::Perspective,
+			"orthographic" => Projection::Orthographic,
+			"environment" => Projection::Equirectangular,
+			ty => {
+				self.warn(
+					d.location,
+					&format!("Unsupported camera '{}', using perspective", ty),
+				);
+				Projection::Perspective
+			}
+		};
+		// The orthographic screen window spans two units across the shorter
+		// axis, which a right angle covers at the target distance of one.
+		self.fov = match self.camera.projection {
+			Projection::Orthographic => 90.,
+			_ => d.float("fov").unwrap_or(90.),
+		};
+
+		let radius = d.float("lensradius").unwrap_or(0.);
+		self.camera.lens = (radius > 0.).then(|| ThinLens {
+			radius,
+			focus_distance: d.float("focaldistance").unwrap_or(1e6),
+			aperture: Aperture::Disc,
+		});
+
+		Ok(())
+	}
+
+	fn sampler_directive(&mut self, d: &Directive) {
+		let spp = d.float("pixelsamples").unwrap_or(16.);
+
+		let (sampler, spp) = match d.ty() {
+			"halton" => (SamplerKind::Halton, spp),
+			"sobol" => (SamplerKind::Sobol, spp),
+			"random" => (SamplerKind::Independent, spp),
+			"stratified" => (
+				SamplerKind::Stratified,
+				d.float("xsamples").unwrap_or(4.) * d.float("ysamples").unwrap_or(4.),
+			),
+			ty => {
+				self.warn(
+					d.location,
+					&format!("Unsupported sampler '{}', using sobol", ty),
+				);
+				(SamplerKind::Sobol, spp)
+			}
+		};
+		self.settings.sampler = sampler;
+		self.settings.spp = (spp as u32).max(1);
+	}
+
+	fn material(&mut self, d: &Directive, ty: &str) -> Material {
+		let grey = |v: f32| Colour::from_rgb(v, v, v);
+		let matte = |albedo: Colour| Material {
+			albedo,
+			specular: 0.,
+			metalic: 0.,
+			roughness: 0.,
+			emission: 0.,
+			texture: None,
+		};
+		let mirror = |albedo: Colour, roughness: f32| Material {
+			albedo,
+			specular: 1.,
+			metalic: 1.,
+			roughness,
+			emission: 0.,
+			texture: None,
+		};
+
+		match ty {
+			"matte" => matte(self.colour(d, "Kd", grey(0.5))),
+			"plastic" => {
+				let specular = self.colour(d, "Ks", grey(0.25)).luminance();
+				Material {
+					specular,
+					roughness: d.float("roughness").unwrap_or(0.1),
+					..matte(self.colour(d, "Kd", grey(0.25)))
+				}
+			}
+			"metal" => {
+				// Copper, pbrt's default metal.
+				let eta = self.colour(d, "eta", Colour::from_rgb(0.200, 0.924, 1.102));
+				let k = self.colour(d, "k", Colour::from_rgb(3.912, 2.452, 2.142));
+				let roughness = match (d.float("uroughness"), d.float("vroughness")) {
+					(Some(u), Some(v)) => (u + v) / 2.,
+					_ => d.float("roughness").unwrap_or(0.01),
+				};
+				mirror(conductor_reflectance(eta, k), roughness)
+			}
+			"mirror" => mirror(self.colour(d, "Kr", grey(0.9)), 0.),
+			"glass" => {
+				self.warn(
+					d.location,
+					"Refraction is not supported, rendering glass as a mirror",
+				);
+				mirror(self.colour(d, "Kr", grey(1.)), 0.)
+			}
+			ty => {
+				self.warn(
+					d.location,
+					&format!("Unsupported material '{}', using matte", ty),
+				);
+				matte(self.colour(d, "Kd", grey(0.5)))
+			}
+		}
+	}
+
+	fn light_directive(&mut self, d: &Directive) -> io::Result<()> {
+		let one = Colour::from_rgb(1., 1., 1.);
+		let scale = self.colour(d, "scale", one.clone());
+		let m = self.object_to_world();
+
+		match d.ty() {
+			"point" => {
+				let intensity = self.colour(d, "I", one) * scale;
+				let position = m.point(d.point("from", Vec3f::new(0., 0., 0.)));
+				self.scene
+					.add_light(Box::new(PointLight::new(position, intensity)));
+			}
+			"spot" => {
+				let intensity = self.colour(d, "I", one) * scale;
+				let from = d.point("from", Vec3f::new(0., 0., 0.));
+				let to = d.point("to", Vec3f::new(0., 0., 1.));
+				let cone = d.float("coneangle").unwrap_or(30.);
+				let delta = d.float("conedeltaangle").unwrap_or(5.);
+				self.scene.add_light(Box::new(SpotLight::new(
+					m.point(from),
+					m.vector(to - from),
+					(cone - delta).to_radians(),
+					cone.to_radians(),
+					intensity,
+				)));
+			}
+			"distant" => {
+				let irradiance = self.colour(d, "L", one) * scale;
+				let from = d.point("from", Vec3f::new(0., 0., 0.));
+				let to = d.point("to", Vec3f::new(0., 0., 1.));
+				self.scene
+					.add_light(Box::new(distant_light(m.vector(from - to), irradiance)));
+			}
+			"infinite" => {
+				let radiance = self.colour(d, "L", one) * scale;
+				let environment = match d.string("mapname") {
+					Some(map) if map.ends_with(".hdr") => {
+						let path = self.path.parent().unwrap_or(Path::new("")).join(map);
+						let environment = scaled_environment(&path, radiance).map_err(|e| {
+							self.error(d.location, &format!("Cannot load '{}': {}", map, e))
+						})?;
+						self.warn(
+							d.location,
+							"Environment maps are used without their light's transformation",
+						);
+						environment
+					}
+					Some(map) => {
+						self.warn(
+							d.location,
+							&format!(
+								"Only .hdr environment maps are supported, ignoring '{}'",
+								map
+							),
+						);
+						constant_environment(radiance)
+					}
+					None => constant_environment(radiance),
+				};
+				self.scene.set_environment(Box::new(environment));
+			}
+			ty => self.warn(d.location, &format!("Unsupported light '{}'", ty)),
+		}
+
+		Ok(())
+	}
+
+	fn shape_directive(&mut self, d: &Directive) -> io::Result<()> {
+		let faces = match d.ty() {
+			"trianglemesh" => {
+				let positions: Vec<Vec3f> = d
+					.param("P")
+					.ok_or_else(|| self.error(d.location, "Triangle mesh without 'P'"))?
+					.numbers()
+					.chunks_exact(3)
+					.map(|p| Vec3f::new(p[0], p[1], p[2]))
+					.collect();
+				let indices: Vec<usize> = match d.param("indices") {
+					Some(indices) => indices.numbers().iter().map(|i| *i as usize).collect(),
+					None if positions.len() == 3 => vec![0, 1, 2],
+					None => return Err(self.error(d.location, "Triangle mesh without 'indices'")),
+				};
+
+				let mut faces = Vec::with_capacity(indices.len() / 3);
+				for i in indices.chunks_exact(3) {
+					let corner = |i: usize| {
+						positions
+							.get(i)
+							.copied()
+							.ok_or_else(|| self.error(d.location, "Vertex index out of range"))
+					};
+					faces.push(Polygon3::new(corner(i[0])?, corner(i[1])?, corner(i[2])?));
+				}
+				faces
+			}
+			"plymesh" => {
+				let filename = d
+					.string("filename")
+					.ok_or_else(|| self.error(d.location, "PLY mesh without 'filename'"))?;
+				let path = self.path.parent().unwrap_or(Path::new("")).join(filename);
+				load_ply_faces(path).map_err(|e| {
+					self.error(d.location, &format!("Cannot load '{}': {}", filename, e))
+				})?
+			}
+			ty => {
+				self.warn(d.location, &format!("Unsupported shape '{}'", ty));
+				return Ok(());
+			}
+		};
+
+		let material = match &self.attributes.area_light {
+			Some(radiance) => emissive(radiance.clone()),
+			None => self.attributes.material.clone(),
+		};
+		if let Some(name) = &self.object {
+			let faces = faces.into_iter().map(|f| f.transform(&self.ctm)).collect();
+			if let Some(parts) = self.objects.get_mut(name) {
+				parts.push((Arc::new(Mesh::new(faces)), material));
+			}
+			return Ok(());
+		}
+
+		let m = self.object_to_world();
+		let faces = faces.into_iter().map(|f| f.transform(&m)).collect();
+		self.scene
+			.add_object(Box::new(SolidObject::new(faces, material)));
+
+		Ok(())
+	}
+}
+
+/// Imports the core of a pbrt-v3 scene: cameras, meshes, basic materials and
+/// lights. Anything else is skipped with a warning.
+pub fn load_pbrt<P: AsRef<Path>>(path: P) -> io::Result<SceneDescription> {
+	let path = path.as_ref();
+	let source = fs::read_to_string(path)?;
+
+	let syntax_error = |((line, column), message): (Location, String)| {
+		io::Error::new(
+			ErrorKind::InvalidData,
+			format!("{}:{}:{}: {}", path.display(), line, column, message),
+		)
+	};
+	let directives = parse(tokenize(&source).map_err(syntax_error)?).map_err(syntax_error)?;
+
+	let mut scene = Scene::new();
+	// pbrt scenes are dark where no light is given.
+	scene.set_environment(Box::new(constant_environment(Colour::new())));
+
+	let mut importer = Importer {
+		path,
+		warnings: Vec::new(),
+		settings: RenderSettings {
+			width: 640,
+			height: 480,
+			spp: 16,
+			max_depth: 5,
+			sampler: SamplerKind::Halton,
+			seed: 0,
+		},
+		camera: CameraDescription::new(),
+		fov: 90.,
+		scene,
+		ctm: Transform::IDENTITY,
+		world: Transform::IDENTITY,
+		coordinate_systems: HashMap::new(),
+		attributes: Attributes {
+			material: Material {
+				albedo: Colour::from_rgb(0.5, 0.5, 0.5),
+				specular: 0.,
+				metalic: 0.,
+				roughness: 0.,
+				emission: 0.,
+				texture: None,
+			},
+			area_light: None,
+		},
+		stack: Vec::new(),
+		materials: HashMap::new(),
+		object: None,
+		objects: HashMap::new(),
+	};
+	for directive in &directives {
+		importer.directive(directive)?;
+	}
+
+	let Importer {
+		mut camera,
+		fov,
+		settings,
+		scene,
+		warnings,
+		..
+	} = importer;
+	let fov = fov.to_radians();
+	camera.fov = if settings.width >= settings.height {
+		Fov::Vertical(fov)
+	} else {
+		Fov::Horizontal(fov)
+	};
+
+	Ok(SceneDescription {
+		scene,
+		camera,
+		settings,
+		warnings,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::assert_close;
+	use crate::{
+		geometry::Ray,
+		loader::tests::{hit_point, scratch},
+	};
+
+	fn load(name: &str, source: &str) -> io::Result<SceneDescription> {
+		let directory = scratch(name, &[("scene.pbrt", source.as_bytes())]);
+		load_pbrt(directory.join("scene.pbrt"))
+	}
+
+	#[test]
+	fn tokenizes_words_strings_and_numbers() {
+		let tokens =
+			tokenize("Shape \"sphere\" # a comment\n  \"float radius\" [-1.5e1 true]").unwrap();
+		let kinds: Vec<TokenKind> = tokens.iter().map(|t| t.kind.clone()).collect();
+		assert_eq!(
+			kinds,
+			[
+				TokenKind::Word("Shape".to_string()),
+				TokenKind::String("sphere".to_string()),
+				TokenKind::String("float radius".to_string()),
+				TokenKind::Open,
+				TokenKind::Number(-15.),
+				TokenKind::String("true".to_string()),
+				TokenKind::Close,
+			]
+		);
+		let locations: Vec<Location> = tokens.iter().map(|t| t.location).collect();
+		assert_eq!(
+			locations,
+			[(1, 1), (1, 7), (2, 3), (2, 18), (2, 19), (2, 26), (2, 30)]
+		);
+
+		assert_eq!(
+			tokenize("Shape \"sphere").err(),
+			Some(((1, 7), "Unterminated string".to_string()))
+		);
+		assert_eq!(
+			tokenize("\nScale 1 2x 3").err(),
+			Some(((2, 9), "Invalid number '2x'".to_string()))
+		);
+	}
+
+	#[test]
+	fn parses_directives() {
+		let directives = parse(
+			tokenize(
+				"LookAt 0 0 5 0 0 0 0 1 0\n\
+				Texture \"checks\" \"spectrum\" \"checkerboard\" \"float uscale\" 4\n\
+				Shape \"trianglemesh\" \"point P\" [0 0 0 1 0 0 0 1 0] \"integer indices\" [0 1 2]",
+			)
+			.unwrap(),
+		)
+		.unwrap();
+		assert_eq!(directives.len(), 3);
+
+		assert_eq!(directives[0].name, "LookAt");
+		assert_eq!(directives[0].numbers, [0., 0., 5., 0., 0., 0., 0., 1., 0.]);
+
+		assert_eq!(
+			directives[1].strings,
+			["checks", "spectrum", "checkerboard"]
+		);
+		assert_eq!(directives[1].float("uscale"), Some(4.));
+
+		let shape = &directives[2];
+		assert_eq!((shape.ty(), shape.location), ("trianglemesh", (3, 1)));
+		assert_eq!(shape.param("P").unwrap().ty, "point");
+		assert_eq!(shape.param("P").unwrap().numbers().len(), 9);
+		assert_eq!(shape.param("indices").unwrap().numbers(), [0., 1., 2.]);
+		assert!(shape.param("N").is_none());
+
+		let error = |source: &str| parse(tokenize(source).unwrap()).err().unwrap();
+		assert_eq!(error("1 2 3").1, "Expected a directive");
+		assert_eq!(
+			error("Shape \"sphere\" \"radius\" 1").1,
+			"Invalid parameter 'radius'"
+		);
+		assert_eq!(
+			error("Shape \"sphere\" \"float radius\" [1").1,
+			"Unterminated list"
+		);
+		assert_eq!(error("Translate [1 \"x\"]").1, "Expected a list of numbers");
+	}
+
+	#[test]
+	fn imports_the_camera_and_settings() {
+		let description = load(
+			"pbrt-camera",
+			r#"
+Film "image" "integer xresolution" [200] "integer yresolution" [100]
+Sampler "stratified" "integer xsamples" [2] "integer ysamples" [3]
+Integrator "path" "integer maxdepth" [7]
+LookAt 1 2 5  1 2 0  0 1 0
+Camera "perspective" "float fov" [40] "float lensradius" [0.5] "float focaldistance" [4]
+WorldBegin
+WorldEnd
+"#,
+		)
+		.unwrap();
+
+		let settings = description.settings;
+		assert_eq!((settings.width, settings.height), (200, 100));
+		assert_eq!(
+			(settings.sampler, settings.spp),
+			(SamplerKind::Stratified, 6)
+		);
+		assert_eq!(settings.max_depth, 7);
+
+		let camera = description.camera;
+		assert_close(camera.position, Vec3f::new(1., 2., 5.));
+		assert_close(camera.target, Vec3f::new(1., 2., 4.));
+		assert_close(camera.up, Vec3f::new(0., 1., 0.));
+		// The field of view spans the shorter image axis.
+		assert!(
+			matches!(camera.fov, Fov::Vertical(fov) if (fov - 40f32.to_radians()).abs() < 1e-6)
+		);
+		let lens = camera.lens.unwrap();
+		assert_eq!((lens.radius, lens.focus_distance), (0.5, 4.));
+		assert!(description.warnings.is_empty());
+	}
+
+	#[test]
+	fn imports_shapes_in_world_space() {
+		let description = load(
+			"pbrt-shapes",
+			r#"
+LookAt 0 0 5  0 0 0  0 1 0
+Camera "perspective"
+WorldBegin
+AttributeBegin
+  Material "matte" "rgb Kd" [0.1 0.2 0.3]
+  Translate 2 0 0
+  Shape "trianglemesh" "point P" [-1 -1 0  1 -1 0  1 1 0  -1 1 0] "integer indices" [0 1 2 0 2 3]
+AttributeEnd
+AttributeBegin
+  AreaLightSource "diffuse" "rgb L" [5 5 5]
+  Shape "trianglemesh" "point P" [-1 -1 -1  1 -1 -1  0 1 -1]
+AttributeEnd
+Shape "sphere"
+WorldEnd
+"#,
+		)
+		.unwrap();
+		let scene = &description.scene;
+
+		assert_close(
+			hit_point(scene, [2.5, 0.5, 5.], [0., 0., -1.]).unwrap(),
+			Vec3f::new(2.5, 0.5, 0.),
+		);
+		assert_eq!(
+			hit_point(scene, [0., 0.5, 5.], [0., 0., -1.]).map(|p| p.z),
+			Some(-1.)
+		);
+		let plane = scene.hit(&Ray::new(Vec3f::new(2., 0., 5.), Vec3f::new(0., 0., -1.)));
+		let plane = plane.unwrap();
+		assert_eq!(plane.object.material().unwrap().albedo.b, 0.3);
+		assert_eq!(plane.light, None);
+
+		let lamp = scene.hit(&Ray::new(Vec3f::new(0., 0., 5.), Vec3f::new(0., 0., -1.)));
+		let lamp = lamp.unwrap();
+		assert!(lamp.light.is_some());
+		assert_eq!(lamp.object.material().unwrap().albedo.r, 5.);
+
+		assert_eq!(description.warnings.len(), 1);
+		assert!(description.warnings[0].ends_with(":14:1: Unsupported shape 'sphere'"));
+	}
+
+	#[test]
+	fn mirrors_the_world_for_flipped_cameras() {
+		let description = load(
+			"pbrt-mirror",
+			r#"
+Scale -1 1 1
+LookAt 0 0 5  0 0 0  0 1 0
+Camera "perspective"
+WorldBegin
+Shape "trianglemesh" "point P" [1 0 0  3 0 0  2 1 0]
+WorldEnd
+"#,
+		)
+		.unwrap();
+
+		// `Scale -1 1 1` flips the image, which the camera cannot do, so the
+		// scene is mirrored through the camera instead.
+		assert_close(
+			hit_point(&description.scene, [-2., 0.5, 5.], [0., 0., -1.]).unwrap(),
+			Vec3f::new(-2., 0.5, 0.),
+		);
+		assert_eq!(
+			hit_point(&description.scene, [2., 0.5, 5.], [0., 0., -1.]),
+			None
+		);
+	}
+
+	#[test]
+	fn instances_objects() {
+		let description = load(
+			"pbrt-instances",
+			r#"
+WorldBegin
+ObjectBegin "triangle"
+  Translate 0 0 -1
+  Shape "trianglemesh" "point P" [0 0 0  1 0 0  0 1 0]
+ObjectEnd
+ObjectInstance "triangle"
+Translate 10 0 0
+ObjectInstance "triangle"
+ObjectInstance "square"
+WorldEnd
+"#,
+		)
+		.unwrap();
+		let scene = &description.scene;
+
+		assert_close(
+			hit_point(scene, [0.25, 0.25, 5.], [0., 0., -1.]).unwrap(),
+			Vec3f::new(0.25, 0.25, -1.),
+		);
+		assert_close(
+			hit_point(scene, [10.25, 0.25, 5.], [0., 0., -1.]).unwrap(),
+			Vec3f::new(10.25, 0.25, -1.),
+		);
+		assert_eq!(hit_point(scene, [5.25, 0.25, 5.], [0., 0., -1.]), None);
+		assert_eq!(description.warnings.len(), 1);
+		assert!(description.warnings[0].ends_with(":10:1: Unknown object 'square'"));
+	}
+
+	#[test]
+	fn reports_where_errors_are() {
+		let error = |name: &str, source: &str| load(name, source).err().unwrap().to_string();
+
+		let message = error(
+			"pbrt-syntax",
+			"WorldBegin\nShape \"trianglemesh\" \"point P\" [0 0 0",
+		);
+		assert!(message.ends_with(":2:22: Unterminated list"), "{}", message);
+
+		let message = error("pbrt-count", "Translate 1 2");
+		assert!(
+			message.ends_with(":1:1: 'Translate' expects 3 numbers"),
+			"{}",
+			message
+		);
+
+		let message = error(
+			"pbrt-indices",
+			"Shape \"trianglemesh\" \"point P\" [0 0 0 1 0 0 0 1 0] \"integer indices\" [0 1 3]",
+		);
+		assert!(
+			message.ends_with(":1:1: Vertex index out of range"),
+			"{}",
+			message
+		);
+
+		let message = error("pbrt-camera", "Scale 1 0 1\nCamera \"perspective\"");
+		assert!(
+			message.ends_with(":2:1: Camera transformation is singular"),
+			"{}",
+			message
+		);
+
+		let message = error(
+			"pbrt-instance",
+			"ObjectBegin \"a\"\nShape \"trianglemesh\" \"point P\" [0 0 0 1 0 0 0 1 0]\nObjectEnd\nScale 0 1 1\nObjectInstance \"a\"",
+		);
+		assert!(
+			message.ends_with(":5:1: Object instance transformation is singular"),
+			"{}",
+			message
+		);
+
+		let message = error(
+			"pbrt-ply",
+			"Shape \"plymesh\" \"string filename\" \"missing.ply\"",
+		);
+		assert!(
+			message.contains(":1:1: Cannot load 'missing.ply'"),
+			"{}",
+			message
+		);
+	}
+}
@@ -0,0 +1,480 @@
+use std::{fs, path::Path, str::SplitAsciiWhitespace};
+
+use crate::{
+	geometry::{Polygon3, SolidObject, Vec3f},
+	image::srgb_to_linear,
+};
+
+use super::{mesh_material, LoadError};
+
+fn invalid<T>(message: &str) -> Result<T, LoadError> {
+	Err(LoadError::Parse(message.to_string()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+	Ascii,
+	LittleEndian,
+	BigEndian,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Scalar {
+	I8,
+	U8,
+	I16,
+	U16,
+	I32,
+	U32,
+	F32,
+	F64,
+}
+
+impl Scalar {
+	/// Largest value of integer types, which colours are stored as fractions
+	/// of.
+	fn max(self) -> Option<f32> {
+		match self {
+			Scalar::I8 => Some(i8::MAX as f32),
+			Scalar::U8 => Some(u8::MAX as f32),
+			Scalar::I16 => Some(i16::MAX as f32),
+			Scalar::U16 => Some(u16::MAX as f32),
+			Scalar::I32 => Some(i32::MAX as f32),
+			Scalar::U32 => Some(u32::MAX as f32),
+			Scalar::F32 | Scalar::F64 => None,
+		}
+	}
+
+	fn parse(name: &str) -> Result<Self, LoadError> {
+		Ok(match name {
+			"char" | "int8" => Scalar::I8,
+			"uchar" | "uint8" => Scalar::U8,
+			"short" | "int16" => Scalar::I16,
+			"ushort" | "uint16" => Scalar::U16,
+			"int" | "int32" => Scalar::I32,
+			"uint" | "uint32" => Scalar::U32,
+			"float" | "float32" => Scalar::F32,
+			"double" | "float64" => Scalar::F64,
+			_ => return invalid(&format!("Unknown property type '{}'", name)),
+		})
+	}
+}
+
+struct Property {
+	name: String,
+	/// Type of the item count for list properties.
+	count: Option<Scalar>,
+	scalar: Scalar,
+}
+
+struct Element {
+	name: String,
+	count: usize,
+	properties: Vec<Property>,
+}
+
+/// Element data following the header.
+enum Body<'a> {
+	Ascii(SplitAsciiWhitespace<'a>),
+	Binary {
+		data: &'a [u8],
+		pos: usize,
+		big_endian: bool,
+	},
+}
+
+macro_rules! read_binary {
+	($data:expr, $pos:expr, $big_endian:expr, $t:ty) => {{
+		const SIZE: usize = std::mem::size_of::<$t>();
+		let bytes: [u8; SIZE] = $data
+			.get(*$pos..*$pos + SIZE)
+			.and_then(|b| b.try_into().ok())
+			.ok_or_else(|| LoadError::Parse("Truncated PLY data".to_string()))?;
+		*$pos += SIZE;
+		if $big_endian {
+			<$t>::from_be_bytes(bytes) as f64
+		} else {
+			<$t>::from_le_bytes(bytes) as f64
+		}
+	}};
+}
+
+impl Body<'_> {
+	fn read(&mut self, scalar: Scalar) -> Result<f64, LoadError> {
+		match self {
+			Body::Ascii(tokens) => {
+				let token = tokens
+					.next()
+					.ok_or_else(|| LoadError::Parse("Truncated PLY data".to_string()))?;
+				token
+					.parse()
+					.or_else(|_| invalid(&format!("Invalid number '{}'", token)))
+			}
+			Body::Binary {
+				data,
+				pos,
+				big_endian,
+			} => Ok(match scalar {
+				Scalar::I8 => read_binary!(data, pos, *big_endian, i8),
+				Scalar::U8 => read_binary!(data, pos, *big_endian, u8),
+				Scalar::I16 => read_binary!(data, pos, *big_endian, i16),
+				Scalar::U16 => read_binary!(data, pos, *big_endian, u16),
+				Scalar::I32 => read_binary!(data, pos, *big_endian, i32),
+				Scalar::U32 => read_binary!(data, pos, *big_endian, u32),
+				Scalar::F32 => read_binary!(data, pos, *big_endian, f32),
+				Scalar::F64 => read_binary!(data, pos, *big_endian, f64),
+			}),
+		}
+	}
+}
+
+fn read_line<'a>(data: &'a [u8], pos: &mut usize) -> Result<&'a str, LoadError> {
+	let start = *pos;
+	let end = data[start..]
+		.iter()
+		.position(|b| *b == b'\n')
+		.map(|i| start + i)
+		.ok_or_else(|| LoadError::Parse("Unterminated header".to_string()))?;
+	*pos = end + 1;
+
+	std::str::from_utf8(&data[start..end])
+		.map(|line| line.trim_end_matches('\r'))
+		.map_err(|_| LoadError::Parse("Header is not valid text".to_string()))
+}
+
+/// Vertex properties kept, by their slot in `Vertex`.
+fn vertex_slot(name: &str) -> Option<usize> {
+	Some(match name {
+		"x" => 0,
+		"y" => 1,
+		"z" => 2,
+		"nx" => 3,
+		"ny" => 4,
+		"nz" => 5,
+		"u" | "s" | "texture_u" | "texture_s" => 6,
+		"v" | "t" | "texture_v" | "texture_t" => 7,
+		"red" | "r" => 8,
+		"green" | "g" => 9,
+		"blue" | "b" => 10,
+		_ => return None,
+	})
+}
+
+type Vertex = [f32; 11];
+
+/// Reads a PLY mesh, ASCII or binary, as an object in a plain grey material.
+/// Per-vertex normals, texture coordinates and colours are kept, colours
+/// stored as integers being taken as sRGB.
+pub fn load_ply<P: AsRef<Path>>(path: P) -> Result<SolidObject, LoadError> {
+	Ok(SolidObject::new(load_ply_faces(path)?, mesh_material()))
+}
+
+/// Triangles of a PLY mesh, for formats that give the material themselves.
+/// Polygons with more than three corners are split into fans.
+pub fn load_ply_faces<P: AsRef<Path>>(path: P) -> Result<Vec<Polygon3>, LoadError> {
+	decode(&fs::read(path)?)
+}
+
+fn decode(data: &[u8]) -> Result<Vec<Polygon3>, LoadError> {
+	let mut pos = 0;
+	if read_line(data, &mut pos)? != "ply" {
+		return invalid("Not a PLY file");
+	}
+
+	let mut format = None;
+	let mut elements: Vec<Element> = Vec::new();
+	loop {
+		let line = read_line(data, &mut pos)?;
+		let words: Vec<&str> = line.split_ascii_whitespace().collect();
+		match words[..] {
+			["end_header"] => break,
+			["format", name, _] => {
+				format = Some(match name {
+					"ascii" => Format::Ascii,
+					"binary_little_endian" => Format::LittleEndian,
+					"binary_big_endian" => Format::BigEndian,
+					_ => return invalid(&format!("Unknown PLY format '{}'", name)),
+				})
+			}
+			["element", name, count] => elements.push(Element {
+				name: name.to_string(),
+				count: count
+					.parse()
+					.or_else(|_| invalid(&format!("Invalid element count '{}'", count)))?,
+				properties: Vec::new(),
+			}),
+			["property", "list", count, scalar, name] => elements
+				.last_mut()
+				.ok_or_else(|| LoadError::Parse("Property before element".to_string()))?
+				.properties
+				.push(Property {
+					name: name.to_string(),
+					count: Some(Scalar::parse(count)?),
+					scalar: Scalar::parse(scalar)?,
+				}),
+			["property", scalar, name] => elements
+				.last_mut()
+				.ok_or_else(|| LoadError::Parse("Property before element".to_string()))?
+				.properties
+				.push(Property {
+					name: name.to_string(),
+					count: None,
+					scalar: Scalar::parse(scalar)?,
+				}),
+			["comment", ..] | ["obj_info", ..] | [] => {}
+			_ => return invalid(&format!("Unexpected header line '{}'", line)),
+		}
+	}
+
+	let mut body = match format {
+		Some(Format::Ascii) => Body::Ascii(
+			std::str::from_utf8(&data[pos..])
+				.or_else(|_| invalid("ASCII data is not valid text"))?
+				.split_ascii_whitespace(),
+		),
+		Some(format) => Body::Binary {
+			data,
+			pos,
+			big_endian: format == Format::BigEndian,
+		},
+		None => return invalid("Missing PLY format"),
+	};
+
+	// Which vertex properties the file has.
+	let mut present = [false; 11];
+	if let Some(vertex) = elements.iter().find(|e| e.name == "vertex") {
+		for property in &vertex.properties {
+			if let (Some(slot), None) = (vertex_slot(&property.name), property.count) {
+				present[slot] = true;
+			}
+		}
+	}
+	let has = |slots: std::ops::Range<usize>| present[slots].iter().all(|p| *p);
+	if !has(0..3) {
+		return Err(LoadError::MissingAttribute("vertex positions"));
+	}
+
+	let mut vertices: Vec<Vertex> = Vec::new();
+	let mut polygons: Vec<Vec<usize>> = Vec::new();
+	for element in &elements {
+		for _ in 0..element.count {
+			let mut vertex = [0.; 11];
+			for property in &element.properties {
+				match property.count {
+					Some(count) => {
+						// Nothing is reserved for the count, which could be anything
+						// in a broken file.
+						let count = body.read(count)? as usize;
+						let mut items = Vec::new();
+						for _ in 0..count {
+							items.push(body.read(property.scalar)? as usize);
+						}
+						if element.name == "face"
+							&& matches!(property.name.as_str(), "vertex_indices" | "vertex_index")
+						{
+							polygons.push(items);
+						}
+					}
+					None => {
+						let value = body.read(property.scalar)? as f32;
+						let Some(slot) = vertex_slot(&property.name) else {
+							continue;
+						};
+						vertex[slot] = match (slot, property.scalar.max()) {
+							(8..=10, Some(max)) => srgb_to_linear(value / max),
+							_ => value,
+						};
+					}
+				}
+			}
+			if element.name == "vertex" {
+				vertices.push(vertex);
+			}
+		}
+	}
+
+	let mut faces = Vec::new();
+	for polygon in polygons {
+		let corner = |i: usize| {
+			vertices
+				.get(polygon[i])
+				.ok_or_else(|| LoadError::Parse("Vertex index out of range".to_string()))
+		};
+		for i in 2..polygon.len() {
+			let corners = [corner(0)?, corner(i - 1)?, corner(i)?];
+			let [a, b, c] = corners.map(|v| Vec3f::new(v[0], v[1], v[2]));
+			let mut face = Polygon3::new(a, b, c);
+			if has(3..6) {
+				face = face.with_normals(corners.map(|v| Vec3f::new(v[3], v[4], v[5])));
+			}
+			if has(6..8) {
+				face = face.with_uvs(corners.map(|v| (v[6], v[7])));
+			}
+			if has(8..11) {
+				face = face.with_colours(corners.map(|v| [v[8], v[9], v[10]]));
+			}
+			faces.push(face);
+		}
+	}
+
+	Ok(faces)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const HEADER: &str = "\
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+";
+
+	/// A unit square in the plane `z = 0` in the given format, its first
+	/// corner white and the others black.
+	fn square(format: &str) -> Vec<u8> {
+		let mut data =
+			format!("ply\nformat {} 1.0\ncomment a square\n{}", format, HEADER).into_bytes();
+		let corners = [[0., 0.], [1., 0.], [1., 1.], [0., 1.]];
+		let big_endian = format == "binary_big_endian";
+		for (i, [x, y]) in corners.into_iter().enumerate() {
+			let colour = if i == 0 { 255 } else { 0 };
+			if format == "ascii" {
+				data.extend(format!("{} {} 0 {} {} {}\n", x, y, colour, colour, colour).bytes());
+			} else {
+				for v in [x, y, 0f32] {
+					data.extend(if big_endian {
+						v.to_be_bytes()
+					} else {
+						v.to_le_bytes()
+					});
+				}
+				data.extend([colour; 3]);
+			}
+		}
+		if format == "ascii" {
+			data.extend(b"4 0 1 2 3\n");
+		} else {
+			data.push(4);
+			for i in 0..4i32 {
+				data.extend(if big_endian {
+					i.to_be_bytes()
+				} else {
+					i.to_le_bytes()
+				});
+			}
+		}
+		data
+	}
+
+	#[test]
+	fn reads_every_format() {
+		for format in ["ascii", "binary_little_endian", "binary_big_endian"] {
+			let faces = decode(&square(format)).unwrap();
+			assert_eq!(faces.len(), 2, "{}", format);
+			assert_eq!(
+				[faces[1].a, faces[1].b, faces[1].c],
+				[
+					Vec3f::new(0., 0., 0.),
+					Vec3f::new(1., 1., 0.),
+					Vec3f::new(0., 1., 0.)
+				],
+				"{}",
+				format
+			);
+			assert_eq!(faces[0].normal, Vec3f::new(0., 0., 1.));
+			assert!(faces[0].normals.is_none() && faces[0].uvs.is_none());
+			assert_eq!(faces[0].colours.unwrap()[0], [1., 1., 1.]);
+			assert_eq!(faces[0].colours.unwrap()[1], [0., 0., 0.]);
+		}
+	}
+
+	#[test]
+	fn keeps_normals_and_texture_coordinates() {
+		let data = b"ply\n\
+format ascii 1.0\n\
+element vertex 3\n\
+property double x\n\
+property double y\n\
+property double z\n\
+property float nx\n\
+property float ny\n\
+property float nz\n\
+property float s\n\
+property float t\n\
+property int flags\n\
+element face 1\n\
+property list uchar uint vertex_index\n\
+end_header\n\
+0 0 0 0 0 2 0 0 7\n\
+1 0 0 0 0 2 1 0 7\n\
+0 1 0 0 0 2 0 1 7\n\
+3 0 1 2\n";
+		let faces = decode(data).unwrap();
+		assert_eq!(faces.len(), 1);
+		assert_eq!(faces[0].normals, Some([Vec3f::new(0., 0., 1.); 3]));
+		assert_eq!(faces[0].uvs, Some([(0., 0.), (1., 0.), (0., 1.)]));
+		assert!(faces[0].colours.is_none());
+	}
+
+	#[test]
+	fn rejects_malformed_files() {
+		let message = |data: &[u8]| decode(data).err().unwrap().to_string();
+
+		let mut truncated = square("binary_little_endian");
+		truncated.truncate(truncated.len() - 2);
+		assert_eq!(message(&truncated), "Truncated PLY data");
+		let ascii = String::from_utf8(square("ascii")).unwrap();
+		assert_eq!(
+			message(ascii.replace("4 0 1 2 3", "4 0 1 2").as_bytes()),
+			"Truncated PLY data"
+		);
+		assert_eq!(
+			message(ascii.replace("4 0 1 2 3", "3 0 1 9").as_bytes()),
+			"Vertex index out of range"
+		);
+		assert_eq!(
+			message(ascii.replace("1 1 0", "1 x 0").as_bytes()),
+			"Invalid number 'x'"
+		);
+
+		assert_eq!(message(b"obj\n"), "Not a PLY file");
+		assert_eq!(message(b"ply\nformat ascii 1.0\n"), "Unterminated header");
+		assert_eq!(
+			message(b"ply\nformat ebcdic 1.0\nend_header\n"),
+			"Unknown PLY format 'ebcdic'"
+		);
+		assert_eq!(
+			message(b"ply\nelement vertex 0\nend_header\n"),
+			"Missing PLY format"
+		);
+		assert_eq!(
+			message(b"ply\nproperty float x\nend_header\n"),
+			"Property before element"
+		);
+		assert_eq!(
+			message(b"ply\nelement vertex 0\nproperty half x\nend_header\n"),
+			"Unknown property type 'half'"
+		);
+	}
+
+	#[test]
+	fn needs_vertex_positions() {
+		let data = b"ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nproperty float y\nend_header\n0 0\n";
+		assert!(matches!(
+			decode(data),
+			Err(LoadError::MissingAttribute("vertex positions"))
+		));
+		assert!(matches!(
+			load_ply(std::env::temp_dir().join("path-tracing-missing.ply")),
+			Err(LoadError::Io(_))
+		));
+	}
+}
@@ -0,0 +1,950 @@
+use std::{
+	collections::{hash_map::Entry, BTreeMap, HashMap},
+	fmt::Display,
+	fs,
+	io::{self, ErrorKind},
+	ops::Range,
+	path::{Path, PathBuf},
+	str::FromStr,
+	sync::Arc,
+};
+
+use serde::{de, Deserialize, Deserializer};
+use toml::Spanned;
+
+use crate::{
+	camera::{
+		Aperture, CameraDescription, Exposure, Fov, Projection, Shutter, StereoLayout, StereoRig,
+		ThinLens,
+	},
+	geometry::{Instance, Mesh, Motion, Object, Scene, SolidObject, Transform, Vec3f, WithScale},
+	image::Colour,
+	light::{EnvironmentMap, Gradient, IesProfile, LightSampling, PointLight, Sky, SpotLight},
+	material::Material,
+	sampler::SamplerKind,
+};
+
+use super::{load_gltf, load_obj, load_ply, load_stl, LoadError, RenderSettings, SceneDescription};
+
+/// A value read from a string by its `FromStr` implementation, so unknown
+/// names are reported where they appear in the file.
+struct Parsed<T>(T);
+
+impl<'de, T: FromStr<Err = String>> Deserialize<'de> for Parsed<T> {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		String::deserialize(deserializer)?
+			.parse()
+			.map(Parsed)
+			.map_err(de::Error::custom)
+	}
+}
+
+/// A count of at least one.
+struct Count(u32);
+
+impl<'de> Deserialize<'de> for Count {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		match u32::deserialize(deserializer)? {
+			0 => Err(de::Error::custom("expected at least 1")),
+			n => Ok(Count(n)),
+		}
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneFile {
+	#[serde(default)]
+	settings: SettingsSpec,
+	#[serde(default)]
+	camera: CameraSpec,
+	environment: Option<Spanned<EnvironmentSpec>>,
+	#[serde(default)]
+	materials: BTreeMap<String, MaterialSpec>,
+	#[serde(default)]
+	lights: Vec<Spanned<LightSpec>>,
+	#[serde(default)]
+	objects: Vec<Spanned<ObjectSpec>>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct SettingsSpec {
+	width: Option<Count>,
+	height: Option<Count>,
+	spp: Option<Count>,
+	max_depth: Option<Count>,
+	sampler: Option<Parsed<SamplerKind>>,
+	seed: Option<u64>,
+	light_sampling: Option<Parsed<LightSampling>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum FovAxis {
+	Horizontal,
+	Vertical,
+}
+
+/// Angles are in degrees.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct CameraSpec {
+	projection: Option<Parsed<Projection>>,
+	position: Option<Spanned<[f32; 3]>>,
+	target: Option<Spanned<[f32; 3]>>,
+	up: Option<[f32; 3]>,
+	fov: Option<f32>,
+	fov_axis: Option<FovAxis>,
+	aperture_radius: f32,
+	/// Distance to the target when not given.
+	focus_distance: Option<f32>,
+	aperture_blades: u32,
+	shutter: Option<Spanned<[f32; 2]>>,
+	exposure: Option<Spanned<ExposureSpec>>,
+	stereo: Option<Spanned<StereoSpec>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExposureSpec {
+	ev100: Option<f32>,
+	iso: Option<f32>,
+	shutter_time: Option<f32>,
+	f_number: Option<f32>,
+	#[serde(default)]
+	compensation: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StereoSpec {
+	interocular: f32,
+	convergence: f32,
+	layout: Parsed<StereoLayout>,
+}
+
+/// Sky angles are in degrees.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case", deny_unknown_fields)]
+enum EnvironmentSpec {
+	Gradient,
+	Map {
+		path: PathBuf,
+	},
+	Sky {
+		elevation: f32,
+		azimuth: f32,
+		#[serde(default = "default_turbidity")]
+		turbidity: f32,
+		#[serde(default = "default_true")]
+		sun: bool,
+	},
+}
+
+fn default_turbidity() -> f32 {
+	3.
+}
+
+fn default_true() -> bool {
+	true
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MaterialSpec {
+	#[serde(default = "default_albedo")]
+	albedo: [f32; 3],
+	#[serde(default)]
+	specular: f32,
+	#[serde(default, alias = "metallic")]
+	metalic: f32,
+	#[serde(default)]
+	roughness: f32,
+	/// Radiance of an emissive material, which turns objects using it into
+	/// area lights.
+	emission: Option<[f32; 3]>,
+}
+
+fn default_albedo() -> [f32; 3] {
+	[0.8, 0.8, 0.8]
+}
+
+fn default_colour() -> [f32; 3] {
+	[1., 1., 1.]
+}
+
+/// Cone angles are half-angles in degrees.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case", deny_unknown_fields)]
+enum LightSpec {
+	Point {
+		position: [f32; 3],
+		intensity: f32,
+		#[serde(default = "default_colour")]
+		colour: [f32; 3],
+		profile: Option<PathBuf>,
+	},
+	Spot {
+		position: [f32; 3],
+		direction: [f32; 3],
+		inner: f32,
+		outer: f32,
+		intensity: f32,
+		#[serde(default = "default_colour")]
+		colour: [f32; 3],
+		profile: Option<PathBuf>,
+	},
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum Primitive {
+	Plane,
+}
+
+/// One factor for every axis, or one for each.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(untagged)]
+enum ScaleSpec {
+	Uniform(f32),
+	Axes([f32; 3]),
+}
+
+impl From<ScaleSpec> for Vec3f {
+	fn from(scale: ScaleSpec) -> Self {
+		match scale {
+			ScaleSpec::Uniform(n) => Vec3f::new(n, n, n),
+			ScaleSpec::Axes(axes) => axes.into(),
+		}
+	}
+}
+
+/// Where an object ends up when the shutter closes, relative to where it
+/// is when it opens.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(untagged)]
+enum MotionSpec {
+	Offset([f32; 3]),
+	Transform(MotionTransformSpec),
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+struct MotionTransformSpec {
+	offset: Option<[f32; 3]>,
+	/// Axis through the object's centre it turns about.
+	axis: Option<[f32; 3]>,
+	/// Degrees turned about `axis`.
+	angle: Option<f32>,
+	scale: Option<ScaleSpec>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ObjectSpec {
+	gltf: Option<PathBuf>,
+	/// Wavefront OBJ model, one object per group with its MTL materials.
+	obj: Option<PathBuf>,
+	/// PLY mesh, keeping its vertex normals, texture coordinates and
+	/// colours.
+	ply: Option<PathBuf>,
+	/// STL mesh, as CAD tools export.
+	stl: Option<PathBuf>,
+	primitive: Option<Primitive>,
+	material: Option<String>,
+	scale: Option<ScaleSpec>,
+	/// Euler angles in degrees, about x first, then y, then z.
+	rotation: Option<[f32; 3]>,
+	/// Rotation as a quaternion `[x, y, z, w]`, as glTF stores them.
+	quaternion: Option<[f32; 4]>,
+	/// Scales uniformly to fit within this width and height.
+	size: Option<[f32; 2]>,
+	position: Option<[f32; 3]>,
+	/// Offset the object travels over the shutter interval, or a table of
+	/// its `offset`, the `angle` it turns about an `axis` and its `scale`.
+	motion: Option<MotionSpec>,
+}
+
+/// One-based line and column of byte `offset` in `source`.
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+	let before = &source[..offset.min(source.len())];
+	let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+	(
+		before.matches('\n').count() + 1,
+		before[line_start..].chars().count() + 1,
+	)
+}
+
+/// File of the model an object places, if it is not a primitive.
+fn model_path(spec: &ObjectSpec) -> Option<&PathBuf> {
+	[&spec.gltf, &spec.obj, &spec.ply, &spec.stl]
+		.into_iter()
+		.find_map(|model| model.as_ref())
+}
+
+fn load_model(
+	spec: &ObjectSpec,
+	path: &Path,
+	warnings: &mut Vec<String>,
+) -> Result<Vec<SolidObject>, LoadError> {
+	if spec.gltf.is_some() {
+		Ok(vec![load_gltf(path)?])
+	} else if spec.obj.is_some() {
+		load_obj(path, warnings)
+	} else if spec.ply.is_some() {
+		Ok(vec![load_ply(path)?])
+	} else {
+		Ok(vec![load_stl(path)?])
+	}
+}
+
+/// Meshes of the parts of a model, for instances to share.
+fn share(objects: Vec<SolidObject>) -> Vec<(Arc<Mesh>, Material)> {
+	objects
+		.into_iter()
+		.map(|object| (Arc::new(Mesh::new(object.faces)), object.material))
+		.collect()
+}
+
+/// Places the parts of a model together: rotated and scaled about the
+/// origin, fitted within `size` and centred on `position`.
+fn place(parts: &mut [Instance], spec: &ObjectSpec) -> Result<(), &'static str> {
+	let bounds = |parts: &[Instance]| {
+		parts
+			.iter()
+			.map(|part| part.bounding().clone())
+			.reduce(|a, b| a.union(&b))
+	};
+	let transform_all = |parts: &mut [Instance], transform: &Transform| {
+		for part in parts.iter_mut() {
+			part.transform(transform);
+		}
+	};
+
+	let rotation = match (spec.rotation, spec.quaternion) {
+		(Some(degrees), None) => Transform::rotate_euler(degrees.into()),
+		(None, Some(quaternion)) => Transform::rotate_quaternion(quaternion),
+		(None, None) => Transform::IDENTITY,
+		(Some(_), Some(_)) => return Err("An object takes one of `rotation` or `quaternion`"),
+	};
+	let scale = spec.scale.map_or(Vec3f::new(1., 1., 1.), Vec3f::from);
+	transform_all(parts, &rotation.mul(&Transform::scale(scale)));
+
+	if let (Some([x, y]), Some(bounds)) = (spec.size, bounds(parts)) {
+		let size = bounds.size();
+		let n = f32::min(x / size.x, y / size.y);
+		for part in parts.iter_mut() {
+			part.scale(n);
+		}
+	}
+	if let (Some(position), Some(bounds)) = (spec.position, bounds(parts)) {
+		let offset = Vec3f::from(position) - bounds.center();
+		transform_all(parts, &Transform::translate(offset));
+	}
+	if parts.iter().any(Instance::is_singular) {
+		return Err("An object cannot be scaled to nothing along an axis");
+	}
+	if let (Some(motion), Some(bounds)) = (spec.motion, bounds(parts)) {
+		let motion = match motion {
+			MotionSpec::Offset(offset) => Motion::offset(offset.into()),
+			MotionSpec::Transform(MotionTransformSpec {
+				offset,
+				axis,
+				angle,
+				scale,
+			}) => Motion {
+				offset: offset.map_or(Vec3f::new(0., 0., 0.), Vec3f::from),
+				axis: axis.map_or(Vec3f::new(0., 1., 0.), Vec3f::from),
+				angle: angle.unwrap_or(0.),
+				scale: scale.map_or(Vec3f::new(1., 1., 1.), Vec3f::from),
+				pivot: bounds.center(),
+			},
+		};
+		if motion.axis.len_sq() == 0. {
+			return Err("A motion needs a non-zero `axis` to turn about");
+		}
+		for part in parts.iter_mut() {
+			part.set_motion(motion);
+		}
+	}
+
+	Ok(())
+}
+
+/// Loads a TOML scene file. Paths inside it are relative to its directory.
+pub fn load_scene_file<P: AsRef<Path>>(path: P) -> io::Result<SceneDescription> {
+	let path = path.as_ref();
+	let source = fs::read_to_string(path)?;
+
+	let error_at = |span: Range<usize>, message: &dyn Display| {
+		let (line, column) = line_column(&source, span.start);
+		io::Error::new(
+			ErrorKind::InvalidData,
+			format!("{}:{}:{}: {}", path.display(), line, column, message),
+		)
+	};
+
+	let file: SceneFile = toml::from_str(&source).map_err(|e| match e.span() {
+		Some(span) => error_at(span, &e.message()),
+		None => io::Error::new(
+			ErrorKind::InvalidData,
+			format!("{}: {}", path.display(), e.message()),
+		),
+	})?;
+	let directory = path.parent().unwrap_or(Path::new(""));
+
+	let mut settings = RenderSettings::new();
+	let spec = &file.settings;
+	if let Some(Count(width)) = spec.width {
+		settings.width = width;
+	}
+	if let Some(Count(height)) = spec.height {
+		settings.height = height;
+	}
+	if let Some(Count(spp)) = spec.spp {
+		settings.spp = spp;
+	}
+	if let Some(Count(max_depth)) = spec.max_depth {
+		settings.max_depth = max_depth;
+	}
+	if let Some(Parsed(sampler)) = spec.sampler {
+		settings.sampler = sampler;
+	}
+	if let Some(seed) = spec.seed {
+		settings.seed = seed;
+	}
+
+	let camera = {
+		let spec = &file.camera;
+		let mut camera = CameraDescription::new();
+		if let Some(Parsed(projection)) = spec.projection {
+			camera.projection = projection;
+		}
+		if let Some(position) = &spec.position {
+			camera.position = (*position.get_ref()).into();
+		}
+		if let Some(target) = &spec.target {
+			camera.target = (*target.get_ref()).into();
+		}
+		if let Some(spot) = spec.target.as_ref().or(spec.position.as_ref()) {
+			if camera.position == camera.target {
+				return Err(error_at(
+					spot.span(),
+					&"The camera cannot look at its own position",
+				));
+			}
+		}
+		if let Some(up) = spec.up {
+			camera.up = up.into();
+		}
+
+		let fov = match (spec.fov, camera.projection) {
+			(Some(fov), _) => fov,
+			(None, Projection::Fisheye) => 180.,
+			(None, _) => 90.,
+		}
+		.to_radians();
+		camera.fov = match spec.fov_axis {
+			Some(FovAxis::Vertical) => Fov::Vertical(fov),
+			_ => Fov::Horizontal(fov),
+		};
+
+		if spec.aperture_radius > 0. {
+			camera.lens = Some(ThinLens {
+				radius: spec.aperture_radius,
+				focus_distance: spec
+					.focus_distance
+					.unwrap_or_else(|| (camera.target - camera.position).len()),
+				aperture: match spec.aperture_blades {
+					0 => Aperture::Disc,
+					blades => Aperture::Polygon(blades),
+				},
+			});
+		}
+		if let Some(shutter) = &spec.shutter {
+			let [open, close] = *shutter.get_ref();
+			if open > close {
+				return Err(error_at(
+					shutter.span(),
+					&"The shutter cannot close before it opens",
+				));
+			}
+			camera.shutter = Shutter { open, close };
+		}
+
+		// The physical sky is far brighter than the other environments, so
+		// it gets a camera exposed for daylight unless told otherwise.
+		if let Some(EnvironmentSpec::Sky { .. }) = file.environment.as_ref().map(|e| e.get_ref()) {
+			camera.exposure = Exposure::daylight();
+		}
+
+		if let Some(exposure) = &spec.exposure {
+			let e = exposure.get_ref();
+			if [e.iso, e.shutter_time, e.f_number]
+				.into_iter()
+				.flatten()
+				.any(|v| v <= 0.)
+			{
+				return Err(error_at(
+					exposure.span(),
+					&"`iso`, `shutter_time` and `f_number` must be positive",
+				));
+			}
+			camera.exposure = match (e.ev100, e.iso, e.shutter_time, e.f_number) {
+				(Some(ev100), _, _, _) => Exposure::from_ev100(ev100),
+				(None, Some(iso), Some(shutter), Some(f_number)) => {
+					Exposure::from_camera(iso, shutter, f_number)
+				}
+				(None, None, None, None) => camera.exposure,
+				_ => return Err(error_at(
+					exposure.span(),
+					&"Exposure needs either `ev100` or all of `iso`, `shutter_time` and `f_number`",
+				)),
+			}
+			.compensate(e.compensation);
+		}
+
+		if let Some(stereo) = &spec.stereo {
+			let (span, stereo) = (stereo.span(), stereo.get_ref());
+			if stereo.convergence <= 0. {
+				return Err(error_at(
+					span,
+					&"The stereo convergence distance must be positive",
+				));
+			}
+			camera.stereo = Some((
+				StereoRig {
+					interocular: stereo.interocular,
+					convergence: stereo.convergence,
+				},
+				stereo.layout.0,
+			));
+		}
+
+		camera
+	};
+
+	let mut scene = Scene::new();
+	if let Some(Parsed(light_sampling)) = file.settings.light_sampling {
+		scene.set_light_sampling(light_sampling);
+	}
+
+	if let Some(environment) = &file.environment {
+		match environment.get_ref() {
+			EnvironmentSpec::Gradient => scene.set_environment(Box::new(Gradient {})),
+			EnvironmentSpec::Map { path } => {
+				let map =
+					EnvironmentMap::open(directory.join(path).to_string_lossy()).map_err(|e| {
+						error_at(
+							environment.span(),
+							&format!("Cannot load '{}': {}", path.display(), e),
+						)
+					})?;
+				scene.set_environment(Box::new(map));
+			}
+			EnvironmentSpec::Sky {
+				elevation,
+				azimuth,
+				turbidity,
+				sun,
+			} => {
+				let sky = Sky::new(elevation.to_radians(), azimuth.to_radians(), *turbidity);
+				if *sun {
+					scene.add_light(Box::new(sky.sun()));
+				}
+				scene.set_environment(Box::new(sky));
+			}
+		}
+	}
+
+	let materials: BTreeMap<&str, Material> = file
+		.materials
+		.iter()
+		.map(|(name, spec)| {
+			let material = Material {
+				albedo: spec.emission.unwrap_or(spec.albedo).into(),
+				specular: spec.specular,
+				metalic: spec.metalic,
+				roughness: spec.roughness,
+				emission: if spec.emission.is_some() { 1. } else { 0. },
+				texture: None,
+			};
+			(name.as_str(), material)
+		})
+		.collect();
+
+	for light in &file.lights {
+		let open_profile = |profile: &Option<PathBuf>| {
+			profile
+				.as_ref()
+				.map(|profile| {
+					IesProfile::open(directory.join(profile).to_string_lossy()).map_err(|e| {
+						error_at(
+							light.span(),
+							&format!("Cannot load '{}': {}", profile.display(), e),
+						)
+					})
+				})
+				.transpose()
+		};
+
+		match light.get_ref() {
+			LightSpec::Point {
+				position,
+				intensity,
+				colour,
+				profile,
+			} => {
+				let mut point =
+					PointLight::new((*position).into(), Colour::from(*colour) * *intensity);
+				point.profile = open_profile(profile)?;
+				scene.add_light(Box::new(point));
+			}
+			LightSpec::Spot {
+				position,
+				direction,
+				inner,
+				outer,
+				intensity,
+				colour,
+				profile,
+			} => {
+				let mut spot = SpotLight::new(
+					(*position).into(),
+					(*direction).into(),
+					inner.to_radians(),
+					outer.to_radians(),
+					Colour::from(*colour) * *intensity,
+				);
+				spot.profile = open_profile(profile)?;
+				scene.add_light(Box::new(spot));
+			}
+		}
+	}
+
+	let mut warnings = Vec::new();
+	// Models placed more than once share one copy of their meshes.
+	let mut models: HashMap<PathBuf, Vec<(Arc<Mesh>, Material)>> = HashMap::new();
+	for object in &file.objects {
+		let spec = object.get_ref();
+
+		let load_error = |model: &PathBuf, e: LoadError| {
+			error_at(
+				object.span(),
+				&format!("Cannot load '{}': {}", model.display(), e),
+			)
+		};
+		let sources = [
+			spec.gltf.is_some(),
+			spec.obj.is_some(),
+			spec.ply.is_some(),
+			spec.stl.is_some(),
+			spec.primitive.is_some(),
+		];
+		if sources.iter().filter(|source| **source).count() != 1 {
+			return Err(error_at(
+				object.span(),
+				&"An object needs exactly one of `gltf`, `obj`, `ply`, `stl` or `primitive`",
+			));
+		}
+		let meshes = match model_path(spec) {
+			Some(model) => match models.entry(directory.join(model)) {
+				Entry::Occupied(entry) => entry.get().clone(),
+				Entry::Vacant(entry) => {
+					let objects = load_model(spec, entry.key(), &mut warnings)
+						.map_err(|e| load_error(model, e))?;
+					entry.insert(share(objects)).clone()
+				}
+			},
+			None => match spec.primitive {
+				Some(Primitive::Plane) | None => share(vec![SolidObject::plane()]),
+			},
+		};
+		let mut parts: Vec<Instance> = meshes
+			.into_iter()
+			.map(|(mesh, material)| Instance::new(mesh, material))
+			.collect();
+
+		if let Some(name) = &spec.material {
+			let material = materials
+				.get(name.as_str())
+				.ok_or_else(|| error_at(object.span(), &format!("Unknown material '{}'", name)))?;
+			for part in &mut parts {
+				part.material = material.clone();
+			}
+		}
+		place(&mut parts, spec).map_err(|message| error_at(object.span(), &message))?;
+		if spec.motion.is_some() && parts.iter().any(|part| part.material.emission == 1.) {
+			let (line, column) = line_column(&source, object.span().start);
+			warnings.push(format!(
+				"{}:{}:{}: Emissive objects cannot move, ignoring `motion`",
+				path.display(),
+				line,
+				column
+			));
+		}
+
+		for part in parts {
+			scene.add_object(Box::new(part));
+		}
+	}
+
+	Ok(SceneDescription {
+		scene,
+		camera,
+		settings,
+		warnings,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		geometry::Ray,
+		loader::tests::{hit_point, scratch},
+	};
+
+	fn load(name: &str, source: &str) -> io::Result<SceneDescription> {
+		let directory = scratch(name, &[("scene.toml", source.as_bytes())]);
+		load_scene_file(directory.join("scene.toml"))
+	}
+
+	fn error(name: &str, source: &str) -> String {
+		load(name, source).err().unwrap().to_string()
+	}
+
+	#[test]
+	fn reads_settings_and_camera() {
+		let description = load(
+			"toml-camera",
+			r#"
+[settings]
+width = 32
+height = 16
+spp = 4
+sampler = "halton"
+seed = 5
+
+[camera]
+position = [1, 2, 3]
+target = [1, 2, 10]
+fov = 60
+fov_axis = "vertical"
+aperture_radius = 0.1
+aperture_blades = 6
+shutter = [0, 0.5]
+exposure = { ev100 = 3, compensation = 1 }
+stereo = { interocular = 0.1, convergence = 5, layout = "top-bottom" }
+"#,
+		)
+		.unwrap();
+
+		let settings = description.settings;
+		assert_eq!((settings.width, settings.height, settings.spp), (32, 16, 4));
+		assert_eq!((settings.sampler, settings.seed), (SamplerKind::Halton, 5));
+
+		let camera = description.camera;
+		assert_eq!(camera.position, Vec3f::new(1., 2., 3.));
+		assert_eq!(camera.target, Vec3f::new(1., 2., 10.));
+		assert!(
+			matches!(camera.fov, Fov::Vertical(fov) if (fov - 60f32.to_radians()).abs() < 1e-6)
+		);
+		let lens = camera.lens.unwrap();
+		assert_eq!((lens.radius, lens.focus_distance), (0.1, 7.));
+		assert_eq!(lens.aperture, Aperture::Polygon(6));
+		assert_eq!((camera.shutter.open, camera.shutter.close), (0., 0.5));
+		let exposed = camera.exposure.apply(Colour::from_rgb(1., 1., 1.)).r;
+		assert!((exposed - 2. / 9.6).abs() < 1e-6);
+		assert_eq!(camera.frame_size(32, 16), (32, 32));
+	}
+
+	#[test]
+	fn places_objects_in_their_materials() {
+		let description = load(
+			"toml-objects",
+			r#"
+[materials.ground]
+albedo = [0.5, 0.25, 0.125]
+
+[materials.lamp]
+emission = [4, 4, 4]
+
+[[objects]]
+primitive = "plane"
+material = "ground"
+size = [10, 10]
+position = [0, -1, 5]
+
+[[objects]]
+primitive = "plane"
+material = "lamp"
+rotation = [-90, 0, 0]
+position = [0, 0, 20]
+"#,
+		)
+		.unwrap();
+		let scene = &description.scene;
+
+		assert_eq!(
+			hit_point(scene, [0., 0., 5.], [0., -1., 0.]),
+			Some(Vec3f::new(0., -1., 5.))
+		);
+		let edge = hit_point(scene, [4.9, 0., 1.], [0., -1., 0.]).unwrap();
+		assert!((edge - Vec3f::new(4.9, -1., 1.)).len() < 1e-5);
+		assert_eq!(hit_point(scene, [5.1, 0., 5.], [0., -1., 0.]), None);
+
+		let ground = scene.hit(&Ray::new(Vec3f::new(0., 0., 5.), Vec3f::new(0., -1., 0.)));
+		let ground = ground.unwrap();
+		assert_eq!(ground.object.material().unwrap().albedo.g, 0.25);
+		assert_eq!(ground.light, None);
+
+		// Turned upright, the lamp faces the camera and lights the scene.
+		let lamp = scene.hit(&Ray::new(Vec3f::new(0., 0., 0.), Vec3f::new(0., 0., 1.)));
+		let lamp = lamp.unwrap();
+		assert!((lamp.point - Vec3f::new(0., 0., 20.)).len() < 1e-5);
+		assert!(lamp.light.is_some());
+		assert_eq!(lamp.object.material().unwrap().albedo.r, 4.);
+	}
+
+	#[test]
+	fn sky_is_exposed_for_daylight() {
+		let description = load(
+			"toml-sky",
+			r#"
+[environment]
+type = "sky"
+elevation = 30
+azimuth = 0
+"#,
+		)
+		.unwrap();
+		let exposed = description
+			.camera
+			.exposure
+			.apply(Colour::from_rgb(1., 1., 1.))
+			.r;
+		assert_eq!(
+			exposed,
+			Exposure::daylight().apply(Colour::from_rgb(1., 1., 1.)).r
+		);
+	}
+
+	#[test]
+	fn warns_about_moving_lights() {
+		let description = load(
+			"toml-motion",
+			r#"
+[materials.lamp]
+emission = [1, 1, 1]
+
+[[objects]]
+primitive = "plane"
+material = "lamp"
+motion = { offset = [1, 0, 0], axis = [0, 1, 0], angle = 90 }
+"#,
+		)
+		.unwrap();
+		assert_eq!(description.warnings.len(), 1);
+		assert!(
+			description.warnings[0]
+				.ends_with(":5:1: Emissive objects cannot move, ignoring `motion`"),
+			"{}",
+			description.warnings[0]
+		);
+	}
+
+	#[test]
+	fn reports_where_errors_are() {
+		let message = error("toml-unknown", "[settings]\nwidth = 4\ncolour = 1\n");
+		assert!(message.ends_with(":3:1: unknown field `colour`, expected one of `width`, `height`, `spp`, `max_depth`, `sampler`, `seed`, `light_sampling`"), "{}", message);
+
+		let message = error("toml-count", "[settings]\nspp = 0\n");
+		assert!(
+			message.ends_with(":2:7: expected at least 1"),
+			"{}",
+			message
+		);
+
+		let message = error("toml-sampler", "[settings]\nsampler = \"random\"\n");
+		assert!(
+			message.ends_with(":2:11: Unknown sampler 'random'"),
+			"{}",
+			message
+		);
+
+		let message = error(
+			"toml-material",
+			"[[objects]]\nprimitive = \"plane\"\nmaterial = \"gold\"\n",
+		);
+		assert!(
+			message.ends_with(":1:1: Unknown material 'gold'"),
+			"{}",
+			message
+		);
+
+		let message = error(
+			"toml-sources",
+			"[[objects]]\nprimitive = \"plane\"\nobj = \"a.obj\"\n",
+		);
+		assert!(message.contains("exactly one of"), "{}", message);
+
+		let message = error("toml-exposure", "[camera]\nexposure = { iso = 100 }\n");
+		assert!(message.ends_with(":2:12: Exposure needs either `ev100` or all of `iso`, `shutter_time` and `f_number`"), "{}", message);
+
+		for (name, camera, expected) in [
+			(
+				"toml-look-at",
+				"position = [1, 2, 3]\ntarget = [1, 2, 3]\n",
+				":3:10: The camera cannot look at its own position",
+			),
+			(
+				"toml-shutter",
+				"shutter = [0.5, 0.2]\n",
+				":2:11: The shutter cannot close before it opens",
+			),
+			(
+				"toml-iso",
+				"exposure = { iso = 0, shutter_time = 0.01, f_number = 2 }\n",
+				":2:12: `iso`, `shutter_time` and `f_number` must be positive",
+			),
+			(
+				"toml-shutter-time",
+				"exposure = { iso = 100, shutter_time = 0, f_number = 2 }\n",
+				":2:12: `iso`, `shutter_time` and `f_number` must be positive",
+			),
+			(
+				"toml-f-number",
+				"exposure = { iso = 100, shutter_time = 0.01, f_number = 0 }\n",
+				":2:12: `iso`, `shutter_time` and `f_number` must be positive",
+			),
+			(
+				"toml-convergence",
+				"stereo = { interocular = 0.1, convergence = 0, layout = \"top-bottom\" }\n",
+				":2:10: The stereo convergence distance must be positive",
+			),
+		] {
+			let message = error(name, &format!("[camera]\n{}", camera));
+			assert!(message.ends_with(expected), "{}", message);
+		}
+
+		let message = error(
+			"toml-flat",
+			"[[objects]]\nprimitive = \"plane\"\nscale = [1, 0, 1]\n",
+		);
+		assert!(
+			message.ends_with(":1:1: An object cannot be scaled to nothing along an axis"),
+			"{}",
+			message
+		);
+
+		let message = error("toml-model", "[[objects]]\nobj = \"missing.obj\"\n");
+		assert!(
+			message.contains(":1:1: Cannot load 'missing.obj'"),
+			"{}",
+			message
+		);
+	}
+}
@@ -0,0 +1,193 @@
+use std::{fs, path::Path};
+
+use crate::geometry::{Polygon3, SolidObject, Vec3f};
+
+use super::{mesh_material, LoadError};
+
+fn invalid<T>(message: &str) -> Result<T, LoadError> {
+	Err(LoadError::Parse(message.to_string()))
+}
+
+/// Reads an STL mesh, ASCII or binary, as an object in a plain grey
+/// material. Facet normals are left out, the faces being shaded flat either
+/// way.
+pub fn load_stl<P: AsRef<Path>>(path: P) -> Result<SolidObject, LoadError> {
+	let data = fs::read(path)?;
+
+	let faces = if is_binary(&data) {
+		decode_binary(&data)?
+	} else {
+		decode_ascii(&data)?
+	};
+
+	Ok(SolidObject::new(faces, mesh_material()))
+}
+
+/// Binary files may also start with `solid`, so they are told apart by their
+/// size matching the triangle count in the header.
+fn is_binary(data: &[u8]) -> bool {
+	match data.get(80..84) {
+		Some(count) => {
+			let count = u32::from_le_bytes(count.try_into().unwrap()) as usize;
+			!data.starts_with(b"solid") || data.len() == 84 + count * 50
+		}
+		None => false,
+	}
+}
+
+fn decode_binary(data: &[u8]) -> Result<Vec<Polygon3>, LoadError> {
+	let count = u32::from_le_bytes(data[80..84].try_into().unwrap()) as usize;
+	let triangles = data
+		.get(84..84 + count * 50)
+		.ok_or_else(|| LoadError::Parse("Truncated STL data".to_string()))?;
+
+	let vertex = |bytes: &[u8]| {
+		let [x, y, z] = [0, 4, 8].map(|i| f32::from_le_bytes(bytes[i..i + 4].try_into().unwrap()));
+		Vec3f::new(x, y, z)
+	};
+
+	// Each triangle is a normal, three corners and two bytes of attributes.
+	Ok(triangles
+		.chunks_exact(50)
+		.map(|t| Polygon3::new(vertex(&t[12..]), vertex(&t[24..]), vertex(&t[36..])))
+		.collect())
+}
+
+fn decode_ascii(data: &[u8]) -> Result<Vec<Polygon3>, LoadError> {
+	let text = std::str::from_utf8(data).or_else(|_| invalid("ASCII STL is not valid text"))?;
+
+	let mut faces = Vec::new();
+	let mut corners: Vec<Vec3f> = Vec::new();
+	let mut words = text.split_ascii_whitespace();
+	while let Some(word) = words.next() {
+		match word {
+			"vertex" => {
+				let mut coordinate = || -> Result<f32, LoadError> {
+					let token = words
+						.next()
+						.ok_or_else(|| LoadError::Parse("Truncated STL data".to_string()))?;
+					token
+						.parse()
+						.or_else(|_| invalid(&format!("Invalid number '{}'", token)))
+				};
+				corners.push(Vec3f::new(coordinate()?, coordinate()?, coordinate()?));
+			}
+			"endloop" => {
+				if corners.len() < 3 {
+					return invalid("A facet needs three vertices");
+				}
+				for i in 2..corners.len() {
+					faces.push(Polygon3::new(corners[0], corners[i - 1], corners[i]));
+				}
+				corners.clear();
+			}
+			_ => {}
+		}
+	}
+
+	Ok(faces)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::loader::tests::scratch;
+
+	const ASCII: &str = "\
+solid square
+facet normal 0 0 1
+  outer loop
+    vertex 0 0 0
+    vertex 1 0 0
+    vertex 1 1 0
+    vertex 0 1 0
+  endloop
+endfacet
+endsolid square
+";
+
+	/// A binary file of one triangle, its header starting like an ASCII one.
+	fn binary() -> Vec<u8> {
+		let mut data = b"solid but binary".to_vec();
+		data.resize(80, 0);
+		data.extend(1u32.to_le_bytes());
+		for v in [0., 0., 1., 0., 0., 0., 2., 0., 0., 0., 2., 0.] {
+			data.extend((v as f32).to_le_bytes());
+		}
+		data.extend([0, 0]);
+		data
+	}
+
+	#[test]
+	fn tells_binary_from_ascii() {
+		assert!(is_binary(&binary()));
+		assert!(!is_binary(ASCII.as_bytes()));
+		assert!(!is_binary(b"solid"));
+
+		// A header not starting with `solid` is binary whatever its size.
+		let mut unnamed = binary();
+		unnamed[..5].copy_from_slice(b"model");
+		unnamed.truncate(90);
+		assert!(is_binary(&unnamed));
+	}
+
+	#[test]
+	fn reads_binary_triangles() {
+		let faces = decode_binary(&binary()).unwrap();
+		assert_eq!(faces.len(), 1);
+		assert_eq!(
+			[faces[0].a, faces[0].b, faces[0].c],
+			[
+				Vec3f::new(0., 0., 0.),
+				Vec3f::new(2., 0., 0.),
+				Vec3f::new(0., 2., 0.)
+			]
+		);
+
+		let mut truncated = binary();
+		truncated.pop();
+		assert_eq!(
+			decode_binary(&truncated).err().unwrap().to_string(),
+			"Truncated STL data"
+		);
+	}
+
+	#[test]
+	fn reads_ascii_facets() {
+		let faces = decode_ascii(ASCII.as_bytes()).unwrap();
+		assert_eq!(faces.len(), 2);
+		assert_eq!(
+			[faces[1].a, faces[1].b, faces[1].c],
+			[
+				Vec3f::new(0., 0., 0.),
+				Vec3f::new(1., 1., 0.),
+				Vec3f::new(0., 1., 0.)
+			]
+		);
+		assert_eq!(faces[1].normal, Vec3f::new(0., 0., 1.));
+
+		let message = |text: &str| decode_ascii(text.as_bytes()).err().unwrap().to_string();
+		assert_eq!(message("vertex 0 0"), "Truncated STL data");
+		assert_eq!(message("vertex 0 x 0"), "Invalid number 'x'");
+		assert_eq!(
+			message("vertex 0 0 0 vertex 1 0 0 endloop"),
+			"A facet needs three vertices"
+		);
+	}
+
+	#[test]
+	fn loads_either_kind_of_file() {
+		let directory = scratch(
+			"stl",
+			&[("ascii.stl", ASCII.as_bytes()), ("binary.stl", &binary())],
+		);
+		assert_eq!(
+			load_stl(directory.join("ascii.stl")).unwrap().faces.len(),
+			2
+		);
+		assert_eq!(
+			load_stl(directory.join("binary.stl")).unwrap().faces.len(),
+			1
+		);
+	}
+}
@@ -1,151 +1,241 @@
+#[cfg(feature = "preview")]
 use pixels::{Pixels, SurfaceTexture};
-use rayon::prelude::*;
+#[cfg(feature = "preview")]
 use winit::{
 	dpi::LogicalSize,
-	event::Event,
+	event::{Event, WindowEvent},
 	event_loop::{ControlFlow, EventLoop},
 	window::WindowBuilder,
 };
 
+mod camera;
+mod cli;
 mod geometry;
 mod image;
+mod light;
+mod loader;
 mod material;
 // mod progress;
+mod render;
+mod sampler;
+#[cfg(test)]
+mod testing;
 
 use std::{
-	f32::consts::PI,
 	fs::File,
 	io::Write,
-	sync::{
-		atomic::{AtomicBool, Ordering},
-		Arc, RwLock,
-	},
-	thread,
+	path::{Path, PathBuf},
+	process,
+	sync::{atomic::AtomicBool, Arc, RwLock},
 };
+#[cfg(feature = "preview")]
+use std::{sync::atomic::Ordering, thread};
 
-use geometry::{Light, Ray, Scene, SolidObject, Vec3f, WithOrigin, WithScale};
-use image::{Colour, Image, ImageFormat};
-use rand::{prelude::SliceRandom, Rng};
+use camera::StereoLayout;
+use image::{Image, ImageFormat};
+use loader::SceneDescription;
+use render::Renderer;
+use sampler::AdaptiveSampling;
 
-const HEIGHT: u32 = 320;
-const WIDTH: u32 = 640;
-const SAMPLES_PER_PIXEL: i32 = 50;
-const MAX_DEPTH: usize = 30;
+fn main() {
+	let cli = cli::parse();
+	let options = cli.command.options();
+
+	if let Some(threads) = options.threads {
+		rayon::ThreadPoolBuilder::new()
+			.num_threads(threads as usize)
+			.build_global()
+			.unwrap_or_else(|e| {
+				eprintln!("error: cannot start the worker threads: {}", e);
+				process::exit(1);
+			});
+	}
 
-const MODEL: &str = "Avocado.glb";
+	println!("Loading scene");
+
+	let SceneDescription {
+		scene,
+		camera,
+		mut settings,
+		warnings,
+	} = loader::load(&options.scene).unwrap_or_else(|e| {
+		eprintln!("error: {}", e);
+		process::exit(1);
+	});
+	for warning in warnings {
+		eprintln!("warning: {}", warning);
+	}
+	options.apply(&mut settings);
+
+	let (width, height) = (settings.width, settings.height);
+	let (frame_width, frame_height) = camera.frame_size(width, height);
+	let stereo = camera.stereo;
+
+	let renderer = Renderer {
+		scene,
+		views: camera.views(width, height),
+		width,
+		height,
+		max_depth: settings.max_depth as usize,
+		shutter: camera.shutter,
+		exposure: camera.exposure,
+		sampler: settings.sampler,
+		seed: settings.seed,
+		adaptive: match options.adaptive {
+			Some(threshold) => AdaptiveSampling {
+				threshold,
+				min_samples: options.min_spp.min(settings.spp),
+				max_samples: settings.spp,
+			},
+			None => AdaptiveSampling {
+				threshold: 0.,
+				min_samples: settings.spp,
+				max_samples: settings.spp,
+			},
+		},
+		progressive: options.progressive,
+	};
+
+	println!("Allocating image");
+
+	let image = Arc::new(RwLock::new(Image::new(frame_width, frame_height)));
+	let sample_map = Arc::new(RwLock::new(Image::new(frame_width, frame_height)));
+	let output = options.output.clone();
+	let sample_map_output = options.sample_map.clone();
+	let outputs = move |image: &Image, sample_map: &Image| {
+		let mut outputs = match stereo {
+			Some((_, StereoLayout::Separate)) => vec![
+				(
+					with_suffix(&output, "_left"),
+					image.crop(0, 0, width, height),
+				),
+				(
+					with_suffix(&output, "_right"),
+					image.crop(width, 0, width, height),
+				),
+			],
+			_ => vec![(output.clone(), image.crop(0, 0, frame_width, frame_height))],
+		};
+		if let Some(path) = &sample_map_output {
+			outputs.push((
+				path.clone(),
+				sample_map.crop(0, 0, frame_width, frame_height),
+			));
+		}
+		outputs
+	};
+
+	match cli.command {
+		cli::Command::Render(_) => {
+			println!("Processing pixels");
+			renderer.render(&image, &sample_map, &AtomicBool::new(false), || {});
+
+			println!("Encoding image");
+			write_images(outputs(&image.read().unwrap(), &sample_map.read().unwrap()));
+		}
+		#[cfg(feature = "preview")]
+		cli::Command::Preview(_) => preview(renderer, image, sample_map, outputs),
+		// Refused by `cli::parse` in builds without the window.
+		#[cfg(not(feature = "preview"))]
+		cli::Command::Preview(_) => unreachable!(),
+	}
+}
+
+/// `path` with `suffix` added to the file name before the extension.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+	let mut name = path.file_stem().unwrap_or_default().to_os_string();
+	name.push(suffix);
+	if let Some(extension) = path.extension() {
+		name.push(".");
+		name.push(extension);
+	}
+	path.with_file_name(name)
+}
+
+/// Writes every image, exiting with an error if one cannot be written.
+fn write_images(outputs: Vec<(PathBuf, Image)>) {
+	for (name, image) in outputs {
+		let written = image::formats::Bmp::encode(&image)
+			.and_then(|data| File::create(&name)?.write_all(&data));
+		if let Err(e) = written {
+			eprintln!("error: cannot write '{}': {}", name.display(), e);
+			process::exit(1);
+		}
+	}
+}
+
+/// Shows the render in a window as it progresses, writing the images once it
+/// finishes or the window is closed.
+#[cfg(feature = "preview")]
+fn preview(
+	renderer: Renderer,
+	image: Arc<RwLock<Image>>,
+	sample_map: Arc<RwLock<Image>>,
+	outputs: impl Fn(&Image, &Image) -> Vec<(PathBuf, Image)> + Send + 'static,
+) -> ! {
+	let (frame_width, frame_height) = {
+		let image = image.read().unwrap();
+		(image.width(), image.height())
+	};
 
-fn main() {
 	let event_loop = EventLoop::new();
 	let window = {
-		let size = LogicalSize::new(WIDTH as f64, HEIGHT as f64);
+		let size = LogicalSize::new(frame_width as f64, frame_height as f64);
 		WindowBuilder::new()
 			.with_title("Hello Pixels")
 			.with_inner_size(size)
 			.with_min_inner_size(size)
 			.build(&event_loop)
-			.unwrap()
+			.unwrap_or_else(|e| {
+				eprintln!("error: cannot open the preview window: {}", e);
+				process::exit(1);
+			})
 	};
 
 	let mut pixels = {
 		let window_size = window.inner_size();
 		let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, &window);
-		Pixels::new(WIDTH, HEIGHT, surface_texture).unwrap()
+		Pixels::new(frame_width, frame_height, surface_texture).unwrap_or_else(|e| {
+			eprintln!("error: cannot draw to the preview window: {}", e);
+			process::exit(1);
+		})
 	};
 
-	let aspect_ratio = (HEIGHT as f32 - 1.) / (WIDTH as f32 - 1.);
-
-	println!("Loading model");
-
-	let mut model1 = SolidObject::from_gltf(MODEL);
-	model1.scale(100.);
-	model1.move_to(Vec3f::new(0., 0., 15.));
-	let mut model2 = SolidObject::from_gltf(MODEL);
-	model2.scale(100.);
-	model2.move_to(Vec3f::new(-4.4, 0., 15.));
-	model2.material.metalic = 1.;
-	let mut ground = SolidObject::plane();
-	ground.scale(10000.);
-	ground.move_to(Vec3f::new(0., -4., 0.));
-	let mut light = Light::plane();
-	light.scale(8.);
-	light.move_to(Vec3f::new(0., -3.5, 15.));
-	let mut scene = Scene::new();
-	scene.add_object(Box::new(model1));
-	scene.add_object(Box::new(model2));
-	scene.add_object(Box::new(ground));
-	scene.add_object(Box::new(light));
-
-	println!("Allocating image");
-
-	let image = Arc::new(RwLock::new(Image::new(WIDTH, HEIGHT)));
-
-	let focus = Vec3f::new(0., 0., 20.);
-	let origin = Vec3f::new(0., 0., 0.);
-	let fov = PI / 2.;
-	let d = 1.;
-
-	let t = (focus - origin).unit();
-	let v = Vec3f::new(1., 0., 0.).cross(t);
-	let b = t.cross(v);
-
-	let gx = d * (fov / 2.).tan();
-	let gy = gx * aspect_ratio;
-
-	let qx = b * ((2. * gx) / (WIDTH as f32 - 1.));
-	let qy = v * ((2. * gy) / (HEIGHT as f32 - 1.));
-
-	let p0 = t * d - b * gx - v * gy;
-
 	println!("Creating window");
 
 	let running = Arc::new(AtomicBool::new(true));
+	let stopped = Arc::new(AtomicBool::new(false));
 
 	{
 		let image = image.clone();
 		let running = running.clone();
+		let stopped = stopped.clone();
 		thread::spawn(move || {
 			println!("Processing pixels");
-
-			let mut iter: Vec<(u32, u32)> = image.read().unwrap().coordinates().collect();
-			iter.shuffle(&mut rand::thread_rng());
-
-			iter.iter().par_bridge().into_par_iter().for_each(|(x, y)| {
-				let mut rng = rand::thread_rng();
-				let mut c = Colour::from_rgb(0., 0., 0.);
-
-				for _ in 0..SAMPLES_PER_PIXEL {
-					let r1: f32 = rng.gen_range(0.0..1.0);
-					let r2: f32 = rng.gen_range(0.0..1.0);
-					let p = p0 + qx * (*x as f32 + r1) + qy * (*y as f32 + r2);
-
-					let r = Ray::new(origin, p.unit());
-
-					c = c + scene.get_colour(&r, MAX_DEPTH) / SAMPLES_PER_PIXEL as f32;
-				}
-
-				image
-					.write()
-					.unwrap()
-					.set_pixel(*x, *y, (c).sqrt().clamp(0., 0.999));
-
-				window.request_redraw();
-			});
+			renderer.render(&image, &sample_map, &stopped, || window.request_redraw());
 
 			println!("Encoding image");
-			let data = image::formats::Bmp::encode(&image.read().unwrap()).unwrap();
-
-			let mut file = File::create("temp.bmp").unwrap();
-			file.write_all(&data).unwrap();
+			write_images(outputs(&image.read().unwrap(), &sample_map.read().unwrap()));
 
 			running.store(false, Ordering::Relaxed);
+			// Wake the event loop so it sees the render is done.
+			window.request_redraw();
 		});
 	}
 
-	event_loop.run(move |event, _, control_flow| {
-		if let Event::RedrawRequested(_) = event {
+	event_loop.run(move |event, _, control_flow| match event {
+		Event::WindowEvent {
+			event: WindowEvent::CloseRequested,
+			..
+		} => {
+			// Keep the loop running until the image is written.
+			println!("Stopping render");
+			stopped.store(true, Ordering::Relaxed);
+		}
+		Event::RedrawRequested(_) => {
 			pixels
 				.get_frame()
-				.copy_from_slice(&image.read().unwrap().into_u8());
+				.copy_from_slice(&image.read().unwrap().to_u8());
 			if pixels.render().is_err() {
 				*control_flow = ControlFlow::Exit;
 				return;
@@ -153,8 +243,8 @@ fn main() {
 
 			if !running.load(Ordering::Relaxed) {
 				*control_flow = ControlFlow::Exit;
-				return;
 			}
 		}
+		_ => {}
 	});
 }
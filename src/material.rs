@@ -1,56 +1,131 @@
+#[cfg(not(feature = "hemi_shading"))]
+use std::f32::consts::FRAC_1_PI;
+#[cfg(feature = "hemi_shading")]
+use std::f32::consts::PI;
+
+use std::sync::Arc;
+
 use crate::{
 	geometry::{Hit, Ray, Vec3f},
-	image::Colour,
+	image::{Colour, Texture},
+	sampler::Sampler,
 };
 
-pub type ReflectedRay = (Option<Ray>, Colour);
+pub struct ReflectedRay {
+	pub ray: Option<Ray>,
+	pub colour: Colour,
+	/// Set when the ray comes from a mirror-like lobe that light sampling can
+	/// never produce.
+	pub delta: bool,
+}
+
+impl ReflectedRay {
+	/// No light continues, for directions that would go into the surface.
+	fn absorbed() -> Self {
+		Self {
+			ray: None,
+			colour: Colour::new(),
+			delta: true,
+		}
+	}
+}
 
 #[derive(Debug, Clone)]
 pub struct Material {
 	pub albedo: Colour,
+	#[allow(dead_code)]
 	pub specular: f32,
 	pub metalic: f32,
+	#[allow(dead_code)]
 	pub roughness: f32,
 	pub emission: f32,
+	/// Image tinting `albedo`, looked up by the texture coordinates of the
+	/// hit.
+	pub texture: Option<Arc<Texture>>,
 }
 
 impl Material {
-	pub fn get_scattered(&self, ray: &Ray, hit: &Hit) -> ReflectedRay {
+	/// Albedo at the hit point, tinted by the texture and the vertex colour
+	/// if there are any.
+	pub fn albedo_at(&self, hit: &Hit) -> Colour {
+		let albedo = match &self.texture {
+			Some(texture) => self.albedo.clone() * texture.sample(hit.uv),
+			None => self.albedo.clone(),
+		};
+		match &hit.colour {
+			Some(colour) => albedo * colour.clone(),
+			None => albedo,
+		}
+	}
+
+	fn reflect(&self, ray: &Ray, hit: &Hit) -> ReflectedRay {
+		let reflected = ray.direction.unit().reflect(hit.normal);
+		let ray_out = Ray::new(hit.point, reflected).with_time(ray.time);
+		if ray_out.direction.dot(hit.geometric_normal) <= 0. {
+			return ReflectedRay::absorbed();
+		}
+
+		ReflectedRay {
+			ray: Some(ray_out),
+			colour: self.albedo_at(hit),
+			delta: true,
+		}
+	}
+
+	pub fn get_scattered(&self, ray: &Ray, hit: &Hit, sampler: &mut dyn Sampler) -> ReflectedRay {
 		// TODO: Mix different types of materials
 
+		// Always draw the same dimensions so later bounces line up across
+		// the samples of a pixel.
+		let u = sampler.get_2d();
+
 		if self.emission == 1. {
-			return (None, self.albedo.clone());
+			return ReflectedRay {
+				ray: None,
+				colour: self.albedo.clone(),
+				delta: true,
+			};
 		}
 
 		if self.metalic != 0. {
-			let reflected = ray.direction.unit().reflect(hit.polygon.normal);
-			let ray_out = Ray::new(hit.point, reflected);
-			return (
-				if ray_out.direction.dot(hit.polygon.normal) > 0. {
-					Some(ray_out)
-				} else {
-					None
-				},
-				self.albedo.clone(),
-			);
+			return self.reflect(ray, hit);
 		}
 
 		#[cfg(feature = "hemi_shading")]
 		let t = {
-			let r = Vec3f::rand_in_unit();
-			hit.point
-				+ if r.dot(hit.polygon.normal) > 0. {
-					r
-				} else {
-					r * -1.
-				}
+			let r = Vec3f::on_unit_sphere(u);
+			hit.point + if r.dot(hit.normal) > 0. { r } else { r * -1. }
 		};
 		#[cfg(not(feature = "hemi_shading"))]
-		let t = hit.point + hit.polygon.normal + Vec3f::rand_in_unit();
+		let t = hit.point + hit.normal + Vec3f::on_unit_sphere(u);
+		// Interpolated normals can tilt the lobe into the surface.
+		if (t - hit.point).dot(hit.geometric_normal) <= 0. {
+			return ReflectedRay::absorbed();
+		}
+
+		ReflectedRay {
+			ray: Some(Ray::new(hit.point, t - hit.point).with_time(ray.time)),
+			colour: self.albedo_at(hit),
+			delta: false,
+		}
+	}
 
-		(
-			Some(Ray::new(hit.point, t - hit.point)),
-			self.albedo.clone(),
-		)
+	/// Solid angle density with which `get_scattered` picks `direction` from
+	/// its diffuse lobe. Since scattered rays are weighted by `albedo` alone,
+	/// the lobe's BSDF times cosine is `albedo * scattering_pdf`.
+	pub fn scattering_pdf(&self, hit: &Hit, direction: Vec3f) -> f32 {
+		if self.emission == 1. || self.metalic != 0. {
+			return 0.;
+		}
+
+		let cos = hit.normal.dot(direction.unit());
+		if cos <= 0. || hit.geometric_normal.dot(direction) <= 0. {
+			return 0.;
+		}
+
+		#[cfg(feature = "hemi_shading")]
+		return 1. / (2. * PI);
+		#[cfg(not(feature = "hemi_shading"))]
+		return cos * FRAC_1_PI;
 	}
 }
@@ -0,0 +1,308 @@
+use std::sync::{
+	atomic::{AtomicBool, Ordering},
+	RwLock,
+};
+
+use rand::{prelude::SliceRandom, rngs::SmallRng, SeedableRng};
+use rayon::prelude::*;
+
+use crate::{
+	camera::{Camera, Exposure, Shutter},
+	geometry::Scene,
+	image::{Colour, Image},
+	sampler::{AdaptiveSampling, PixelStatistics, Sampler, SamplerKind},
+};
+
+/// Everything needed to turn a scene into a frame.
+pub struct Renderer {
+	pub scene: Scene,
+	/// Cameras each rendering a `width` by `height` tile of the frame at
+	/// their offset.
+	pub views: Vec<(Box<dyn Camera>, (u32, u32))>,
+	pub width: u32,
+	pub height: u32,
+	pub max_depth: usize,
+	pub shutter: Shutter,
+	pub exposure: Exposure,
+	pub sampler: SamplerKind,
+	pub seed: u64,
+	pub adaptive: AdaptiveSampling,
+	/// Renders the whole frame one sample per pixel at a time, so a preview
+	/// converges everywhere at once.
+	pub progressive: bool,
+}
+
+impl Renderer {
+	/// Renders into `image`, writing the fraction of the maximum samples
+	/// each pixel took into `sample_map`. Setting `stopped` ends the render
+	/// early, after the current pass in progressive mode. `on_update` is
+	/// called whenever pixels have changed.
+	pub fn render(
+		&self,
+		image: &RwLock<Image>,
+		sample_map: &RwLock<Image>,
+		stopped: &AtomicBool,
+		on_update: impl Fn() + Sync,
+	) {
+		self.sampler.prepare();
+
+		let mut pixels: Vec<(u32, u32)> = image.read().unwrap().coordinates().collect();
+		// The visiting order only spreads a preview over the window, the pixel
+		// values don't depend on it.
+		pixels.shuffle(&mut SmallRng::seed_from_u64(self.seed));
+
+		let write_pixel = |(x, y): (u32, u32), statistics: &PixelStatistics| {
+			image.write().unwrap().set_pixel(
+				x,
+				y,
+				self.exposure
+					.apply(statistics.mean())
+					.sqrt()
+					.clamp(0., 0.999),
+			);
+			let taken = statistics.count as f32 / self.adaptive.max_samples as f32;
+			sample_map
+				.write()
+				.unwrap()
+				.set_pixel(x, y, Colour::from_rgb(taken, taken, taken));
+		};
+
+		if self.progressive {
+			// Every pass adds one sample to each pixel still sampling, so the
+			// frame is a complete image after any of them.
+			let mut statistics = vec![PixelStatistics::new(); pixels.len()];
+			while !stopped.load(Ordering::Relaxed)
+				&& !statistics
+					.iter()
+					.all(|statistics| self.adaptive.done(statistics))
+			{
+				statistics
+					.par_iter_mut()
+					.zip(&pixels)
+					.for_each(|(statistics, pixel)| {
+						if self.adaptive.done(statistics) {
+							return;
+						}
+						let mut sampler = self.sampler.build(self.adaptive.max_samples, self.seed);
+						statistics.add(self.sample(*pixel, sampler.as_mut(), statistics.count));
+						write_pixel(*pixel, statistics);
+					});
+
+				on_update();
+			}
+		} else {
+			pixels.par_iter().for_each(|pixel| {
+				if stopped.load(Ordering::Relaxed) {
+					return;
+				}
+
+				let mut sampler = self.sampler.build(self.adaptive.max_samples, self.seed);
+				let mut statistics = PixelStatistics::new();
+				while !self.adaptive.done(&statistics) {
+					statistics.add(self.sample(*pixel, sampler.as_mut(), statistics.count));
+				}
+				write_pixel(*pixel, &statistics);
+
+				on_update();
+			});
+		}
+	}
+
+	/// One sample of a pixel, through whichever view covers it.
+	fn sample(&self, (x, y): (u32, u32), sampler: &mut dyn Sampler, index: u32) -> Colour {
+		let (camera, (ox, oy)) = self
+			.views
+			.iter()
+			.find(|(_, (ox, oy))| {
+				(*ox..ox + self.width).contains(&x) && (*oy..oy + self.height).contains(&y)
+			})
+			.unwrap();
+		let (vx, vy) = (x - ox, y - oy);
+
+		sampler.start_pixel_sample((x, y), index);
+		let (r1, r2) = sampler.get_2d();
+		let lens = sampler.get_2d();
+		let time = sampler.get_1d();
+		let r = camera.ray(
+			(
+				(vx as f32 + r1) / self.width as f32,
+				(vy as f32 + r2) / self.height as f32,
+			),
+			lens,
+		);
+
+		match r {
+			Some(r) => {
+				let r = r.with_time(self.shutter.sample(time));
+				self.scene.get_colour(&r, self.max_depth, sampler)
+			}
+			None => Colour::new(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		camera::{Fov, PerspectiveCamera},
+		geometry::{SolidObject, Vec3f, WithOrigin, WithScale},
+	};
+
+	const WIDTH: u32 = 8;
+	const HEIGHT: u32 = 6;
+
+	/// A ground plane under the default sky.
+	fn renderer(sampler: SamplerKind, seed: u64) -> Renderer {
+		let mut ground = SolidObject::plane();
+		ground.scale(10.);
+		ground.move_to(Vec3f::new(0., -1., 5.));
+		let mut scene = Scene::new();
+		scene.add_object(Box::new(ground));
+
+		let camera = PerspectiveCamera::new(
+			Vec3f::new(0., 0., 0.),
+			Vec3f::new(0., -0.2, 1.),
+			Vec3f::new(0., 1., 0.),
+			Fov::Horizontal(1.),
+			WIDTH as f32 / HEIGHT as f32,
+		);
+
+		Renderer {
+			scene,
+			views: vec![(Box::new(camera), (0, 0))],
+			width: WIDTH,
+			height: HEIGHT,
+			max_depth: 4,
+			shutter: Shutter {
+				open: 0.,
+				close: 0.,
+			},
+			exposure: Exposure::new(),
+			sampler,
+			seed,
+			adaptive: AdaptiveSampling {
+				threshold: 0.,
+				min_samples: 4,
+				max_samples: 4,
+			},
+			progressive: false,
+		}
+	}
+
+	/// The image and sample map, rendered with `threads` worker threads.
+	fn render(renderer: &Renderer, threads: usize) -> (Vec<[f32; 3]>, Vec<f32>) {
+		render_until(renderer, threads, false)
+	}
+
+	/// Like `render`, stopping at the first update if `stop` is set.
+	fn render_until(renderer: &Renderer, threads: usize, stop: bool) -> (Vec<[f32; 3]>, Vec<f32>) {
+		let image = RwLock::new(Image::new(WIDTH, HEIGHT));
+		let sample_map = RwLock::new(Image::new(WIDTH, HEIGHT));
+		let stopped = AtomicBool::new(false);
+		rayon::ThreadPoolBuilder::new()
+			.num_threads(threads)
+			.build()
+			.unwrap()
+			.install(|| {
+				renderer.render(&image, &sample_map, &stopped, || {
+					stopped.fetch_or(stop, Ordering::Relaxed);
+				})
+			});
+
+		let (image, sample_map) = (
+			image.into_inner().unwrap(),
+			sample_map.into_inner().unwrap(),
+		);
+		image
+			.coordinates()
+			.map(|(x, y)| {
+				let c = image.get_pixel(x, y);
+				([c.r, c.g, c.b], sample_map.get_pixel(x, y).r)
+			})
+			.unzip()
+	}
+
+	#[test]
+	fn seeded_renders_repeat_exactly() {
+		for sampler in [SamplerKind::Independent, SamplerKind::Sobol] {
+			let seeded = renderer(sampler, 7);
+			let (image, _) = render(&seeded, 1);
+			assert_eq!(render(&seeded, 4).0, image);
+
+			let (reseeded, _) = render(&renderer(sampler, 8), 4);
+			assert_ne!(reseeded, image);
+		}
+	}
+
+	#[test]
+	fn progressive_passes_add_up_to_the_same_image() {
+		let mut tiled = renderer(SamplerKind::Halton, 3);
+		tiled.adaptive = AdaptiveSampling {
+			threshold: 0.05,
+			min_samples: 2,
+			max_samples: 16,
+		};
+		let (image, sample_map) = render(&tiled, 4);
+		assert!(sample_map.iter().any(|taken| *taken < 1.));
+
+		let progressive = Renderer {
+			progressive: true,
+			..tiled
+		};
+		assert_eq!(render(&progressive, 4), (image, sample_map));
+	}
+
+	#[test]
+	fn stopped_progressive_renders_are_complete() {
+		let progressive = Renderer {
+			progressive: true,
+			..renderer(SamplerKind::Sobol, 1)
+		};
+		let (image, sample_map) = render_until(&progressive, 4, true);
+
+		// One pass gave every pixel its first sample.
+		assert!(sample_map.iter().all(|taken| *taken == 0.25));
+		assert!(image.iter().all(|c| c.iter().any(|v| *v > 0.)));
+	}
+
+	#[test]
+	fn stopping_before_the_start_leaves_the_image_empty() {
+		let renderer = renderer(SamplerKind::Independent, 1);
+		let image = RwLock::new(Image::new(WIDTH, HEIGHT));
+		let sample_map = RwLock::new(Image::new(WIDTH, HEIGHT));
+		renderer.render(&image, &sample_map, &AtomicBool::new(true), || {});
+
+		let image = image.into_inner().unwrap();
+		assert!(image
+			.coordinates()
+			.all(|(x, y)| image.get_pixel(x, y).r == 0.));
+	}
+
+	#[test]
+	fn each_view_fills_its_own_tile() {
+		// The ground on the left, the sky on the right.
+		let mut renderer = renderer(SamplerKind::Stratified, 1);
+		let sky = PerspectiveCamera::new(
+			Vec3f::new(0., 0., 0.),
+			Vec3f::new(0., 1., 0.2),
+			Vec3f::new(0., 1., 0.),
+			Fov::Horizontal(0.5),
+			WIDTH as f32 / HEIGHT as f32,
+		);
+		renderer.views.push((Box::new(sky), (WIDTH, 0)));
+
+		let image = RwLock::new(Image::new(2 * WIDTH, HEIGHT));
+		let sample_map = RwLock::new(Image::new(2 * WIDTH, HEIGHT));
+		renderer.render(&image, &sample_map, &AtomicBool::new(false), || {});
+
+		let image = image.into_inner().unwrap();
+		let (ground, sky) = (
+			image.get_pixel(WIDTH / 2, HEIGHT - 1),
+			image.get_pixel(WIDTH + WIDTH / 2, 0),
+		);
+		// Looking up, the gradient sky is blue, the ground lit by it grey.
+		assert!(sky.b > sky.r + 0.05);
+		assert!((ground.b - ground.r).abs() < sky.b - sky.r);
+	}
+}
@@ -0,0 +1,135 @@
+use crate::image::Colour;
+
+/// Keeps sampling a pixel past `min_samples` until the standard error of its
+/// luminance falls below `threshold` times the luminance, or `max_samples`
+/// is reached. The error needs at least two samples to be measured.
+#[derive(Debug, Clone, Copy)]
+pub struct AdaptiveSampling {
+	pub threshold: f32,
+	pub min_samples: u32,
+	pub max_samples: u32,
+}
+
+impl AdaptiveSampling {
+	pub fn done(&self, statistics: &PixelStatistics) -> bool {
+		statistics.count >= self.max_samples
+			|| (statistics.count >= self.min_samples
+				&& statistics.relative_error() <= self.threshold)
+	}
+}
+
+/// Running mean of a pixel's samples, with the variance of their luminance
+/// tracked by Welford's method.
+#[derive(Debug, Clone)]
+pub struct PixelStatistics {
+	pub count: u32,
+	mean: Colour,
+	luminance_mean: f32,
+	luminance_m2: f32,
+}
+
+impl PixelStatistics {
+	pub fn new() -> Self {
+		Self {
+			count: 0,
+			mean: Colour::new(),
+			luminance_mean: 0.,
+			luminance_m2: 0.,
+		}
+	}
+
+	pub fn add(&mut self, colour: Colour) {
+		self.count += 1;
+		let n = self.count as f32;
+
+		let luminance = colour.luminance();
+		let delta = luminance - self.luminance_mean;
+		self.luminance_mean += delta / n;
+		self.luminance_m2 += delta * (luminance - self.luminance_mean);
+
+		self.mean = self.mean.clone() + (colour - self.mean.clone()) / n;
+	}
+
+	pub fn mean(&self) -> Colour {
+		self.mean.clone()
+	}
+
+	/// Sample variance of the luminance.
+	pub fn variance(&self) -> f32 {
+		if self.count < 2 {
+			return 0.;
+		}
+		self.luminance_m2 / (self.count - 1) as f32
+	}
+
+	/// Standard error of the mean luminance relative to the mean itself,
+	/// infinite until there are two samples to measure it from.
+	pub fn relative_error(&self) -> f32 {
+		if self.count < 2 {
+			return f32::INFINITY;
+		}
+
+		let error = (self.variance() / self.count as f32).sqrt();
+		if error == 0. {
+			return 0.;
+		}
+		error / self.luminance_mean.max(f32::EPSILON)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn grey(v: f32) -> Colour {
+		Colour::from_rgb(v, v, v)
+	}
+
+	fn statistics(values: &[f32]) -> PixelStatistics {
+		let mut statistics = PixelStatistics::new();
+		for v in values {
+			statistics.add(grey(*v));
+		}
+		statistics
+	}
+
+	#[test]
+	fn tracks_mean_and_variance() {
+		let s = statistics(&[1., 2., 3., 4.]);
+		assert_eq!(s.count, 4);
+		assert!((s.mean().g - 2.5).abs() < 1e-5);
+		assert!((s.variance() - 5. / 3.).abs() < 1e-4);
+		let expected = (5f32 / 3. / 4.).sqrt() / 2.5;
+		assert!((s.relative_error() - expected).abs() < 1e-4);
+	}
+
+	#[test]
+	fn needs_two_samples_to_stop() {
+		let adaptive = AdaptiveSampling {
+			threshold: 0.1,
+			min_samples: 1,
+			max_samples: 64,
+		};
+
+		assert!(!adaptive.done(&statistics(&[])));
+		// A single sample, black or not, says nothing about the error.
+		assert!(!adaptive.done(&statistics(&[0.])));
+		assert!(!adaptive.done(&statistics(&[0.5])));
+		assert!(adaptive.done(&statistics(&[0.5, 0.5])));
+		assert!(adaptive.done(&statistics(&[0., 0.])));
+		assert!(!adaptive.done(&statistics(&[0., 1.])));
+	}
+
+	#[test]
+	fn respects_the_sample_bounds() {
+		let adaptive = AdaptiveSampling {
+			threshold: 0.1,
+			min_samples: 4,
+			max_samples: 6,
+		};
+
+		assert!(!adaptive.done(&statistics(&[0.5; 3])));
+		assert!(adaptive.done(&statistics(&[0.5; 4])));
+		assert!(adaptive.done(&statistics(&[0., 1., 0., 1., 0., 1.])));
+	}
+}
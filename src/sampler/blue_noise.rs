@@ -0,0 +1,199 @@
+use std::sync::OnceLock;
+
+use super::{hash, Sampler, ONE_MINUS_EPSILON};
+
+const TILE_SIZE: usize = 64;
+/// Width of the Gaussian the void-and-cluster energy is measured with.
+const SIGMA: f32 = 1.5;
+
+/// Short rank-1 sequences shifted per pixel by a blue-noise tile, for
+/// previews at a handful of samples per pixel. Since neighbouring pixels get
+/// very different shifts, the error ends up as high frequency noise rather
+/// than clumps. Every dimension reads the tile at its own offset.
+pub struct BlueNoiseSampler {
+	seed: u64,
+	pixel: (u32, u32),
+	index: u32,
+	dimension: u32,
+}
+
+impl BlueNoiseSampler {
+	pub fn new(seed: u64) -> Self {
+		Self {
+			seed,
+			pixel: (0, 0),
+			index: 0,
+			dimension: 0,
+		}
+	}
+
+	/// Builds the shared tile if it is not there yet, which takes a moment.
+	pub fn prepare() {
+		tile();
+	}
+
+	fn shift(&mut self) -> f32 {
+		let offset = hash(&[self.seed, self.dimension as u64]);
+		self.dimension += 1;
+
+		let x = (self.pixel.0 as usize + (offset & 0xffff) as usize) % TILE_SIZE;
+		let y = (self.pixel.1 as usize + (offset >> 16 & 0xffff) as usize) % TILE_SIZE;
+		tile()[x + y * TILE_SIZE]
+	}
+}
+
+impl Sampler for BlueNoiseSampler {
+	fn start_pixel_sample(&mut self, pixel: (u32, u32), index: u32) {
+		self.pixel = pixel;
+		self.index = index;
+		self.dimension = 0;
+	}
+
+	fn get_1d(&mut self) -> f32 {
+		// Golden ratio sequence.
+		let v = self.index as f32 * 0.618_034 + self.shift();
+		(v - v.floor()).min(ONE_MINUS_EPSILON)
+	}
+
+	fn get_2d(&mut self) -> (f32, f32) {
+		// The R2 sequence, from the plastic number.
+		let x = self.index as f32 * 0.754_877_7 + self.shift();
+		let y = self.index as f32 * 0.569_840_3 + self.shift();
+		(
+			(x - x.floor()).min(ONE_MINUS_EPSILON),
+			(y - y.floor()).min(ONE_MINUS_EPSILON),
+		)
+	}
+}
+
+/// Tile of values in `[0, 1)` whose thresholds all form blue-noise
+/// patterns, built once by Ulichney's void-and-cluster method.
+fn tile() -> &'static [f32] {
+	static TILE: OnceLock<Vec<f32>> = OnceLock::new();
+	TILE.get_or_init(void_and_cluster)
+}
+
+fn void_and_cluster() -> Vec<f32> {
+	const N: usize = TILE_SIZE * TILE_SIZE;
+
+	let kernel: Vec<f32> = (0..N)
+		.map(|i| {
+			let wrap = |d: usize| d.min(TILE_SIZE - d) as f32;
+			let (dx, dy) = (wrap(i % TILE_SIZE), wrap(i / TILE_SIZE));
+			(-(dx * dx + dy * dy) / (2. * SIGMA * SIGMA)).exp()
+		})
+		.collect();
+
+	let mut pattern = vec![false; N];
+	let mut energy = vec![0f32; N];
+	let toggle = |pattern: &mut [bool], energy: &mut [f32], p: usize| {
+		pattern[p] = !pattern[p];
+		let sign = if pattern[p] { 1. } else { -1. };
+		let (px, py) = (p % TILE_SIZE, p / TILE_SIZE);
+		for (q, e) in energy.iter_mut().enumerate() {
+			let dx = (q % TILE_SIZE + TILE_SIZE - px) % TILE_SIZE;
+			let dy = (q / TILE_SIZE + TILE_SIZE - py) % TILE_SIZE;
+			*e += sign * kernel[dx + dy * TILE_SIZE];
+		}
+	};
+	// Densest set pixel and emptiest unset pixel.
+	let cluster = |pattern: &[bool], energy: &[f32]| {
+		(0..N)
+			.filter(|p| pattern[*p])
+			.max_by(|a, b| energy[*a].total_cmp(&energy[*b]))
+			.unwrap()
+	};
+	let void = |pattern: &[bool], energy: &[f32]| {
+		(0..N)
+			.filter(|p| !pattern[*p])
+			.min_by(|a, b| energy[*a].total_cmp(&energy[*b]))
+			.unwrap()
+	};
+
+	// Spread an initial random pattern out by moving points from the
+	// tightest cluster to the largest void until that changes nothing, with
+	// a bound in case it ends up swapping between two equal layouts.
+	for p in 0..N {
+		if hash(&[p as u64]).is_multiple_of(10) {
+			toggle(&mut pattern, &mut energy, p);
+		}
+	}
+	for _ in 0..N {
+		let c = cluster(&pattern, &energy);
+		toggle(&mut pattern, &mut energy, c);
+		let v = void(&pattern, &energy);
+		toggle(&mut pattern, &mut energy, v);
+		if c == v {
+			break;
+		}
+	}
+
+	let ones = pattern.iter().filter(|p| **p).count();
+	let mut rank = vec![0; N];
+
+	// Rank the initial points by removing clusters first...
+	let (mut removed, mut removed_energy) = (pattern.clone(), energy.clone());
+	for r in (0..ones).rev() {
+		let c = cluster(&removed, &removed_energy);
+		toggle(&mut removed, &mut removed_energy, c);
+		rank[c] = r;
+	}
+	// ...then the rest by filling voids.
+	for r in ones..N {
+		let v = void(&pattern, &energy);
+		toggle(&mut pattern, &mut energy, v);
+		rank[v] = r;
+	}
+
+	rank.iter().map(|r| (*r as f32 + 0.5) / N as f32).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const N: usize = TILE_SIZE * TILE_SIZE;
+
+	#[test]
+	fn tile_ranks_every_pixel_once() {
+		let mut values = tile().to_vec();
+		values.sort_by(f32::total_cmp);
+		for (i, v) in values.into_iter().enumerate() {
+			assert_eq!(v, (i as f32 + 0.5) / N as f32);
+		}
+	}
+
+	#[test]
+	fn thresholds_spread_points_apart() {
+		// The darkest tenth has no two points side by side, where a random
+		// pattern of that density would have about eighty such pairs.
+		let set = |x: usize, y: usize| tile()[x % TILE_SIZE + y % TILE_SIZE * TILE_SIZE] < 0.1;
+		for y in 0..TILE_SIZE {
+			for x in 0..TILE_SIZE {
+				if set(x, y) {
+					assert!(!set(x + 1, y) && !set(x, y + 1), "({}, {})", x, y);
+				}
+			}
+		}
+
+		// Neighbours differ by more than the third random values would.
+		let difference = (0..N)
+			.map(|i| (tile()[i] - tile()[(i + 1) % N]).abs())
+			.sum::<f32>()
+			/ N as f32;
+		assert!(difference > 0.4, "{}", difference);
+	}
+
+	#[test]
+	fn neighbouring_pixels_get_different_shifts() {
+		let mut sampler = BlueNoiseSampler::new(1);
+		let mut first = |pixel| {
+			sampler.start_pixel_sample(pixel, 0);
+			sampler.get_2d()
+		};
+		let a = first((10, 10));
+		assert_ne!(a, first((11, 10)));
+		assert_ne!(a, first((10, 11)));
+		assert_eq!(a, first((10 + TILE_SIZE as u32, 10)));
+	}
+}
@@ -0,0 +1,147 @@
+use super::{hash, hash_float, mix_bits, permutation_element, Sampler, ONE_MINUS_EPSILON};
+
+/// Bases of the Halton dimensions, enough for paths of about fifty bounces.
+/// Dimensions past these fall back to hashed random numbers.
+const PRIMES: [u32; 256] = [
+	2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+	101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+	197, 199, 211, 223, 227, 229, 233, 239, 241, 251, 257, 263, 269, 271, 277, 281, 283, 293, 307,
+	311, 313, 317, 331, 337, 347, 349, 353, 359, 367, 373, 379, 383, 389, 397, 401, 409, 419, 421,
+	431, 433, 439, 443, 449, 457, 461, 463, 467, 479, 487, 491, 499, 503, 509, 521, 523, 541, 547,
+	557, 563, 569, 571, 577, 587, 593, 599, 601, 607, 613, 617, 619, 631, 641, 643, 647, 653, 659,
+	661, 673, 677, 683, 691, 701, 709, 719, 727, 733, 739, 743, 751, 757, 761, 769, 773, 787, 797,
+	809, 811, 821, 823, 827, 829, 839, 853, 857, 859, 863, 877, 881, 883, 887, 907, 911, 919, 929,
+	937, 941, 947, 953, 967, 971, 977, 983, 991, 997, 1009, 1013, 1019, 1021, 1031, 1033, 1039,
+	1049, 1051, 1061, 1063, 1069, 1087, 1091, 1093, 1097, 1103, 1109, 1117, 1123, 1129, 1151, 1153,
+	1163, 1171, 1181, 1187, 1193, 1201, 1213, 1217, 1223, 1229, 1231, 1237, 1249, 1259, 1277, 1279,
+	1283, 1289, 1291, 1297, 1301, 1303, 1307, 1319, 1321, 1327, 1361, 1367, 1373, 1381, 1399, 1409,
+	1423, 1427, 1429, 1433, 1439, 1447, 1451, 1453, 1459, 1471, 1481, 1483, 1487, 1489, 1493, 1499,
+	1511, 1523, 1531, 1543, 1549, 1553, 1559, 1567, 1571, 1579, 1583, 1597, 1601, 1607, 1609, 1613,
+	1619,
+];
+
+/// Halton sequence with each dimension a radical inverse in its own prime
+/// base. Digits are Owen scrambled with a seed per pixel and dimension,
+/// which breaks up the correlation between the higher bases and keeps
+/// neighbouring pixels from sharing the same pattern.
+pub struct HaltonSampler {
+	seed: u64,
+	pixel: (u32, u32),
+	index: u32,
+	dimension: usize,
+}
+
+impl HaltonSampler {
+	pub fn new(seed: u64) -> Self {
+		Self {
+			seed,
+			pixel: (0, 0),
+			index: 0,
+			dimension: 0,
+		}
+	}
+
+	fn sample(&mut self) -> f32 {
+		let dimension = self.dimension;
+		self.dimension += 1;
+
+		let seed = hash(&[
+			self.seed,
+			self.pixel.0 as u64,
+			self.pixel.1 as u64,
+			dimension as u64,
+		]);
+		match PRIMES.get(dimension) {
+			Some(base) => scrambled_radical_inverse(*base, self.index, seed),
+			None => hash_float(hash(&[seed, self.index as u64])),
+		}
+	}
+}
+
+impl Sampler for HaltonSampler {
+	fn start_pixel_sample(&mut self, pixel: (u32, u32), index: u32) {
+		self.pixel = pixel;
+		self.index = index;
+		self.dimension = 0;
+	}
+
+	fn get_1d(&mut self) -> f32 {
+		self.sample()
+	}
+
+	fn get_2d(&mut self) -> (f32, f32) {
+		(self.sample(), self.sample())
+	}
+}
+
+/// Mirrors the digits of `index` in `base` about the radix point, permuting
+/// each digit by a permutation that depends on `seed` and the digits before
+/// it.
+pub fn scrambled_radical_inverse(base: u32, mut index: u32, seed: u64) -> f32 {
+	let inv_base = 1. / base as f64;
+	let mut reversed = 0u64;
+	let mut scale = 1.;
+
+	// Keep going past the last digit of `index`, as the scrambled zeros still
+	// contribute until the digits drop below single precision.
+	while scale > f32::EPSILON as f64 / 2. {
+		let digit = index % base;
+		let permutation = mix_bits(seed ^ reversed) as u32;
+		reversed = reversed * base as u64 + permutation_element(digit, base, permutation) as u64;
+		scale *= inv_base;
+		index /= base;
+	}
+
+	((reversed as f64 * scale) as f32).min(ONE_MINUS_EPSILON)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn bases_are_ascending_primes() {
+		assert!(PRIMES.windows(2).all(|w| w[0] < w[1]));
+		for p in PRIMES {
+			assert!(
+				(2..p).take_while(|d| d * d <= p).all(|d| p % d != 0),
+				"{}",
+				p
+			);
+		}
+	}
+
+	#[test]
+	fn radical_inverse_strata() {
+		// Each run of `base^k` indices puts one point in every interval of
+		// width `base^-k`, scrambled or not.
+		for (base, count) in [(2, 64), (3, 27), (5, 25), (13, 169)] {
+			for seed in [0, 99] {
+				let mut seen = vec![false; count as usize];
+				for i in count..2 * count {
+					let u = scrambled_radical_inverse(base, i, seed);
+					let stratum = (u * count as f32) as usize;
+					assert!(!seen[stratum]);
+					seen[stratum] = true;
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn dimensions_use_their_own_base() {
+		// Eight samples fill the eighths of the base two dimension, nine the
+		// ninths of the base three one.
+		let mut sampler = HaltonSampler::new(3);
+		let mut eighths = [false; 8];
+		let mut ninths = [false; 9];
+		for i in 0..9 {
+			sampler.start_pixel_sample((2, 4), i);
+			let (u, v) = sampler.get_2d();
+			if i < 8 {
+				assert!(!std::mem::replace(&mut eighths[(u * 8.) as usize], true));
+			}
+			assert!(!std::mem::replace(&mut ninths[(v * 9.) as usize], true));
+		}
+	}
+}
@@ -0,0 +1,115 @@
+/// 64-bit finaliser from MurmurHash3, spreading every input bit over the
+/// output.
+pub fn mix_bits(mut v: u64) -> u64 {
+	v ^= v >> 33;
+	v = v.wrapping_mul(0xff51afd7ed558ccd);
+	v ^= v >> 33;
+	v = v.wrapping_mul(0xc4ceb9fe1a85ec53);
+	v ^= v >> 33;
+	v
+}
+
+pub fn hash(values: &[u64]) -> u64 {
+	values
+		.iter()
+		.fold(0x9e3779b97f4a7c15, |h, v| mix_bits(h ^ mix_bits(*v)))
+}
+
+/// Element `i` of a pseudo-random permutation of `0..n` selected by `seed`,
+/// after Kensler's "Correlated Multi-Jittered Sampling".
+pub fn permutation_element(mut i: u32, n: u32, seed: u32) -> u32 {
+	let mut w = n.wrapping_sub(1);
+	w |= w >> 1;
+	w |= w >> 2;
+	w |= w >> 4;
+	w |= w >> 8;
+	w |= w >> 16;
+
+	loop {
+		i ^= seed;
+		i = i.wrapping_mul(0xe170893d);
+		i ^= seed >> 16;
+		i ^= (i & w) >> 4;
+		i ^= seed >> 8;
+		i = i.wrapping_mul(0x0929eb3f);
+		i ^= seed >> 23;
+		i ^= (i & w) >> 1;
+		i = i.wrapping_mul(1 | seed >> 27);
+		i = i.wrapping_mul(0x6935fa69);
+		i ^= (i & w) >> 11;
+		i = i.wrapping_mul(0x74dcb303);
+		i ^= (i & w) >> 2;
+		i = i.wrapping_mul(0x9e501cc3);
+		i ^= (i & w) >> 2;
+		i = i.wrapping_mul(0xc860a3df);
+		i &= w;
+		i ^= i >> 5;
+		if i < n {
+			break;
+		}
+	}
+
+	(i.wrapping_add(seed)) % n
+}
+
+/// Uniform number in `[0, 1)` from the top bits of a hash.
+pub fn hash_float(h: u64) -> f32 {
+	(h >> 40) as f32 / (1u64 << 24) as f32
+}
+
+/// Nested uniform scramble of the bits of a fixed point number in `[0, 1)`,
+/// the hash based approximation by Laine and Karras.
+pub fn owen_scramble(mut v: u32, seed: u32) -> u32 {
+	v = v.reverse_bits();
+	v ^= v.wrapping_mul(0x3d20adea);
+	v = v.wrapping_add(seed);
+	v = v.wrapping_mul((seed >> 16) | 1);
+	v ^= v.wrapping_mul(0x05526c56);
+	v ^= v.wrapping_mul(0x53a22864);
+	v.reverse_bits()
+}
+
+pub fn fixed_to_float(v: u32) -> f32 {
+	(v as f32 * 2f32.powi(-32)).min(super::ONE_MINUS_EPSILON)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn permutation_elements_form_permutations() {
+		for n in [1, 2, 7, 16, 100] {
+			for seed in [0, 1, 0xdeadbeef] {
+				let mut seen = vec![false; n as usize];
+				for i in 0..n {
+					let p = permutation_element(i, n, seed);
+					assert!(!seen[p as usize]);
+					seen[p as usize] = true;
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn owen_scrambling_keeps_strata() {
+		// Sixteen values in distinct sixteenths stay in distinct sixteenths.
+		for seed in [0, 7, 0x12345678] {
+			let mut seen = [false; 16];
+			for k in 0..16u32 {
+				let top = owen_scramble(k << 28 | 0x0123456, seed) >> 28;
+				assert!(!seen[top as usize]);
+				seen[top as usize] = true;
+			}
+		}
+	}
+
+	#[test]
+	fn floats_stay_below_one() {
+		assert_eq!(hash_float(0), 0.);
+		assert!(hash_float(u64::MAX) < 1.);
+		assert!(fixed_to_float(u32::MAX) < 1.);
+		assert_eq!(fixed_to_float(1 << 31), 0.5);
+		assert_ne!(hash(&[1, 2]), hash(&[2, 1]));
+	}
+}
@@ -0,0 +1,45 @@
+use super::{hash, hash_float, Sampler};
+
+/// Uniform numbers with no correlation between samples, each hashed from
+/// the seed, pixel, sample index and dimension.
+pub struct IndependentSampler {
+	seed: u64,
+	pixel: (u32, u32),
+	index: u32,
+	dimension: u32,
+}
+
+impl IndependentSampler {
+	pub fn new(seed: u64) -> Self {
+		Self {
+			seed,
+			pixel: (0, 0),
+			index: 0,
+			dimension: 0,
+		}
+	}
+}
+
+impl Sampler for IndependentSampler {
+	fn start_pixel_sample(&mut self, pixel: (u32, u32), index: u32) {
+		self.pixel = pixel;
+		self.index = index;
+		self.dimension = 0;
+	}
+
+	fn get_1d(&mut self) -> f32 {
+		let u = hash_float(hash(&[
+			self.seed,
+			self.pixel.0 as u64,
+			self.pixel.1 as u64,
+			self.index as u64,
+			self.dimension as u64,
+		]));
+		self.dimension += 1;
+		u
+	}
+
+	fn get_2d(&mut self) -> (f32, f32) {
+		(self.get_1d(), self.get_1d())
+	}
+}
@@ -0,0 +1,184 @@
+use std::str::FromStr;
+
+mod hash;
+pub use hash::*;
+
+mod independent;
+pub use independent::*;
+
+mod stratified;
+pub use stratified::*;
+
+mod halton;
+pub use halton::*;
+
+mod sobol;
+pub use sobol::*;
+
+mod blue_noise;
+pub use blue_noise::*;
+
+mod adaptive;
+pub use adaptive::*;
+
+/// Source of sample values for the dimensions of a path. Each call hands out
+/// the next dimension of the current pixel sample, and the values depend only
+/// on the seed, pixel, sample index and dimension, so renders are
+/// reproducible whatever order pixels are visited in.
+pub trait Sampler: Send {
+	/// Restarts at the first dimension of sample `index` of `pixel`.
+	fn start_pixel_sample(&mut self, pixel: (u32, u32), index: u32);
+
+	fn get_1d(&mut self) -> f32;
+
+	fn get_2d(&mut self) -> (f32, f32);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SamplerKind {
+	Independent,
+	Stratified,
+	Halton,
+	Sobol,
+	BlueNoise,
+}
+
+impl FromStr for SamplerKind {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"independent" => Ok(SamplerKind::Independent),
+			"stratified" => Ok(SamplerKind::Stratified),
+			"halton" => Ok(SamplerKind::Halton),
+			"sobol" => Ok(SamplerKind::Sobol),
+			"blue-noise" => Ok(SamplerKind::BlueNoise),
+			_ => Err(format!("Unknown sampler '{}'", s)),
+		}
+	}
+}
+
+impl SamplerKind {
+	/// Builds what the samplers of this kind share ahead of time, rather
+	/// than holding up the first pixels of a render.
+	pub fn prepare(self) {
+		if self == SamplerKind::BlueNoise {
+			BlueNoiseSampler::prepare();
+		}
+	}
+
+	pub fn build(self, samples_per_pixel: u32, seed: u64) -> Box<dyn Sampler> {
+		match self {
+			SamplerKind::Independent => Box::new(IndependentSampler::new(seed)),
+			SamplerKind::Stratified => Box::new(StratifiedSampler::new(samples_per_pixel, seed)),
+			SamplerKind::Halton => Box::new(HaltonSampler::new(seed)),
+			SamplerKind::Sobol => Box::new(SobolSampler::new(samples_per_pixel, seed)),
+			SamplerKind::BlueNoise => Box::new(BlueNoiseSampler::new(seed)),
+		}
+	}
+}
+
+/// Largest value below one, so scaled samples stay inside their interval.
+pub const ONE_MINUS_EPSILON: f32 = 1. - f32::EPSILON / 2.;
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const KINDS: [SamplerKind; 4] = [
+		SamplerKind::Independent,
+		SamplerKind::Stratified,
+		SamplerKind::Halton,
+		SamplerKind::Sobol,
+	];
+
+	/// First 2D dimension of `count` samples of one pixel.
+	fn points(kind: SamplerKind, count: u32) -> Vec<(f32, f32)> {
+		let mut sampler = kind.build(count, 0);
+		(0..count)
+			.map(|i| {
+				sampler.start_pixel_sample((7, 3), i);
+				sampler.get_2d()
+			})
+			.collect()
+	}
+
+	/// Largest difference between the fraction of points in a box anchored
+	/// at the origin and the box's area, over a grid of boxes.
+	fn discrepancy(points: &[(f32, f32)]) -> f32 {
+		let mut worst = 0f32;
+		for i in 1..=32 {
+			for j in 1..=32 {
+				let (a, b) = (i as f32 / 32., j as f32 / 32.);
+				let inside = points.iter().filter(|(x, y)| *x < a && *y < b).count();
+				worst = worst.max((inside as f32 / points.len() as f32 - a * b).abs());
+			}
+		}
+		worst
+	}
+
+	#[test]
+	fn samples_stay_in_the_unit_interval() {
+		for kind in KINDS {
+			let mut sampler = kind.build(16, 9);
+			for i in 0..32 {
+				sampler.start_pixel_sample((i, 2 * i), i);
+				for _ in 0..100 {
+					let (u, v) = sampler.get_2d();
+					let w = sampler.get_1d();
+					assert!(
+						[u, v, w].iter().all(|x| (0. ..1.).contains(x)),
+						"{:?}",
+						kind
+					);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn low_discrepancy_samplers_beat_random_numbers() {
+		let random = discrepancy(&points(SamplerKind::Independent, 256));
+		for kind in [
+			SamplerKind::Stratified,
+			SamplerKind::Halton,
+			SamplerKind::Sobol,
+		] {
+			let d = discrepancy(&points(kind, 256));
+			assert!(
+				d < 0.03 && d < random / 2.,
+				"{:?}: {} against {}",
+				kind,
+				d,
+				random
+			);
+		}
+	}
+
+	#[test]
+	fn parses_names() {
+		assert_eq!("sobol".parse(), Ok(SamplerKind::Sobol));
+		assert!("random".parse::<SamplerKind>().is_err());
+	}
+
+	fn pixel_values(sampler: &mut dyn Sampler, pixel: (u32, u32), index: u32) -> Vec<f32> {
+		sampler.start_pixel_sample(pixel, index);
+		(0..8).map(|_| sampler.get_1d()).collect()
+	}
+
+	#[test]
+	fn values_depend_only_on_seed_pixel_and_index() {
+		for kind in KINDS {
+			let mut sampler = kind.build(4, 42);
+			let first = pixel_values(sampler.as_mut(), (5, 6), 2);
+			// Visiting other pixels and samples in between changes nothing.
+			pixel_values(sampler.as_mut(), (0, 0), 3);
+			pixel_values(sampler.as_mut(), (5, 6), 1);
+			assert_eq!(pixel_values(sampler.as_mut(), (5, 6), 2), first);
+			assert_eq!(pixel_values(kind.build(4, 42).as_mut(), (5, 6), 2), first);
+
+			let reseeded = pixel_values(kind.build(4, 43).as_mut(), (5, 6), 2);
+			assert_ne!(reseeded, first, "{:?}", kind);
+		}
+	}
+}
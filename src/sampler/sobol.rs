@@ -0,0 +1,126 @@
+use super::{fixed_to_float, hash, owen_scramble, permutation_element, Sampler};
+
+/// Owen-scrambled Sobol points. Every 1D or 2D dimension draws from the
+/// first two Sobol dimensions, with the sample order shuffled and the bits
+/// scrambled by a seed of its own, which keeps the stratification of each
+/// pair without needing tables for high dimensions.
+pub struct SobolSampler {
+	seed: u64,
+	samples_per_pixel: u32,
+	pixel: (u32, u32),
+	index: u32,
+	dimension: u32,
+}
+
+impl SobolSampler {
+	pub fn new(samples_per_pixel: u32, seed: u64) -> Self {
+		Self {
+			seed,
+			samples_per_pixel: samples_per_pixel.max(1),
+			pixel: (0, 0),
+			index: 0,
+			dimension: 0,
+		}
+	}
+
+	fn seed(&mut self) -> u64 {
+		let seed = hash(&[
+			self.seed,
+			self.pixel.0 as u64,
+			self.pixel.1 as u64,
+			self.dimension as u64,
+		]);
+		self.dimension += 1;
+		seed
+	}
+
+	/// Index into the sequence for the current dimension, a shuffle of the
+	/// pixel's sample indices.
+	fn shuffled_index(&self, seed: u64) -> u32 {
+		if self.index < self.samples_per_pixel {
+			permutation_element(self.index, self.samples_per_pixel, seed as u32)
+		} else {
+			self.index
+		}
+	}
+}
+
+impl Sampler for SobolSampler {
+	fn start_pixel_sample(&mut self, pixel: (u32, u32), index: u32) {
+		self.pixel = pixel;
+		self.index = index;
+		self.dimension = 0;
+	}
+
+	fn get_1d(&mut self) -> f32 {
+		let seed = self.seed();
+		let index = self.shuffled_index(seed);
+
+		fixed_to_float(owen_scramble(index.reverse_bits(), (seed >> 32) as u32))
+	}
+
+	fn get_2d(&mut self) -> (f32, f32) {
+		let seed = self.seed();
+		let index = self.shuffled_index(seed);
+		let (x, y) = sobol_2d(index);
+
+		(
+			fixed_to_float(owen_scramble(x, (seed >> 32) as u32)),
+			fixed_to_float(owen_scramble(y, seed as u32 ^ 0x5bd1e995)),
+		)
+	}
+}
+
+/// The first two dimensions of the Sobol sequence as 32-bit fixed point.
+/// The first is the base two radical inverse; the second is generated by
+/// the upper triangular Pascal matrix mod 2.
+fn sobol_2d(mut index: u32) -> (u32, u32) {
+	let x = index.reverse_bits();
+	let mut y = 0;
+	let mut direction = 1u32 << 31;
+
+	while index > 0 {
+		if index & 1 == 1 {
+			y ^= direction;
+		}
+		index >>= 1;
+		direction ^= direction >> 1;
+	}
+
+	(x, y)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn first_points_of_the_sequence() {
+		let points: Vec<(f32, f32)> = (0..4)
+			.map(sobol_2d)
+			.map(|(x, y)| (fixed_to_float(x), fixed_to_float(y)))
+			.collect();
+		assert_eq!(points, [(0., 0.), (0.5, 0.5), (0.25, 0.75), (0.75, 0.25)]);
+	}
+
+	#[test]
+	fn pixel_samples_fill_every_elementary_interval() {
+		let mut sampler = SobolSampler::new(16, 5);
+		let points: Vec<(f32, f32)> = (0..16)
+			.map(|i| {
+				sampler.start_pixel_sample((1, 2), i);
+				sampler.get_1d();
+				sampler.get_2d()
+			})
+			.collect();
+
+		for (columns, rows) in [(16, 1), (8, 2), (4, 4), (2, 8), (1, 16)] {
+			let mut seen = [false; 16];
+			for (x, y) in &points {
+				let cell = (y * rows as f32) as usize * columns + (x * columns as f32) as usize;
+				assert!(!seen[cell], "{} by {}", columns, rows);
+				seen[cell] = true;
+			}
+		}
+	}
+}
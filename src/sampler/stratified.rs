@@ -0,0 +1,117 @@
+use super::{hash, hash_float, mix_bits, permutation_element, Sampler, ONE_MINUS_EPSILON};
+
+/// Jittered samples, one per stratum of each dimension. Strata are visited
+/// in a different order per pixel and dimension so dimensions stay
+/// uncorrelated.
+pub struct StratifiedSampler {
+	seed: u64,
+	samples_per_pixel: u32,
+	/// Strata along x and y of 2D dimensions, their product being the sample
+	/// count.
+	x_strata: u32,
+	y_strata: u32,
+	pixel: (u32, u32),
+	index: u32,
+	dimension: u32,
+}
+
+impl StratifiedSampler {
+	pub fn new(samples_per_pixel: u32, seed: u64) -> Self {
+		let samples_per_pixel = samples_per_pixel.max(1);
+		let x_strata = (1..=(samples_per_pixel as f32).sqrt() as u32)
+			.rev()
+			.find(|x| samples_per_pixel.is_multiple_of(*x))
+			.unwrap_or(1);
+
+		Self {
+			seed,
+			samples_per_pixel,
+			x_strata,
+			y_strata: samples_per_pixel / x_strata,
+			pixel: (0, 0),
+			index: 0,
+			dimension: 0,
+		}
+	}
+
+	/// Stratum of the current sample in the next dimension, and a hash to
+	/// jitter within it.
+	fn stratum(&mut self) -> (u32, u64) {
+		let seed = hash(&[
+			self.seed,
+			self.pixel.0 as u64,
+			self.pixel.1 as u64,
+			self.dimension as u64,
+		]);
+		self.dimension += 1;
+
+		let stratum = permutation_element(
+			self.index % self.samples_per_pixel,
+			self.samples_per_pixel,
+			seed as u32,
+		);
+		(stratum, hash(&[seed, self.index as u64]))
+	}
+}
+
+impl Sampler for StratifiedSampler {
+	fn start_pixel_sample(&mut self, pixel: (u32, u32), index: u32) {
+		self.pixel = pixel;
+		self.index = index;
+		self.dimension = 0;
+	}
+
+	fn get_1d(&mut self) -> f32 {
+		let (stratum, jitter) = self.stratum();
+		let jitter = hash_float(jitter);
+
+		((stratum as f32 + jitter) / self.samples_per_pixel as f32).min(ONE_MINUS_EPSILON)
+	}
+
+	fn get_2d(&mut self) -> (f32, f32) {
+		let (stratum, jitter) = self.stratum();
+		self.dimension += 1;
+		let (x, y) = (stratum % self.x_strata, stratum / self.x_strata);
+
+		(
+			((x as f32 + hash_float(jitter)) / self.x_strata as f32).min(ONE_MINUS_EPSILON),
+			((y as f32 + hash_float(mix_bits(jitter))) / self.y_strata as f32)
+				.min(ONE_MINUS_EPSILON),
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn samples(spp: u32, pixel: (u32, u32)) -> Vec<(f32, (f32, f32))> {
+		let mut sampler = StratifiedSampler::new(spp, 1);
+		(0..spp)
+			.map(|i| {
+				sampler.start_pixel_sample(pixel, i);
+				(sampler.get_1d(), sampler.get_2d())
+			})
+			.collect()
+	}
+
+	#[test]
+	fn one_sample_per_stratum() {
+		for (spp, x_strata, y_strata) in [(16, 4, 4), (8, 2, 4), (7, 1, 7)] {
+			let mut strata_1d = vec![0; spp as usize];
+			let mut strata_2d = vec![0; spp as usize];
+			for (u, (x, y)) in samples(spp, (3, 5)) {
+				strata_1d[(u * spp as f32) as usize] += 1;
+				let (x, y) = ((x * x_strata as f32) as u32, (y * y_strata as f32) as u32);
+				strata_2d[(y * x_strata + x) as usize] += 1;
+			}
+			assert!(strata_1d.iter().all(|n| *n == 1));
+			assert!(strata_2d.iter().all(|n| *n == 1));
+		}
+	}
+
+	#[test]
+	fn pixels_visit_strata_in_different_orders() {
+		assert_ne!(samples(16, (0, 0)), samples(16, (1, 0)));
+	}
+}
@@ -0,0 +1,36 @@
+use std::fmt::Debug;
+
+use crate::geometry::Vec3f;
+
+/// Values the tests compare up to rounding.
+pub trait Close: Copy + Debug {
+	fn distance(self, other: Self) -> f32;
+	fn magnitude(self) -> f32;
+}
+
+impl Close for f32 {
+	fn distance(self, other: Self) -> f32 {
+		(self - other).abs()
+	}
+
+	fn magnitude(self) -> f32 {
+		self.abs()
+	}
+}
+
+impl Close for Vec3f {
+	fn distance(self, other: Self) -> f32 {
+		(self - other).len()
+	}
+
+	fn magnitude(self) -> f32 {
+		self.len()
+	}
+}
+
+/// Asserts that `a` is `b` up to rounding, relative to `b` when it is larger
+/// than one.
+pub fn assert_close<T: Close>(a: T, b: T) {
+	let tolerance = 5e-6 * b.magnitude().max(1.);
+	assert!(a.distance(b) <= tolerance, "{:?} != {:?}", a, b);
+}